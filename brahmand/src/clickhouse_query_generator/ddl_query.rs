@@ -1,11 +1,12 @@
 use crate::{
     graph_catalog::graph_schema::{
-        Direction, GraphSchema, GraphSchemaElement, IndexType, NodeIdSchema, NodeSchema,
-        RelationshipIndexSchema, RelationshipSchema,
+        Direction, GraphSchema, GraphSchemaElement, IndexType, MergeTreeEngine, NodeIdSchema,
+        NodeSchema, PropertyIndexSchema, RelationshipIndexSchema, RelationshipSchema,
+        TableEngineSchema,
     },
     open_cypher_parser::ast::{
-        ColumnSchema, CreateNodeTableClause, CreateRelTableClause, Expression, Literal,
-        OpenCypherQueryAst,
+        AdjIndexAction, AdjIndexClause, ColumnSchema, CreateIndexClause, CreateNodeTableClause,
+        CreateRelTableClause, Expression, Literal, OpenCypherQueryAst, Operator,
     },
 };
 
@@ -33,6 +34,202 @@ fn get_default_value(
     }
 }
 
+// Renders an expression of the table properties, e.g. `cityHash64(id)` or `index_granularity = 8192`.
+fn get_expression_sql(expr: &Expression) -> Result<String, ClickhouseQueryGeneratorError> {
+    match expr {
+        Expression::Variable(var) => Ok(var.to_string()),
+        Expression::Literal(literal) => Ok(get_literal_to_string(literal)),
+        Expression::List(items) => Ok(format!("({})", get_expressions_sql(items)?)),
+        Expression::FunctionCallExp(function_call) => Ok(format!(
+            "{}({})",
+            function_call.name,
+            get_expressions_sql(&function_call.args)?
+        )),
+        Expression::OperatorApplicationExp(operator_application) => {
+            let operator: String = operator_application.operator.into();
+            let operands = operator_application
+                .operands
+                .iter()
+                .map(get_expression_sql)
+                .collect::<Result<Vec<String>, ClickhouseQueryGeneratorError>>()?;
+            match operands.as_slice() {
+                [operand] if matches!(operator_application.operator, Operator::Not) => {
+                    Ok(format!("{operator} {operand}"))
+                }
+                [operand] => Ok(format!("{operand} {operator}")),
+                _ => Ok(operands.join(&format!(" {operator} "))),
+            }
+        }
+        _ => Err(ClickhouseQueryGeneratorError::UnsupportedTablePropertyExpression),
+    }
+}
+
+fn get_expressions_sql(exprs: &[Expression]) -> Result<String, ClickhouseQueryGeneratorError> {
+    let exprs = exprs
+        .iter()
+        .map(get_expression_sql)
+        .collect::<Result<Vec<String>, ClickhouseQueryGeneratorError>>()?;
+    Ok(exprs.join(", "))
+}
+
+fn get_column_definition(
+    column_schema: &ColumnSchema,
+) -> Result<String, ClickhouseQueryGeneratorError> {
+    let column_name = column_schema.column_name;
+    let column_type = column_schema.column_dtype;
+    let mut column_definition = format!("{column_name} {column_type}");
+    if let Some(default_value) = &column_schema.default_value {
+        let default_val = get_default_value(default_value)?;
+        column_definition.push_str(&format!(" DEFAULT {default_val}"));
+    }
+    if let Some(codec) = &column_schema.codec {
+        column_definition.push_str(&format!(" CODEC({})", get_expressions_sql(codec)?));
+    }
+    Ok(column_definition)
+}
+
+// `ENGINE (MergeTree)` or `ENGINE (ReplacingMergeTree(version))`, the version being a column of
+// the table.
+fn get_merge_tree_engine(
+    expr: &Expression,
+    columns: &[ColumnSchema],
+) -> Result<MergeTreeEngine, ClickhouseQueryGeneratorError> {
+    let (name, args) = match expr {
+        Expression::Variable(name) => (*name, &[][..]),
+        Expression::FunctionCallExp(function_call) => {
+            (function_call.name.as_str(), function_call.args.as_slice())
+        }
+        _ => return Err(ClickhouseQueryGeneratorError::UnsupportedEngine),
+    };
+
+    match (name.to_lowercase().as_str(), args) {
+        ("mergetree", []) => Ok(MergeTreeEngine::MergeTree),
+        ("replacingmergetree", []) => Ok(MergeTreeEngine::ReplacingMergeTree { version: None }),
+        ("replacingmergetree", [Expression::Variable(version)])
+            if columns.iter().any(|column| column.column_name == *version) =>
+        {
+            Ok(MergeTreeEngine::ReplacingMergeTree {
+                version: Some(version.to_string()),
+            })
+        }
+        _ => Err(ClickhouseQueryGeneratorError::UnsupportedEngine),
+    }
+}
+
+// ON CLUSTER of the DDL, the column codecs and the storage related table properties:
+// REPLICATED (true), DISTRIBUTED (sharding key), ENGINE (..), PARTITION BY (..), ORDER BY (..),
+// TTL (..) and SETTINGS (name = value, ..).
+fn get_table_engine(
+    on_cluster: Option<&str>,
+    properties: &[Expression],
+    columns: &[ColumnSchema],
+) -> Result<TableEngineSchema, ClickhouseQueryGeneratorError> {
+    let mut engine = TableEngineSchema {
+        cluster: on_cluster.map(|cluster| cluster.to_string()),
+        ..TableEngineSchema::default()
+    };
+
+    for prop in properties.iter() {
+        if let Expression::FunctionCallExp(function_call) = prop {
+            let args = &function_call.args;
+            match function_call.name.to_lowercase().as_str() {
+                "replicated" => {
+                    if let Some(Expression::Literal(Literal::Boolean(val))) = args.first() {
+                        engine.replicated = *val;
+                    }
+                }
+                "distributed" => {
+                    let sharding_key = args
+                        .first()
+                        .ok_or(ClickhouseQueryGeneratorError::UnsupportedShardingKey)?;
+                    engine.sharding_key = Some(get_expression_sql(sharding_key)?);
+                }
+                "engine" => {
+                    let merge_tree = args
+                        .first()
+                        .ok_or(ClickhouseQueryGeneratorError::UnsupportedEngine)?;
+                    engine.merge_tree = get_merge_tree_engine(merge_tree, columns)?;
+                }
+                "partition by" => engine.partition_by = Some(get_expressions_sql(args)?),
+                "order by" => engine.order_by = Some(get_expressions_sql(args)?),
+                "ttl" => engine.ttl = Some(get_expressions_sql(args)?),
+                "settings" => {
+                    engine.settings =
+                        args.iter()
+                            .map(get_expression_sql)
+                            .collect::<Result<Vec<String>, ClickhouseQueryGeneratorError>>()?;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for column in columns.iter() {
+        if let Some(codec) = &column.codec {
+            engine
+                .column_codecs
+                .insert(column.column_name.to_string(), get_expressions_sql(codec)?);
+        }
+    }
+
+    if engine.is_distributed() && engine.cluster.is_none() {
+        return Err(ClickhouseQueryGeneratorError::MissingClusterForDistributedTable);
+    }
+
+    Ok(engine)
+}
+
+// Everything after the ENGINE of a node or rel table. ClickHouse requires the primary key to be
+// a prefix of the sorting key, which defaults to the primary key.
+fn get_storage_clauses(
+    engine: &TableEngineSchema,
+    primary_keys: &str,
+) -> Result<String, ClickhouseQueryGeneratorError> {
+    let mut clauses = vec![];
+    if let Some(order_by) = &engine.order_by {
+        if order_by != primary_keys && !order_by.starts_with(&format!("{primary_keys}, ")) {
+            return Err(ClickhouseQueryGeneratorError::PrimaryKeyNotPrefixOfOrderBy);
+        }
+        clauses.push(format!("ORDER BY ({order_by})"));
+    }
+    if let Some(partition_by) = &engine.partition_by {
+        clauses.push(format!("PARTITION BY {partition_by}"));
+    }
+    clauses.push(format!("PRIMARY KEY ({primary_keys})"));
+    if let Some(ttl) = &engine.ttl {
+        clauses.push(format!("TTL {ttl}"));
+    }
+    if !engine.settings.is_empty() {
+        clauses.push(format!("SETTINGS {}", engine.settings.join(", ")));
+    }
+    Ok(clauses.join(" "))
+}
+
+// CREATE TABLE of `table_name`, `definition` being the columns and `key` its PRIMARY KEY or ORDER BY.
+// A distributed table keeps its rows in `{table_name}_local` on every shard and `table_name`
+// becomes the Distributed table over them, so reads and inserts use `table_name` either way.
+fn get_create_table_queries(
+    table_name: &str,
+    definition: &str,
+    merge_tree: &str,
+    key: &str,
+    engine: &TableEngineSchema,
+) -> Vec<String> {
+    let on_cluster = engine.on_cluster();
+    let local_table_name = engine.local_table_name(table_name);
+    let merge_tree = engine.merge_tree_engine(merge_tree);
+    let mut queries = vec![format!(
+        "CREATE TABLE {local_table_name}{on_cluster} {definition} ENGINE = {merge_tree} {key};"
+    )];
+
+    if let (Some(cluster), Some(sharding_key)) = (&engine.cluster, &engine.sharding_key) {
+        queries.push(format!(
+            "CREATE TABLE {table_name}{on_cluster} AS {local_table_name} ENGINE = Distributed({cluster}, currentDatabase(), {local_table_name}, {sharding_key});"
+        ));
+    }
+    queries
+}
+
 #[derive(Debug, Clone)]
 pub struct NodeProperties {
     pub primary_keys: String,
@@ -158,22 +355,17 @@ fn generate_create_node_table_query(
     let columns_vec: Vec<String> = create_node_table_clause
         .table_schema
         .iter()
-        .map(
-            |column_schema| -> Result<String, ClickhouseQueryGeneratorError> {
-                let column_name = column_schema.column_name;
-                let column_type = column_schema.column_dtype;
-                if let Some(default_value) = &column_schema.default_value {
-                    let default_val = get_default_value(default_value)?;
-                    Ok(format!("{column_name} {column_type} DEFAULT {default_val}"))
-                } else {
-                    Ok(format!("{column_name} {column_type}"))
-                }
-            },
-        )
+        .map(get_column_definition)
         .collect::<Result<Vec<String>, ClickhouseQueryGeneratorError>>()?;
 
     let columns = columns_vec.join(", ");
 
+    let engine = get_table_engine(
+        create_node_table_clause.on_cluster,
+        &create_node_table_clause.table_properties,
+        &create_node_table_clause.table_schema,
+    )?;
+
     // for now only check for primary key. Later we can support multiple properties like skipping indexes etc.
     let node_props = get_node_props(
         create_node_table_clause.table_properties,
@@ -182,8 +374,12 @@ fn generate_create_node_table_query(
 
     let table_name = create_node_table_clause.table_name;
     let primary_keys = node_props.primary_keys.clone();
-    let create_table_string = format!(
-        "CREATE TABLE {table_name} ( {columns} ) ENGINE = MergeTree() PRIMARY KEY ({primary_keys});"
+    let create_table_strings = get_create_table_queries(
+        table_name,
+        &format!("( {columns} )"),
+        &engine.merge_tree.to_string(),
+        &get_storage_clauses(&engine, &primary_keys)?,
+        &engine,
     );
 
     let column_names: Vec<String> = create_node_table_clause
@@ -197,10 +393,11 @@ fn generate_create_node_table_query(
         column_names,
         node_id: node_props.node_id,
         primary_keys: node_props.primary_keys,
+        engine,
     };
 
     Ok((
-        vec![create_table_string],
+        create_table_strings,
         vec![GraphSchemaElement::Node(node_schema)],
     ))
 }
@@ -225,18 +422,7 @@ fn generate_create_rel_table_query(
     let columns_vec: Vec<String> = create_rel_table_clause
         .table_schema
         .iter()
-        .map(
-            |column_schema| -> Result<String, ClickhouseQueryGeneratorError> {
-                let column_name = column_schema.column_name;
-                let column_type = column_schema.column_dtype;
-                if let Some(default_value) = &column_schema.default_value {
-                    let default_val = get_default_value(default_value)?;
-                    Ok(format!("{column_name} {column_type} DEFAULT {default_val}"))
-                } else {
-                    Ok(format!("{column_name} {column_type}"))
-                }
-            },
-        )
+        .map(get_column_definition)
         .collect::<Result<Vec<String>, ClickhouseQueryGeneratorError>>()?;
 
     let mut columns = "".to_string();
@@ -245,22 +431,31 @@ fn generate_create_rel_table_query(
         columns = format!(", {}", columns_vec.join(", "));
     }
 
+    let engine = get_table_engine(
+        create_rel_table_clause.on_cluster,
+        &create_rel_table_clause.table_properties,
+        &create_rel_table_clause.table_schema,
+    )?;
+
     let rel_props = get_rel_props(create_rel_table_clause.table_properties, from_node, to_node);
 
     let primary_keys = rel_props.primary_keys;
 
     let mut create_table_strings: Vec<String> = vec![];
-    let mut graph_schema_elements: Vec<GraphSchemaElement> = vec![];
 
     let rel_table_name = create_rel_table_clause.table_name;
 
     // store schema separately so that we can infer on it
 
-    let create_rel_table_string = format!(
-        "CREATE TABLE {rel_table_name} (from_{from_node} {from_node_id_dtype}, to_{to_node} {to_node_id_dtype}{columns}) ENGINE = MergeTree() PRIMARY KEY ({primary_keys});"
-    );
-
-    create_table_strings.push(create_rel_table_string);
+    create_table_strings.append(&mut get_create_table_queries(
+        rel_table_name,
+        &format!(
+            "(from_{from_node} {from_node_id_dtype}, to_{to_node} {to_node_id_dtype}{columns})"
+        ),
+        &engine.merge_tree.to_string(),
+        &get_storage_clauses(&engine, &primary_keys)?,
+        &engine,
+    ));
 
     let column_names: Vec<String> = create_rel_table_clause
         .table_schema
@@ -275,65 +470,334 @@ fn generate_create_rel_table_query(
         to_node: to_node.to_string(),
         from_node_id_dtype: from_table_schema.node_id.dtype.clone(),
         to_node_id_dtype: to_table_schema.node_id.dtype.clone(),
+        engine,
     };
 
-    graph_schema_elements.push(GraphSchemaElement::Rel(relationship_schema));
+    if rel_props.adj_index {
+        create_table_strings.append(&mut get_create_adj_index_queries(&relationship_schema));
+    }
 
+    let mut graph_schema_elements = vec![GraphSchemaElement::Rel(relationship_schema)];
     if rel_props.adj_index {
-        // CREATE TABLE so_graph.edge_posts_to_users
-        // (
-        //     posts_id UInt32,
-        //     users_ids AggregateFunction(groupBitmap, UInt32),
-        //     INDEX IDX_edge_posts_to_users (posts_id) TYPE minmax GRANULARITY 1
-        // ) ENGINE = AggregatingMergeTree()
-        // ORDER BY posts_id;
-        let create_outgoing_rel_table_string = format!(
-            "CREATE TABLE {rel_table_name}_outgoing (from_id {from_node_id_dtype}, to_id AggregateFunction(groupBitmap, {to_node_id_dtype})) ENGINE = AggregatingMergeTree() ORDER BY from_id;"
-        );
-        create_table_strings.push(create_outgoing_rel_table_string);
-        let create_incoming_rel_table_string = format!(
-            "CREATE TABLE {rel_table_name}_incoming (from_id {to_node_id_dtype}, to_id AggregateFunction(groupBitmap, {from_node_id_dtype})) ENGINE = AggregatingMergeTree() ORDER BY from_id;"
-        );
-        create_table_strings.push(create_incoming_rel_table_string);
-        // CREATE MATERIALIZED VIEW so_graph.MV_posts_to_users TO so_graph.edge_posts_to_users AS
-        // SELECT
-        //     posts_id,
-        //     groupBitmapState(users_id) AS users_ids
-        // FROM so_graph.raw_edge_posts_and_users
-        // GROUP BY posts_id;
-        let create_outgoing_rel_mv_string = format!(
-            "CREATE MATERIALIZED VIEW mv_{rel_table_name}_outgoing TO {rel_table_name}_outgoing AS SELECT from_{from_node} AS from_id, groupBitmapState(to_{to_node}) AS to_id FROM {rel_table_name} GROUP BY from_id;"
-        );
-        create_table_strings.push(create_outgoing_rel_mv_string);
-        let create_incoming_rel_mv_string = format!(
-            "CREATE MATERIALIZED VIEW mv_{rel_table_name}_incoming TO {rel_table_name}_incoming AS SELECT to_{to_node} AS from_id, groupBitmapState(from_{from_node}) AS to_id FROM {rel_table_name} GROUP BY from_id;"
-        );
-        create_table_strings.push(create_incoming_rel_mv_string);
+        graph_schema_elements.append(&mut get_adj_index_schema_elements(rel_table_name));
+    }
 
-        let relationship_outgoing_index_schema = RelationshipIndexSchema {
-            base_rel_table_name: rel_table_name.to_string(),
-            table_name: format!("{}_{}", rel_table_name, Direction::Outgoing),
-            direction: Direction::Outgoing,
-            index_type: IndexType::Bitmap,
-        };
+    Ok((create_table_strings, graph_schema_elements))
+}
 
-        graph_schema_elements.push(GraphSchemaElement::RelIndex(
-            relationship_outgoing_index_schema,
-        ));
+// The views run on each shard, so the index tables are sharded like the rel table.
+fn get_adj_index_engine(rel_engine: &TableEngineSchema) -> TableEngineSchema {
+    TableEngineSchema {
+        cluster: rel_engine.cluster.clone(),
+        replicated: rel_engine.replicated,
+        sharding_key: rel_engine
+            .sharding_key
+            .as_ref()
+            .map(|_| "from_id".to_string()),
+        ..TableEngineSchema::default()
+    }
+}
 
-        let relationship_incoming_index_schema = RelationshipIndexSchema {
-            base_rel_table_name: rel_table_name.to_string(),
-            table_name: format!("{}_{}", rel_table_name, Direction::Incoming),
-            direction: Direction::Incoming,
-            index_type: IndexType::Bitmap,
-        };
+// Bitmap tables of the adjacency index, e.g. for the outgoing direction
+// CREATE TABLE so_graph.edge_posts_to_users
+// (
+//     posts_id UInt32,
+//     users_ids AggregateFunction(groupBitmap, UInt32),
+//     INDEX IDX_edge_posts_to_users (posts_id) TYPE minmax GRANULARITY 1
+// ) ENGINE = AggregatingMergeTree()
+// ORDER BY posts_id;
+// and the materialized views keeping them up to date with the inserts into the rel table
+// CREATE MATERIALIZED VIEW so_graph.MV_posts_to_users TO so_graph.edge_posts_to_users AS
+// SELECT
+//     posts_id,
+//     groupBitmapState(users_id) AS users_ids
+// FROM so_graph.raw_edge_posts_and_users
+// GROUP BY posts_id;
+fn get_create_adj_index_queries(rel_schema: &RelationshipSchema) -> Vec<String> {
+    let rel_table_name = &rel_schema.table_name;
+    let from_node = &rel_schema.from_node;
+    let to_node = &rel_schema.to_node;
+    let from_node_id_dtype = &rel_schema.from_node_id_dtype;
+    let to_node_id_dtype = &rel_schema.to_node_id_dtype;
+    let engine = &rel_schema.engine;
+    let index_engine = get_adj_index_engine(engine);
+
+    let mut queries = vec![];
+    queries.append(&mut get_create_table_queries(
+        &format!("{rel_table_name}_outgoing"),
+        &format!("(from_id {from_node_id_dtype}, to_id AggregateFunction(groupBitmap, {to_node_id_dtype}))"),
+        "AggregatingMergeTree()",
+        "ORDER BY from_id",
+        &index_engine,
+    ));
+    queries.append(&mut get_create_table_queries(
+        &format!("{rel_table_name}_incoming"),
+        &format!("(from_id {to_node_id_dtype}, to_id AggregateFunction(groupBitmap, {from_node_id_dtype}))"),
+        "AggregatingMergeTree()",
+        "ORDER BY from_id",
+        &index_engine,
+    ));
+
+    let on_cluster = engine.on_cluster();
+    let local_rel_table_name = engine.local_table_name(rel_table_name);
+    let local_outgoing_table_name =
+        index_engine.local_table_name(&format!("{rel_table_name}_outgoing"));
+    let local_incoming_table_name =
+        index_engine.local_table_name(&format!("{rel_table_name}_incoming"));
+    queries.push(format!(
+        "CREATE MATERIALIZED VIEW mv_{rel_table_name}_outgoing{on_cluster} TO {local_outgoing_table_name} AS SELECT from_{from_node} AS from_id, groupBitmapState(to_{to_node}) AS to_id FROM {local_rel_table_name} GROUP BY from_id;"
+    ));
+    queries.push(format!(
+        "CREATE MATERIALIZED VIEW mv_{rel_table_name}_incoming{on_cluster} TO {local_incoming_table_name} AS SELECT to_{to_node} AS from_id, groupBitmapState(from_{from_node}) AS to_id FROM {local_rel_table_name} GROUP BY from_id;"
+    ));
+    queries
+}
 
-        graph_schema_elements.push(GraphSchemaElement::RelIndex(
-            relationship_incoming_index_schema,
-        ));
+// Fills the bitmap tables from the rows already in the rel table. The views keep running
+// meanwhile, the rows they also insert are harmless as the bitmaps are merged.
+fn get_backfill_adj_index_queries(rel_schema: &RelationshipSchema) -> Vec<String> {
+    let rel_table_name = &rel_schema.table_name;
+    let from_node = &rel_schema.from_node;
+    let to_node = &rel_schema.to_node;
+    vec![
+        format!(
+            "INSERT INTO {rel_table_name}_outgoing SELECT from_{from_node} AS from_id, groupBitmapState(to_{to_node}) AS to_id FROM {rel_table_name} GROUP BY from_id;"
+        ),
+        format!(
+            "INSERT INTO {rel_table_name}_incoming SELECT to_{to_node} AS from_id, groupBitmapState(from_{from_node}) AS to_id FROM {rel_table_name} GROUP BY from_id;"
+        ),
+    ]
+}
+
+fn get_truncate_adj_index_queries(rel_schema: &RelationshipSchema) -> Vec<String> {
+    let index_engine = get_adj_index_engine(&rel_schema.engine);
+    let on_cluster = index_engine.on_cluster();
+    [Direction::Outgoing, Direction::Incoming]
+        .iter()
+        .map(|direction| {
+            let local_table_name =
+                index_engine.local_table_name(&format!("{}_{direction}", rel_schema.table_name));
+            format!("TRUNCATE TABLE IF EXISTS {local_table_name}{on_cluster};")
+        })
+        .collect()
+}
+
+// The views go first so that nothing is written to the dropped tables.
+fn get_drop_adj_index_queries(rel_schema: &RelationshipSchema) -> Vec<String> {
+    let index_engine = get_adj_index_engine(&rel_schema.engine);
+    let on_cluster = index_engine.on_cluster();
+    let directions = [Direction::Outgoing, Direction::Incoming];
+
+    let mut queries: Vec<String> = directions
+        .iter()
+        .map(|direction| {
+            format!(
+                "DROP VIEW IF EXISTS mv_{}_{direction}{on_cluster};",
+                rel_schema.table_name
+            )
+        })
+        .collect();
+    for direction in directions {
+        let table_name = format!("{}_{direction}", rel_schema.table_name);
+        queries.push(format!("DROP TABLE IF EXISTS {table_name}{on_cluster};"));
+        if index_engine.is_distributed() {
+            let local_table_name = index_engine.local_table_name(&table_name);
+            queries.push(format!(
+                "DROP TABLE IF EXISTS {local_table_name}{on_cluster};"
+            ));
+        }
     }
+    queries
+}
 
-    Ok((create_table_strings, graph_schema_elements))
+fn get_adj_index_schema_elements(rel_table_name: &str) -> Vec<GraphSchemaElement> {
+    [Direction::Outgoing, Direction::Incoming]
+        .into_iter()
+        .map(|direction| {
+            GraphSchemaElement::RelIndex(RelationshipIndexSchema {
+                base_rel_table_name: rel_table_name.to_string(),
+                table_name: format!("{rel_table_name}_{direction}"),
+                direction,
+                index_type: IndexType::Bitmap,
+            })
+        })
+        .collect()
+}
+
+fn generate_adj_index_query(
+    adj_index_clause: AdjIndexClause,
+    current_graph_schema: &GraphSchema,
+) -> Result<(Vec<String>, Vec<GraphSchemaElement>), ClickhouseQueryGeneratorError> {
+    let rel_table_name = adj_index_clause.rel_table_name;
+    let rel_schema = current_graph_schema
+        .get_relationships_schema_opt(rel_table_name)
+        .ok_or(ClickhouseQueryGeneratorError::UnknownRelTableInAdjIndex)?;
+    // both directions are created and dropped together
+    let has_adj_index = current_graph_schema
+        .get_relationship_index_schema_opt(&format!("{rel_table_name}_{}", Direction::Outgoing))
+        .is_some();
+
+    match adj_index_clause.action {
+        AdjIndexAction::Create => {
+            if has_adj_index {
+                return Err(ClickhouseQueryGeneratorError::AdjIndexAlreadyExists);
+            }
+            let mut queries = get_create_adj_index_queries(rel_schema);
+            queries.append(&mut get_backfill_adj_index_queries(rel_schema));
+            Ok((queries, get_adj_index_schema_elements(rel_table_name)))
+        }
+        AdjIndexAction::Rebuild => {
+            if !has_adj_index {
+                return Err(ClickhouseQueryGeneratorError::MissingAdjIndex);
+            }
+            // traversals may miss edges until the backfill is done
+            let mut queries = get_truncate_adj_index_queries(rel_schema);
+            queries.append(&mut get_backfill_adj_index_queries(rel_schema));
+            Ok((queries, vec![]))
+        }
+        AdjIndexAction::Drop => {
+            if !has_adj_index {
+                return Err(ClickhouseQueryGeneratorError::MissingAdjIndex);
+            }
+            let elements = [Direction::Outgoing, Direction::Incoming]
+                .iter()
+                .map(|direction| {
+                    GraphSchemaElement::DropRelIndex(format!("{rel_table_name}_{direction}"))
+                })
+                .collect();
+            Ok((get_drop_adj_index_queries(rel_schema), elements))
+        }
+    }
+}
+
+// `minmax`, `set(max_rows)`, `bloom_filter`, `bloom_filter(false_positive_rate)`,
+// `tokenbf_v1(size_bytes, hash_functions, seed)` or
+// `ngrambf_v1(ngram_size, size_bytes, hash_functions, seed)`.
+fn get_index_type(expr: &Expression) -> Result<IndexType, ClickhouseQueryGeneratorError> {
+    let (name, args) = match expr {
+        Expression::Variable(name) => (*name, &[][..]),
+        Expression::FunctionCallExp(function_call) => {
+            (function_call.name.as_str(), function_call.args.as_slice())
+        }
+        _ => return Err(ClickhouseQueryGeneratorError::UnsupportedIndexType),
+    };
+    let int_args: Option<Vec<u64>> = args
+        .iter()
+        .map(|arg| match arg {
+            Expression::Literal(Literal::Integer(val)) => u64::try_from(*val).ok(),
+            _ => None,
+        })
+        .collect();
+
+    match (name.to_lowercase().as_str(), args, int_args.as_deref()) {
+        ("minmax", [], _) => Ok(IndexType::MinMax),
+        ("set", _, Some([max_rows])) => Ok(IndexType::Set {
+            max_rows: *max_rows,
+        }),
+        ("bloom_filter", [], _) => Ok(IndexType::BloomFilter {
+            false_positive_rate: None,
+        }),
+        ("bloom_filter", [Expression::Literal(Literal::Float(rate))], _)
+            if *rate > 0.0 && *rate < 1.0 =>
+        {
+            Ok(IndexType::BloomFilter {
+                false_positive_rate: Some(*rate),
+            })
+        }
+        ("tokenbf_v1", _, Some([size_bytes, hash_functions, seed])) => {
+            Ok(IndexType::TokenBloomFilter {
+                size_bytes: *size_bytes,
+                hash_functions: *hash_functions,
+                seed: *seed,
+            })
+        }
+        ("ngrambf_v1", _, Some([ngram_size, size_bytes, hash_functions, seed])) => {
+            Ok(IndexType::NgramBloomFilter {
+                ngram_size: *ngram_size,
+                size_bytes: *size_bytes,
+                hash_functions: *hash_functions,
+                seed: *seed,
+            })
+        }
+        _ => Err(ClickhouseQueryGeneratorError::UnsupportedIndexType),
+    }
+}
+
+// Every column used by an indexed expression must be a column of the table.
+fn check_index_columns(
+    expr: &Expression,
+    column_names: &[String],
+) -> Result<(), ClickhouseQueryGeneratorError> {
+    match expr {
+        Expression::Variable(column) => {
+            if column_names.iter().any(|column_name| column_name == column) {
+                Ok(())
+            } else {
+                Err(ClickhouseQueryGeneratorError::UnknownColumnInIndex)
+            }
+        }
+        Expression::FunctionCallExp(function_call) => function_call
+            .args
+            .iter()
+            .try_for_each(|arg| check_index_columns(arg, column_names)),
+        Expression::OperatorApplicationExp(operator_application) => operator_application
+            .operands
+            .iter()
+            .try_for_each(|operand| check_index_columns(operand, column_names)),
+        _ => Ok(()),
+    }
+}
+
+fn generate_create_index_query(
+    create_index_clause: CreateIndexClause,
+    current_graph_schema: &GraphSchema,
+) -> Result<(Vec<String>, Vec<GraphSchemaElement>), ClickhouseQueryGeneratorError> {
+    let table_name = create_index_clause.table_name;
+
+    let (column_names, engine) = if let Some(node_schema) =
+        current_graph_schema.get_node_schema_opt(table_name)
+    {
+        (node_schema.column_names.clone(), &node_schema.engine)
+    } else if let Some(rel_schema) = current_graph_schema.get_relationships_schema_opt(table_name) {
+        let mut column_names = rel_schema.column_names.clone();
+        column_names.push(format!("from_{}", rel_schema.from_node));
+        column_names.push(format!("to_{}", rel_schema.to_node));
+        (column_names, &rel_schema.engine)
+    } else {
+        return Err(ClickhouseQueryGeneratorError::UnknownTableInIndex);
+    };
+
+    for column in &create_index_clause.columns {
+        check_index_columns(column, &column_names)?;
+    }
+    let columns = get_expressions_sql(&create_index_clause.columns)?;
+    let index_type = get_index_type(&create_index_clause.index_type)?;
+    let granularity = create_index_clause.granularity.unwrap_or(1);
+    let index_name = create_index_clause.index_name;
+
+    // the rows of a Distributed table, and so its indexes, are in the local tables
+    let local_table_name = engine.local_table_name(table_name);
+    let on_cluster = engine.on_cluster();
+    let queries = vec![
+        format!(
+            "ALTER TABLE {local_table_name}{on_cluster} ADD INDEX {index_name} ({columns}) TYPE {index_type} GRANULARITY {granularity};"
+        ),
+        // the index is only built for new parts otherwise
+        format!("ALTER TABLE {local_table_name}{on_cluster} MATERIALIZE INDEX {index_name};"),
+    ];
+
+    let property_index_schema = PropertyIndexSchema {
+        index_name: index_name.to_string(),
+        table_name: table_name.to_string(),
+        columns,
+        index_type,
+        granularity,
+    };
+
+    Ok((
+        queries,
+        vec![GraphSchemaElement::PropertyIndex(property_index_schema)],
+    ))
 }
 
 pub fn generate_query(
@@ -347,6 +811,14 @@ pub fn generate_query(
     if let Some(create_rel_table_clause) = query_ast.create_rel_table_clause {
         return generate_create_rel_table_query(create_rel_table_clause, current_graph_schema);
     }
+
+    if let Some(create_index_clause) = query_ast.create_index_clause {
+        return generate_create_index_query(create_index_clause, current_graph_schema);
+    }
+
+    if let Some(adj_index_clause) = query_ast.adj_index_clause {
+        return generate_adj_index_query(adj_index_clause, current_graph_schema);
+    }
     // throw error
     Err(ClickhouseQueryGeneratorError::UnsupportedDDLQuery)
 }
@@ -355,7 +827,7 @@ pub fn generate_query(
 mod tests {
     use std::collections::HashMap;
 
-    use crate::open_cypher_parser::ast::{FunctionCall, Literal};
+    use crate::open_cypher_parser::ast::{FunctionCall, Literal, OperatorApplication};
 
     use super::*;
 
@@ -380,6 +852,7 @@ mod tests {
             column_name: "id",
             column_dtype: "Int64",
             default_value: None,
+            codec: None,
         }];
 
         let out = get_node_props(props, &cols).unwrap();
@@ -401,6 +874,7 @@ mod tests {
             column_name: "id",
             column_dtype: "Int64",
             default_value: None,
+            codec: None,
         }];
         let err = get_node_props(props, &cols).unwrap_err();
         assert!(matches!(
@@ -417,6 +891,7 @@ mod tests {
             column_name: "pk",
             column_dtype: "Int64",
             default_value: None,
+            codec: None,
         }];
         let err = get_node_props(props, &cols).unwrap_err();
         assert!(matches!(err, ClickhouseQueryGeneratorError::MissingNodeId));
@@ -437,11 +912,13 @@ mod tests {
                 column_name: "id1",
                 column_dtype: "Int64",
                 default_value: None,
+                codec: None,
             },
             ColumnSchema {
                 column_name: "id2",
                 column_dtype: "Int64",
                 default_value: None,
+                codec: None,
             },
         ];
         let err = get_node_props(props, &cols).unwrap_err();
@@ -462,6 +939,7 @@ mod tests {
             column_name: "pk",
             column_dtype: "Int64",
             default_value: None,
+            codec: None,
         }];
         let err = get_node_props(props, &cols).unwrap_err();
         assert!(matches!(err, ClickhouseQueryGeneratorError::InvalidNodeId));
@@ -478,6 +956,7 @@ mod tests {
             column_name: "id",
             column_dtype: "String",
             default_value: None,
+            codec: None,
         }];
         let err = get_node_props(props, &cols).unwrap_err();
         assert!(matches!(
@@ -498,11 +977,13 @@ mod tests {
                 column_name: "pk",
                 column_dtype: "UInt64",
                 default_value: None,
+                codec: None,
             },
             ColumnSchema {
                 column_name: "id",
                 column_dtype: "UInt64",
                 default_value: None,
+                codec: None,
             },
         ];
 
@@ -674,10 +1155,12 @@ mod tests {
     fn error_on_missing_primary_key() {
         let clause = CreateNodeTableClause {
             table_name: "Bad",
+            on_cluster: None,
             table_schema: vec![ColumnSchema {
                 column_name: "x",
                 column_dtype: "Int64",
                 default_value: None,
+                codec: None,
             }],
             table_properties: vec![fn_call("node id", vec![Expression::Variable("x")])],
         };
@@ -693,10 +1176,12 @@ mod tests {
     fn error_on_missing_node_id() {
         let clause = CreateNodeTableClause {
             table_name: "Bad",
+            on_cluster: None,
             table_schema: vec![ColumnSchema {
                 column_name: "x",
                 column_dtype: "Int64",
                 default_value: None,
+                codec: None,
             }],
             table_properties: vec![fn_call("primary key", vec![Expression::Variable("x")])],
         };
@@ -709,10 +1194,12 @@ mod tests {
     fn error_on_invalid_node_id_column() {
         let clause = CreateNodeTableClause {
             table_name: "Bad",
+            on_cluster: None,
             table_schema: vec![ColumnSchema {
                 column_name: "a",
                 column_dtype: "Int64",
                 default_value: None,
+                codec: None,
             }],
             table_properties: vec![
                 fn_call("primary key", vec![Expression::Variable("a")]),
@@ -728,10 +1215,12 @@ mod tests {
     fn error_on_invalid_node_id_dtype() {
         let clause = CreateNodeTableClause {
             table_name: "Bad",
+            on_cluster: None,
             table_schema: vec![ColumnSchema {
                 column_name: "key",
                 column_dtype: "String",
                 default_value: None,
+                codec: None,
             }],
             table_properties: vec![
                 fn_call("primary key", vec![Expression::Variable("key")]),
@@ -761,6 +1250,7 @@ mod tests {
                     column: "user_id".to_string(),
                     dtype: "UInt64".to_string(),
                 },
+                engine: TableEngineSchema::default(),
             },
         );
         nodes.insert(
@@ -773,6 +1263,7 @@ mod tests {
                     column: "post_id".to_string(),
                     dtype: "UInt64".to_string(),
                 },
+                engine: TableEngineSchema::default(),
             },
         );
         GraphSchema::build(1, nodes, HashMap::new(), HashMap::new())
@@ -820,6 +1311,7 @@ mod tests {
     fn respects_pk_fn_with_args_full_query_concrete() {
         let clause = CreateRelTableClause {
             table_name: "follows",
+            on_cluster: None,
             from: "User",
             to: "Post",
             table_schema: vec![],
@@ -839,12 +1331,14 @@ mod tests {
     fn includes_default_value_in_columns_full_query_concrete() {
         let clause = CreateRelTableClause {
             table_name: "follows",
+            on_cluster: None,
             from: "User",
             to: "Post",
             table_schema: vec![ColumnSchema {
                 column_name: "count",
                 column_dtype: "Int32",
                 default_value: Some(Expression::Literal(Literal::Integer(99))),
+                codec: None,
             }],
             table_properties: vec![],
         };
@@ -854,10 +1348,224 @@ mod tests {
         assert_eq!(&queries[0], expected_base);
     }
 
+    #[test]
+    fn distributed_node_table_on_cluster() {
+        let clause = CreateNodeTableClause {
+            table_name: "User",
+            on_cluster: Some("graph"),
+            table_schema: vec![ColumnSchema {
+                column_name: "id",
+                column_dtype: "UInt64",
+                default_value: None,
+                codec: None,
+            }],
+            table_properties: vec![
+                fn_call("primary key", vec![Expression::Variable("id")]),
+                fn_call("node id", vec![Expression::Variable("id")]),
+                fn_call(
+                    "replicated",
+                    vec![Expression::Literal(Literal::Boolean(true))],
+                ),
+                fn_call(
+                    "distributed",
+                    vec![fn_call("cityHash64", vec![Expression::Variable("id")])],
+                ),
+            ],
+        };
+
+        let (queries, elements) = generate_create_node_table_query(clause).unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "CREATE TABLE User_local ON CLUSTER graph ( id UInt64 ) ENGINE = ReplicatedMergeTree() PRIMARY KEY (id);",
+                "CREATE TABLE User ON CLUSTER graph AS User_local ENGINE = Distributed(graph, currentDatabase(), User_local, cityHash64(id));",
+            ]
+        );
+        match &elements[0] {
+            GraphSchemaElement::Node(node_schema) => {
+                assert_eq!(node_schema.table_name, "User");
+                assert!(node_schema.engine.is_distributed());
+            }
+            _ => panic!("Expected a Node schema element"),
+        }
+
+        let clause = CreateNodeTableClause {
+            table_name: "User",
+            on_cluster: None,
+            table_schema: vec![ColumnSchema {
+                column_name: "id",
+                column_dtype: "UInt64",
+                default_value: None,
+                codec: None,
+            }],
+            table_properties: vec![
+                fn_call("primary key", vec![Expression::Variable("id")]),
+                fn_call("node id", vec![Expression::Variable("id")]),
+                fn_call("distributed", vec![Expression::Variable("id")]),
+            ],
+        };
+        let err = generate_create_node_table_query(clause).unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::MissingClusterForDistributedTable
+        ));
+    }
+
+    #[test]
+    fn node_table_engine_options() {
+        let columns = vec![
+            ColumnSchema {
+                column_name: "id",
+                column_dtype: "UInt64",
+                default_value: None,
+                codec: None,
+            },
+            ColumnSchema {
+                column_name: "ts",
+                column_dtype: "DateTime",
+                default_value: None,
+                codec: Some(vec![
+                    Expression::Variable("Delta"),
+                    fn_call("ZSTD", vec![Expression::Literal(Literal::Integer(3))]),
+                ]),
+            },
+        ];
+        let clause = CreateNodeTableClause {
+            table_name: "Event",
+            on_cluster: None,
+            table_schema: columns.clone(),
+            table_properties: vec![
+                fn_call("primary key", vec![Expression::Variable("id")]),
+                fn_call("node id", vec![Expression::Variable("id")]),
+                fn_call(
+                    "engine",
+                    vec![fn_call(
+                        "ReplacingMergeTree",
+                        vec![Expression::Variable("ts")],
+                    )],
+                ),
+                fn_call(
+                    "order by",
+                    vec![Expression::Variable("id"), Expression::Variable("ts")],
+                ),
+                fn_call(
+                    "partition by",
+                    vec![fn_call("toYYYYMM", vec![Expression::Variable("ts")])],
+                ),
+                fn_call(
+                    "settings",
+                    vec![Expression::OperatorApplicationExp(OperatorApplication {
+                        operator: Operator::Equal,
+                        operands: vec![
+                            Expression::Variable("index_granularity"),
+                            Expression::Literal(Literal::Integer(8192)),
+                        ],
+                    })],
+                ),
+            ],
+        };
+
+        let (queries, elements) = generate_create_node_table_query(clause).unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "CREATE TABLE Event ( id UInt64, ts DateTime CODEC(Delta, ZSTD(3)) ) ENGINE = ReplacingMergeTree(ts) ORDER BY (id, ts) PARTITION BY toYYYYMM(ts) PRIMARY KEY (id) SETTINGS index_granularity = 8192;"
+            ]
+        );
+        match &elements[0] {
+            GraphSchemaElement::Node(node_schema) => {
+                assert_eq!(
+                    node_schema.engine.merge_tree,
+                    MergeTreeEngine::ReplacingMergeTree {
+                        version: Some("ts".to_string())
+                    }
+                );
+                assert_eq!(node_schema.engine.order_by.as_deref(), Some("id, ts"));
+                assert_eq!(node_schema.engine.column_codecs["ts"], "Delta, ZSTD(3)");
+            }
+            _ => panic!("Expected a Node schema element"),
+        }
+
+        let clause = CreateNodeTableClause {
+            table_name: "Event",
+            on_cluster: None,
+            table_schema: columns.clone(),
+            table_properties: vec![
+                fn_call("primary key", vec![Expression::Variable("id")]),
+                fn_call("node id", vec![Expression::Variable("id")]),
+                fn_call(
+                    "order by",
+                    vec![Expression::Variable("ts"), Expression::Variable("id")],
+                ),
+            ],
+        };
+        let err = generate_create_node_table_query(clause).unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::PrimaryKeyNotPrefixOfOrderBy
+        ));
+
+        let clause = CreateNodeTableClause {
+            table_name: "Event",
+            on_cluster: None,
+            table_schema: columns,
+            table_properties: vec![
+                fn_call("primary key", vec![Expression::Variable("id")]),
+                fn_call("node id", vec![Expression::Variable("id")]),
+                fn_call(
+                    "engine",
+                    vec![fn_call(
+                        "ReplacingMergeTree",
+                        vec![Expression::Variable("version")],
+                    )],
+                ),
+            ],
+        };
+        let err = generate_create_node_table_query(clause).unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::UnsupportedEngine
+        ));
+    }
+
+    #[test]
+    fn distributed_rel_table_with_adj_index() {
+        let clause = CreateRelTableClause {
+            table_name: "follows",
+            on_cluster: Some("graph"),
+            from: "User",
+            to: "Post",
+            table_schema: vec![],
+            table_properties: vec![
+                fn_call(
+                    "adj index",
+                    vec![Expression::Literal(Literal::Boolean(true))],
+                ),
+                fn_call("distributed", vec![Expression::Variable("from_User")]),
+            ],
+        };
+
+        let (queries, _) = generate_create_rel_table_query(clause, &make_schema()).unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "CREATE TABLE follows_local ON CLUSTER graph (from_User UInt64, to_Post UInt64) ENGINE = MergeTree() PRIMARY KEY (from_User, to_Post);",
+                "CREATE TABLE follows ON CLUSTER graph AS follows_local ENGINE = Distributed(graph, currentDatabase(), follows_local, from_User);",
+                "CREATE TABLE follows_outgoing_local ON CLUSTER graph (from_id UInt64, to_id AggregateFunction(groupBitmap, UInt64)) ENGINE = AggregatingMergeTree() ORDER BY from_id;",
+                "CREATE TABLE follows_outgoing ON CLUSTER graph AS follows_outgoing_local ENGINE = Distributed(graph, currentDatabase(), follows_outgoing_local, from_id);",
+                "CREATE TABLE follows_incoming_local ON CLUSTER graph (from_id UInt64, to_id AggregateFunction(groupBitmap, UInt64)) ENGINE = AggregatingMergeTree() ORDER BY from_id;",
+                "CREATE TABLE follows_incoming ON CLUSTER graph AS follows_incoming_local ENGINE = Distributed(graph, currentDatabase(), follows_incoming_local, from_id);",
+                "CREATE MATERIALIZED VIEW mv_follows_outgoing ON CLUSTER graph TO follows_outgoing_local AS SELECT from_User AS from_id, groupBitmapState(to_Post) AS to_id FROM follows_local GROUP BY from_id;",
+                "CREATE MATERIALIZED VIEW mv_follows_incoming ON CLUSTER graph TO follows_incoming_local AS SELECT to_Post AS from_id, groupBitmapState(from_User) AS to_id FROM follows_local GROUP BY from_id;",
+            ]
+        );
+    }
+
     #[test]
     fn error_unknown_from() {
         let clause = CreateRelTableClause {
             table_name: "Bad",
+            on_cluster: None,
             from: "X", // not in schema
             to: "B",
             table_schema: vec![],
@@ -874,6 +1582,7 @@ mod tests {
     fn error_unknown_to_concrete() {
         let clause = CreateRelTableClause {
             table_name: "BadRel",
+            on_cluster: None,
             from: "User",  // valid in schema
             to: "Comment", // not present in make_schema()
             table_schema: vec![],
@@ -886,16 +1595,265 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn create_index_on_node_and_rel() {
+        let mut schema = make_schema();
+        schema.insert_rel_schema(
+            "follows".to_string(),
+            RelationshipSchema {
+                table_name: "follows".to_string(),
+                column_names: vec!["since".to_string()],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema {
+                    cluster: Some("graph".to_string()),
+                    sharding_key: Some("from_User".to_string()),
+                    ..TableEngineSchema::default()
+                },
+            },
+        );
+
+        let clause = CreateIndexClause {
+            index_name: "idx_user_id",
+            table_name: "User",
+            columns: vec![Expression::Variable("user_id")],
+            index_type: Expression::Variable("bloom_filter"),
+            granularity: None,
+        };
+        let (queries, elements) = generate_create_index_query(clause, &schema).unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "ALTER TABLE User ADD INDEX idx_user_id (user_id) TYPE bloom_filter GRANULARITY 1;",
+                "ALTER TABLE User MATERIALIZE INDEX idx_user_id;",
+            ]
+        );
+        match &elements[0] {
+            GraphSchemaElement::PropertyIndex(property_index_schema) => {
+                assert_eq!(property_index_schema.table_name, "User");
+                assert_eq!(
+                    property_index_schema.index_type,
+                    IndexType::BloomFilter {
+                        false_positive_rate: None
+                    }
+                );
+            }
+            _ => panic!("Expected a PropertyIndex schema element"),
+        }
+
+        let clause = CreateIndexClause {
+            index_name: "idx_since",
+            table_name: "follows",
+            columns: vec![
+                Expression::Variable("since"),
+                Expression::Variable("to_User"),
+            ],
+            index_type: fn_call("set", vec![Expression::Literal(Literal::Integer(100))]),
+            granularity: Some(4),
+        };
+        let (queries, _) = generate_create_index_query(clause, &schema).unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "ALTER TABLE follows_local ON CLUSTER graph ADD INDEX idx_since (since, to_User) TYPE set(100) GRANULARITY 4;",
+                "ALTER TABLE follows_local ON CLUSTER graph MATERIALIZE INDEX idx_since;",
+            ]
+        );
+    }
+
+    #[test]
+    fn create_index_errors() {
+        let schema = make_schema();
+        let clause = |table_name, column, index_type| CreateIndexClause {
+            index_name: "idx",
+            table_name,
+            columns: vec![Expression::Variable(column)],
+            index_type,
+            granularity: None,
+        };
+
+        let err = generate_create_index_query(
+            clause("Comment", "user_id", Expression::Variable("minmax")),
+            &schema,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::UnknownTableInIndex
+        ));
+
+        let err = generate_create_index_query(
+            clause("User", "email", Expression::Variable("minmax")),
+            &schema,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::UnknownColumnInIndex
+        ));
+
+        let err = generate_create_index_query(
+            clause(
+                "User",
+                "user_id",
+                fn_call(
+                    "bloom_filter",
+                    vec![Expression::Literal(Literal::Float(1.5))],
+                ),
+            ),
+            &schema,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::UnsupportedIndexType
+        ));
+    }
+
+    fn follows_schema(engine: TableEngineSchema) -> RelationshipSchema {
+        RelationshipSchema {
+            table_name: "follows".to_string(),
+            column_names: vec![],
+            from_node: "User".to_string(),
+            to_node: "Post".to_string(),
+            from_node_id_dtype: "UInt64".to_string(),
+            to_node_id_dtype: "UInt64".to_string(),
+            engine,
+        }
+    }
+
+    #[test]
+    fn create_rebuild_and_drop_adj_index() {
+        let mut schema = make_schema();
+        schema.insert_rel_schema(
+            "follows".to_string(),
+            follows_schema(TableEngineSchema::default()),
+        );
+        let clause = |action| AdjIndexClause {
+            action,
+            rel_table_name: "follows",
+        };
+
+        let err = generate_adj_index_query(clause(AdjIndexAction::Rebuild), &schema).unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::MissingAdjIndex
+        ));
+
+        let (queries, elements) =
+            generate_adj_index_query(clause(AdjIndexAction::Create), &schema).unwrap();
+        assert_eq!(
+            queries[2..],
+            [
+                "CREATE MATERIALIZED VIEW mv_follows_outgoing TO follows_outgoing AS SELECT from_User AS from_id, groupBitmapState(to_Post) AS to_id FROM follows GROUP BY from_id;",
+                "CREATE MATERIALIZED VIEW mv_follows_incoming TO follows_incoming AS SELECT to_Post AS from_id, groupBitmapState(from_User) AS to_id FROM follows GROUP BY from_id;",
+                "INSERT INTO follows_outgoing SELECT from_User AS from_id, groupBitmapState(to_Post) AS to_id FROM follows GROUP BY from_id;",
+                "INSERT INTO follows_incoming SELECT to_Post AS from_id, groupBitmapState(from_User) AS to_id FROM follows GROUP BY from_id;",
+            ]
+        );
+        for element in elements {
+            if let GraphSchemaElement::RelIndex(rel_index_schema) = element {
+                schema
+                    .insert_rel_index_schema(rel_index_schema.table_name.clone(), rel_index_schema);
+            }
+        }
+
+        let err = generate_adj_index_query(clause(AdjIndexAction::Create), &schema).unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::AdjIndexAlreadyExists
+        ));
+
+        let (queries, elements) =
+            generate_adj_index_query(clause(AdjIndexAction::Rebuild), &schema).unwrap();
+        assert_eq!(
+            queries[..2],
+            [
+                "TRUNCATE TABLE IF EXISTS follows_outgoing;",
+                "TRUNCATE TABLE IF EXISTS follows_incoming;",
+            ]
+        );
+        assert_eq!(queries.len(), 4);
+        assert!(elements.is_empty());
+
+        let (queries, elements) =
+            generate_adj_index_query(clause(AdjIndexAction::Drop), &schema).unwrap();
+        assert_eq!(
+            queries,
+            vec![
+                "DROP VIEW IF EXISTS mv_follows_outgoing;",
+                "DROP VIEW IF EXISTS mv_follows_incoming;",
+                "DROP TABLE IF EXISTS follows_outgoing;",
+                "DROP TABLE IF EXISTS follows_incoming;",
+            ]
+        );
+        assert!(matches!(
+            &elements[..],
+            [GraphSchemaElement::DropRelIndex(outgoing), GraphSchemaElement::DropRelIndex(incoming)]
+                if outgoing == "follows_outgoing" && incoming == "follows_incoming"
+        ));
+    }
+
+    #[test]
+    fn drop_distributed_adj_index() {
+        let mut schema = make_schema();
+        schema.insert_rel_schema(
+            "follows".to_string(),
+            follows_schema(TableEngineSchema {
+                cluster: Some("graph".to_string()),
+                sharding_key: Some("from_User".to_string()),
+                ..TableEngineSchema::default()
+            }),
+        );
+        for element in get_adj_index_schema_elements("follows") {
+            if let GraphSchemaElement::RelIndex(rel_index_schema) = element {
+                schema
+                    .insert_rel_index_schema(rel_index_schema.table_name.clone(), rel_index_schema);
+            }
+        }
+
+        let clause = AdjIndexClause {
+            action: AdjIndexAction::Drop,
+            rel_table_name: "follows",
+        };
+        let (queries, _) = generate_adj_index_query(clause, &schema).unwrap();
+        assert_eq!(
+            queries[2..],
+            [
+                "DROP TABLE IF EXISTS follows_outgoing ON CLUSTER graph;",
+                "DROP TABLE IF EXISTS follows_outgoing_local ON CLUSTER graph;",
+                "DROP TABLE IF EXISTS follows_incoming ON CLUSTER graph;",
+                "DROP TABLE IF EXISTS follows_incoming_local ON CLUSTER graph;",
+            ]
+        );
+
+        let clause = AdjIndexClause {
+            action: AdjIndexAction::Drop,
+            rel_table_name: "likes",
+        };
+        let err = generate_adj_index_query(clause, &schema).unwrap_err();
+        assert!(matches!(
+            err,
+            ClickhouseQueryGeneratorError::UnknownRelTableInAdjIndex
+        ));
+    }
+
     #[test]
     fn generate_query_unsupported() {
         // AST with no DDL clauses
         let ast = OpenCypherQueryAst {
+            source: "",
+            query_prefix: None,
             match_clause: None,
             with_clause: None,
             where_clause: None,
             create_clause: None,
             create_node_table_clause: None,
             create_rel_table_clause: None,
+            create_index_clause: None,
+            adj_index_clause: None,
             set_clause: None,
             remove_clause: None,
             delete_clause: None,
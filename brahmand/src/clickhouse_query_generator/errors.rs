@@ -28,6 +28,36 @@ pub enum ClickhouseQueryGeneratorError {
     UnsupportedDDLQuery,
     #[error("Unsupported expression found for default value.")]
     UnsupportedDefaultValue,
+    #[error(
+        "Unsupported sharding key found. Use a column or a function of columns e.g. DISTRIBUTED (cityHash64(id))."
+    )]
+    UnsupportedShardingKey,
+    #[error("Unsupported expression found in table property.")]
+    UnsupportedTablePropertyExpression,
+    #[error(
+        "Unsupported table engine found. Use MergeTree or ReplacingMergeTree with an optional version column of the table."
+    )]
+    UnsupportedEngine,
+    #[error("Primary key must be a prefix of ORDER BY in DDL.")]
+    PrimaryKeyNotPrefixOfOrderBy,
+    #[error(
+        "Unsupported index type found. Use minmax, set(max_rows), bloom_filter, bloom_filter(false_positive_rate), tokenbf_v1(size, hashes, seed) or ngrambf_v1(n, size, hashes, seed)."
+    )]
+    UnsupportedIndexType,
+    #[error("Unknown table found in index. Make sure to create the node or relationship first.")]
+    UnknownTableInIndex,
+    #[error("Unknown column found in index. Only the properties of the table can be indexed.")]
+    UnknownColumnInIndex,
+    #[error(
+        "Unknown relationship table found in adj index. Make sure to create the relationship first."
+    )]
+    UnknownRelTableInAdjIndex,
+    #[error("The relationship already has an adj index.")]
+    AdjIndexAlreadyExists,
+    #[error("The relationship has no adj index. Use CREATE ADJ INDEX first.")]
+    MissingAdjIndex,
+    #[error("Distributed tables need ON CLUSTER in DDL.")]
+    MissingClusterForDistributedTable,
     #[error("Primary key is missing in DDL.")]
     MissingPrimaryKey,
     #[error("Node id column is missing in DDL.")]
@@ -229,9 +229,11 @@ impl ToSql for Join {
             JoinType::Right => "RIGHT JOIN",
         };
 
+        let global = if self.global { "GLOBAL " } else { "" };
+
         let mut sql = format!(
-            "{} {} AS {}",
-            join_type_tr, self.table_name, self.table_alias
+            "{}{} {} AS {}",
+            global, join_type_tr, self.table_name, self.table_alias
         );
 
         let joining_on_str_vec: Vec<String> =
@@ -339,12 +341,17 @@ impl RenderExpr {
                     }
                 }
             }
-            RenderExpr::InSubquery(InSubquery { expr, subplan }) => {
+            RenderExpr::InSubquery(InSubquery {
+                expr,
+                subplan,
+                global,
+            }) => {
                 let left = expr.to_sql();
                 let body = subplan.to_sql();
                 let body = body.split_whitespace().collect::<Vec<&str>>().join(" ");
+                let in_str = if *global { "GLOBAL IN" } else { "IN" };
 
-                format!("{} IN ({})", left, body)
+                format!("{} {} ({})", left, in_str, body)
             }
         }
     }
@@ -0,0 +1,278 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Deserialize;
+
+use crate::{
+    open_cypher_parser,
+    query_planner::logical_expr::{
+        AggregateFnCall, Column, LogicalExpr, OperatorApplication, ScalarFnCall,
+    },
+};
+
+use super::graph_schema::GraphSchema;
+
+// Alias of the element in row filters e.g. "n.country = 'IN'"
+pub const ROW_FILTER_ALIAS: &str = "n";
+
+// What a caller may read from the graph. Node labels and relationship types which are not
+// listed can not be matched or traversed.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GraphPermissions {
+    pub nodes: HashMap<String, ElementPermissions>,
+    pub relationships: HashMap<String, ElementPermissions>,
+    // can create and drop node and relationship tables and their indexes
+    pub ddl: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ElementPermissions {
+    pub hidden_properties: HashSet<String>,
+    // Cypher predicate which every readable row has to satisfy
+    pub row_filter: Option<String>,
+}
+
+impl ElementPermissions {
+    // Row filter with the properties of `n` converted to columns of the element's table, so that
+    // it can be added to the table filters like the predicates of a WHERE clause.
+    pub fn row_filter_expr(&self) -> Result<Option<LogicalExpr>, String> {
+        let Some(row_filter) = &self.row_filter else {
+            return Ok(None);
+        };
+        let expression = open_cypher_parser::parse_standalone_expression(row_filter)
+            .ok_or_else(|| format!("Invalid row filter `{}`.", row_filter))?;
+        convert_row_filter(LogicalExpr::from(expression))
+            .map(Some)
+            .map_err(|e| format!("Invalid row filter `{}`: {}", row_filter, e))
+    }
+
+    // A property is hidden when every role hides it, a row is readable when any role can read it.
+    fn union(&self, other: &ElementPermissions) -> ElementPermissions {
+        let row_filter = match (&self.row_filter, &other.row_filter) {
+            (Some(filter), Some(other_filter)) if filter == other_filter => Some(filter.clone()),
+            (Some(filter), Some(other_filter)) => {
+                Some(format!("({}) OR ({})", filter, other_filter))
+            }
+            _ => None,
+        };
+        ElementPermissions {
+            hidden_properties: self
+                .hidden_properties
+                .intersection(&other.hidden_properties)
+                .cloned()
+                .collect(),
+            row_filter,
+        }
+    }
+}
+
+impl GraphPermissions {
+    // Permissions of a caller with several roles.
+    pub fn union<'a>(
+        permissions: impl IntoIterator<Item = &'a GraphPermissions>,
+    ) -> GraphPermissions {
+        let mut merged = GraphPermissions::default();
+        for role_permissions in permissions {
+            merged.ddl |= role_permissions.ddl;
+            for (merged_elements, elements) in [
+                (&mut merged.nodes, &role_permissions.nodes),
+                (&mut merged.relationships, &role_permissions.relationships),
+            ] {
+                for (label, element) in elements {
+                    let merged_element = match merged_elements.get(label) {
+                        Some(merged_element) => merged_element.union(element),
+                        None => element.clone(),
+                    };
+                    merged_elements.insert(label.clone(), merged_element);
+                }
+            }
+        }
+        merged
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for element in self.nodes.values().chain(self.relationships.values()) {
+            element.row_filter_expr()?;
+        }
+        Ok(())
+    }
+
+    // Graph schema without the hidden properties, nodes and relationships returned as entities
+    // only carry the properties the caller can read.
+    pub fn restrict_schema(&self, graph_schema: &GraphSchema) -> GraphSchema {
+        let mut nodes = graph_schema.get_nodes_schemas().clone();
+        for (label, node_schema) in nodes.iter_mut() {
+            if let Some(permissions) = self.nodes.get(label) {
+                node_schema
+                    .column_names
+                    .retain(|column| !permissions.hidden_properties.contains(column));
+            }
+        }
+        let mut relationships = graph_schema.get_relationships_schemas().clone();
+        for (rel_type, rel_schema) in relationships.iter_mut() {
+            if let Some(permissions) = self.relationships.get(rel_type) {
+                rel_schema
+                    .column_names
+                    .retain(|column| !permissions.hidden_properties.contains(column));
+            }
+        }
+        GraphSchema::build(
+            graph_schema.get_version(),
+            nodes,
+            relationships,
+            graph_schema.get_relationships_indexes_schemas().clone(),
+        )
+    }
+}
+
+fn convert_row_filter(expr: LogicalExpr) -> Result<LogicalExpr, String> {
+    let convert_all = |exprs: Vec<LogicalExpr>| {
+        exprs
+            .into_iter()
+            .map(convert_row_filter)
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let converted = match expr {
+        LogicalExpr::PropertyAccessExp(property_access) => {
+            if property_access.table_alias.0 != ROW_FILTER_ALIAS {
+                return Err(format!(
+                    "properties should be accessed on `{}`",
+                    ROW_FILTER_ALIAS
+                ));
+            }
+            LogicalExpr::Column(property_access.column)
+        }
+        LogicalExpr::OperatorApplicationExp(op_app) => {
+            LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: op_app.operator,
+                operands: convert_all(op_app.operands)?,
+            })
+        }
+        LogicalExpr::ScalarFnCall(fc) => LogicalExpr::ScalarFnCall(ScalarFnCall {
+            name: fc.name,
+            args: convert_all(fc.args)?,
+        }),
+        LogicalExpr::List(exprs) => LogicalExpr::List(convert_all(exprs)?),
+        LogicalExpr::Literal(literal) => LogicalExpr::Literal(literal),
+        LogicalExpr::AggregateFnCall(AggregateFnCall { name, .. }) => {
+            return Err(format!("aggregate function `{}` is not allowed", name));
+        }
+        LogicalExpr::Parameter(name) => {
+            return Err(format!("parameter `${}` is not allowed", name));
+        }
+        _ => return Err("only properties, literals and functions are allowed".to_string()),
+    };
+    Ok(converted)
+}
+
+// Columns of the element's table which an expression reads, e.g. the table filters.
+pub fn referenced_columns(expr: &LogicalExpr) -> Vec<&Column> {
+    match expr {
+        LogicalExpr::Column(column) => vec![column],
+        LogicalExpr::OperatorApplicationExp(op_app) => op_app
+            .operands
+            .iter()
+            .flat_map(referenced_columns)
+            .collect(),
+        LogicalExpr::ScalarFnCall(fc) => fc.args.iter().flat_map(referenced_columns).collect(),
+        LogicalExpr::AggregateFnCall(fc) => fc.args.iter().flat_map(referenced_columns).collect(),
+        LogicalExpr::List(exprs) => exprs.iter().flat_map(referenced_columns).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph_catalog::graph_schema::{NodeIdSchema, NodeSchema, TableEngineSchema},
+        query_planner::logical_expr::{Literal, Operator},
+    };
+    use serde_json::json;
+
+    fn permissions(value: serde_json::Value) -> GraphPermissions {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_row_filter_expr() {
+        let element = ElementPermissions {
+            row_filter: Some("n.country = 'IN'".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            element.row_filter_expr().unwrap(),
+            Some(LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::Equal,
+                operands: vec![
+                    LogicalExpr::Column(Column("country".to_string())),
+                    LogicalExpr::Literal(Literal::String("IN".to_string())),
+                ],
+            }))
+        );
+
+        let other_alias = ElementPermissions {
+            row_filter: Some("u.country = 'IN'".to_string()),
+            ..Default::default()
+        };
+        assert!(other_alias.row_filter_expr().is_err());
+        let invalid = ElementPermissions {
+            row_filter: Some("n.country =".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.row_filter_expr().is_err());
+    }
+
+    #[test]
+    fn test_union() {
+        let analyst = permissions(json!({
+            "nodes": {"User": {"hidden_properties": ["email", "phone"], "row_filter": "n.country = 'IN'"}},
+            "relationships": {"FOLLOWS": {}},
+        }));
+        let support = permissions(json!({
+            "nodes": {
+                "User": {"hidden_properties": ["phone"], "row_filter": "n.country = 'US'"},
+                "Ticket": {},
+            },
+        }));
+
+        let merged = GraphPermissions::union([&analyst, &support]);
+        let user = &merged.nodes["User"];
+        assert_eq!(user.hidden_properties, HashSet::from(["phone".to_string()]));
+        assert_eq!(
+            user.row_filter.as_deref(),
+            Some("(n.country = 'IN') OR (n.country = 'US')")
+        );
+        assert!(merged.nodes.contains_key("Ticket"));
+        assert!(merged.relationships.contains_key("FOLLOWS"));
+        assert!(merged.validate().is_ok());
+    }
+
+    #[test]
+    fn test_restrict_schema() {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "User".to_string(),
+            NodeSchema {
+                table_name: "User".to_string(),
+                column_names: vec!["user_id".to_string(), "email".to_string()],
+                primary_keys: "user_id".to_string(),
+                node_id: NodeIdSchema {
+                    column: "user_id".to_string(),
+                    dtype: "UInt64".to_string(),
+                },
+                engine: TableEngineSchema::default(),
+            },
+        );
+        let graph_schema = GraphSchema::build(1, nodes, HashMap::new(), HashMap::new());
+        let permissions = permissions(json!({"nodes": {"User": {"hidden_properties": ["email"]}}}));
+
+        let restricted = permissions.restrict_schema(&graph_schema);
+        assert_eq!(
+            restricted.get_node_schema("User").unwrap().column_names,
+            vec!["user_id".to_string()]
+        );
+    }
+}
@@ -10,6 +10,8 @@ pub struct NodeSchema {
     pub column_names: Vec<String>,
     pub primary_keys: String,
     pub node_id: NodeIdSchema,
+    #[serde(default)]
+    pub engine: TableEngineSchema,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -20,6 +22,95 @@ pub struct RelationshipSchema {
     pub to_node: String,
     pub from_node_id_dtype: String,
     pub to_node_id_dtype: String,
+    // shared by the adjacency index tables of the relationship
+    #[serde(default)]
+    pub engine: TableEngineSchema,
+}
+
+// Where the tables of a node or relationship live on a ClickHouse cluster. Schemas stored before
+// it was added default to plain MergeTree tables on a single server.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TableEngineSchema {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cluster: Option<String>,
+    #[serde(default)]
+    pub replicated: bool,
+    // set when `table_name` is a Distributed table over `{table_name}_local` on every shard
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sharding_key: Option<String>,
+    #[serde(default)]
+    pub merge_tree: MergeTreeEngine,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<String>,
+    // sorting key when it is longer than the primary key, which is always its prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<String>,
+    // e.g. "index_granularity = 8192"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub settings: Vec<String>,
+    // column name to its codecs e.g. "Delta, ZSTD(3)"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub column_codecs: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub enum MergeTreeEngine {
+    #[default]
+    MergeTree,
+    // keeps one row per sorting key, the one with the highest version or else the last inserted
+    ReplacingMergeTree {
+        version: Option<String>,
+    },
+}
+
+impl fmt::Display for MergeTreeEngine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeTreeEngine::MergeTree => f.write_str("MergeTree()"),
+            MergeTreeEngine::ReplacingMergeTree { version } => {
+                write!(
+                    f,
+                    "ReplacingMergeTree({})",
+                    version.as_deref().unwrap_or_default()
+                )
+            }
+        }
+    }
+}
+
+impl TableEngineSchema {
+    pub fn is_distributed(&self) -> bool {
+        self.sharding_key.is_some()
+    }
+
+    // " ON CLUSTER name", or nothing for a single server
+    pub fn on_cluster(&self) -> String {
+        self.cluster
+            .as_ref()
+            .map(|cluster| format!(" ON CLUSTER {cluster}"))
+            .unwrap_or_default()
+    }
+
+    // e.g. MergeTree() becomes ReplicatedMergeTree(), the replication path and replica name come
+    // from the default_replica_path and default_replica_name of the server config.
+    pub fn merge_tree_engine(&self, merge_tree: &str) -> String {
+        if self.replicated {
+            format!("Replicated{merge_tree}")
+        } else {
+            merge_tree.to_string()
+        }
+    }
+
+    // Table holding the rows of `table_name` on each server.
+    pub fn local_table_name(&self, table_name: &str) -> String {
+        if self.is_distributed() {
+            format!("{table_name}_local")
+        } else {
+            table_name.to_string()
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,24 +136,81 @@ impl fmt::Display for Direction {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Bitmap is the adjacency index of a relationship, the others are ClickHouse data skipping
+// indexes on the properties of a node or relationship.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum IndexType {
     Bitmap,
+    MinMax,
+    Set {
+        max_rows: u64,
+    },
+    BloomFilter {
+        false_positive_rate: Option<f64>,
+    },
+    TokenBloomFilter {
+        size_bytes: u64,
+        hash_functions: u64,
+        seed: u64,
+    },
+    NgramBloomFilter {
+        ngram_size: u64,
+        size_bytes: u64,
+        hash_functions: u64,
+        seed: u64,
+    },
 }
 
+// The data skipping indexes are written as their ClickHouse TYPE.
 impl fmt::Display for IndexType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             IndexType::Bitmap => f.write_str("Bitmap"),
+            IndexType::MinMax => f.write_str("minmax"),
+            IndexType::Set { max_rows } => write!(f, "set({max_rows})"),
+            IndexType::BloomFilter {
+                false_positive_rate: None,
+            } => f.write_str("bloom_filter"),
+            IndexType::BloomFilter {
+                false_positive_rate: Some(rate),
+            } => write!(f, "bloom_filter({rate})"),
+            IndexType::TokenBloomFilter {
+                size_bytes,
+                hash_functions,
+                seed,
+            } => write!(f, "tokenbf_v1({size_bytes}, {hash_functions}, {seed})"),
+            IndexType::NgramBloomFilter {
+                ngram_size,
+                size_bytes,
+                hash_functions,
+                seed,
+            } => write!(
+                f,
+                "ngrambf_v1({ngram_size}, {size_bytes}, {hash_functions}, {seed})"
+            ),
         }
     }
 }
 
+// Data skipping index on the properties of a node or relationship table.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PropertyIndexSchema {
+    pub index_name: String,
+    pub table_name: String,
+    // indexed expression e.g. "email" or "lower(name)"
+    pub columns: String,
+    pub index_type: IndexType,
+    pub granularity: u64,
+}
+
 #[derive(Debug, Clone)]
 pub enum GraphSchemaElement {
     Node(NodeSchema),
     Rel(RelationshipSchema),
     RelIndex(RelationshipIndexSchema),
+    // table name of a dropped relationship index
+    DropRelIndex(String),
+    PropertyIndex(PropertyIndexSchema),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -77,6 +225,9 @@ pub struct GraphSchema {
     nodes: HashMap<String, NodeSchema>,
     relationships: HashMap<String, RelationshipSchema>,
     relationships_indexes: HashMap<String, RelationshipIndexSchema>,
+    // node or relationship label to its data skipping indexes
+    #[serde(default)]
+    property_indexes: HashMap<String, Vec<PropertyIndexSchema>>,
 }
 
 impl GraphSchema {
@@ -91,6 +242,7 @@ impl GraphSchema {
             nodes,
             relationships,
             relationships_indexes,
+            property_indexes: HashMap::new(),
         }
     }
 
@@ -111,6 +263,20 @@ impl GraphSchema {
             .insert(rel_label, rel_index_schema);
     }
 
+    pub fn remove_rel_index_schema(&mut self, table_name: &str) {
+        self.relationships_indexes.remove(table_name);
+    }
+
+    // Replaces the index of the same name on the table, if any.
+    pub fn insert_property_index_schema(&mut self, property_index_schema: PropertyIndexSchema) {
+        let indexes = self
+            .property_indexes
+            .entry(property_index_schema.table_name.clone())
+            .or_default();
+        indexes.retain(|index| index.index_name != property_index_schema.index_name);
+        indexes.push(property_index_schema);
+    }
+
     pub fn get_version(&self) -> u32 {
         self.version
     }
@@ -152,6 +318,17 @@ impl GraphSchema {
         &self.nodes
     }
 
+    pub fn get_relationships_indexes_schemas(&self) -> &HashMap<String, RelationshipIndexSchema> {
+        &self.relationships_indexes
+    }
+
+    pub fn get_property_indexes(&self, label: &str) -> &[PropertyIndexSchema] {
+        self.property_indexes
+            .get(label)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
     pub fn get_node_schema_opt(&self, node_label: &str) -> Option<&NodeSchema> {
         self.nodes.get(node_label)
     }
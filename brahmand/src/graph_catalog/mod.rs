@@ -1,2 +1,3 @@
 pub mod errors;
+pub mod graph_permissions;
 pub mod graph_schema;
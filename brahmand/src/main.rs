@@ -8,6 +8,5 @@ mod server;
 
 #[tokio::main]
 async fn main() {
-    println!("\nbrahmandDB v{}\n", env!("CARGO_PKG_VERSION"));
     server::run().await;
 }
@@ -0,0 +1,86 @@
+use nom::branch::alt;
+use nom::combinator::{cut, map};
+use nom::error::context;
+use nom::{IResult, Parser, bytes::complete::tag_no_case};
+
+use super::ast::{AdjIndexAction, AdjIndexClause};
+use super::common::ws;
+use super::errors::OpenCypherParsingError;
+use super::expression::parse_identifier;
+
+fn parse_adj_index_action(
+    input: &str,
+) -> IResult<&str, AdjIndexAction, OpenCypherParsingError<'_>> {
+    alt((
+        map(ws(tag_no_case("CREATE")), |_| AdjIndexAction::Create),
+        map(ws(tag_no_case("REBUILD")), |_| AdjIndexAction::Rebuild),
+        map(ws(tag_no_case("DROP")), |_| AdjIndexAction::Drop),
+    ))
+    .parse(input)
+}
+
+fn parse_rel_table_name(input: &str) -> IResult<&str, &str> {
+    let (input, _) = ws(tag_no_case("ON")).parse(input)?;
+    ws(parse_identifier).parse(input)
+}
+
+// CREATE ADJ INDEX ON FOLLOWS, REBUILD ADJ INDEX ON FOLLOWS or DROP ADJ INDEX ON FOLLOWS
+pub fn parse_adj_index_clause(
+    input: &str,
+) -> IResult<&str, AdjIndexClause<'_>, OpenCypherParsingError<'_>> {
+    let (input, action) = parse_adj_index_action(input)?;
+    let (input, _) = ws(tag_no_case("ADJ")).parse(input)?;
+    let (input, _) = ws(tag_no_case("INDEX")).parse(input)?;
+
+    let (input, rel_table_name) =
+        context("Error in adj index clause", cut(rel_table_name_parser)).parse(input)?;
+
+    Ok((
+        input,
+        AdjIndexClause {
+            action,
+            rel_table_name,
+        },
+    ))
+}
+
+fn rel_table_name_parser(input: &'_ str) -> IResult<&'_ str, &'_ str, OpenCypherParsingError<'_>> {
+    parse_rel_table_name(input).map_err(|e| match e {
+        nom::Err::Incomplete(needed) => nom::Err::Incomplete(needed),
+        nom::Err::Error(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
+        nom::Err::Failure(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adj_index_clause() {
+        for (input, action) in [
+            ("CREATE ADJ INDEX ON FOLLOWS", AdjIndexAction::Create),
+            ("rebuild adj index on FOLLOWS", AdjIndexAction::Rebuild),
+            ("DROP ADJ INDEX ON FOLLOWS", AdjIndexAction::Drop),
+        ] {
+            let (remaining, ast) = parse_adj_index_clause(input).unwrap();
+            assert!(remaining.trim().is_empty());
+            assert_eq!(
+                ast,
+                AdjIndexClause {
+                    action,
+                    rel_table_name: "FOLLOWS",
+                }
+            );
+        }
+
+        assert!(matches!(
+            parse_adj_index_clause("DROP ADJ INDEX FOLLOWS"),
+            Err(nom::Err::Failure(_))
+        ));
+        assert!(matches!(
+            parse_adj_index_clause("CREATE INDEX idx ON User(email) TYPE minmax"),
+            Err(nom::Err::Error(_))
+        ));
+    }
+}
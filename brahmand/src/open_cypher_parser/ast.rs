@@ -4,12 +4,17 @@ use std::{cell::RefCell, fmt, rc::Rc};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct OpenCypherQueryAst<'a> {
+    // the statement text, every slice in the AST points into it
+    pub source: &'a str,
+    pub query_prefix: Option<QueryPrefix>,
     pub match_clause: Option<MatchClause<'a>>,
     pub with_clause: Option<WithClause<'a>>,
     pub where_clause: Option<WhereClause<'a>>,
     pub create_clause: Option<CreateClause<'a>>,
     pub create_node_table_clause: Option<CreateNodeTableClause<'a>>,
     pub create_rel_table_clause: Option<CreateRelTableClause<'a>>,
+    pub create_index_clause: Option<CreateIndexClause<'a>>,
+    pub adj_index_clause: Option<AdjIndexClause<'a>>,
     pub set_clause: Option<SetClause<'a>>,
     pub remove_clause: Option<RemoveClause<'a>>,
     pub delete_clause: Option<DeleteClause<'a>>,
@@ -19,9 +24,18 @@ pub struct OpenCypherQueryAst<'a> {
     pub limit_clause: Option<LimitClause>,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum QueryPrefix {
+    // only plan the query
+    Explain,
+    // plan and execute the query, collecting execution stats
+    Profile,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct MatchClause<'a> {
-    pub path_patterns: Vec<PathPattern<'a>>,
+    // path pattern along with its path variable if assigned. e.g. MATCH p = (a)-[]->(b)
+    pub path_patterns: Vec<(Option<&'a str>, PathPattern<'a>)>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -32,6 +46,7 @@ pub struct CreateClause<'a> {
 #[derive(Debug, PartialEq, Clone)]
 pub struct CreateNodeTableClause<'a> {
     pub table_name: &'a str,
+    pub on_cluster: Option<&'a str>,
     pub table_schema: Vec<ColumnSchema<'a>>,
     pub table_properties: Vec<Expression<'a>>,
 }
@@ -41,17 +56,46 @@ pub struct ColumnSchema<'a> {
     pub column_name: &'a str,
     pub column_dtype: &'a str,
     pub default_value: Option<Expression<'a>>,
+    // compression codecs e.g. CODEC(Delta, ZSTD(3))
+    pub codec: Option<Vec<Expression<'a>>>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct CreateRelTableClause<'a> {
     pub table_name: &'a str,
+    pub on_cluster: Option<&'a str>,
     pub from: &'a str,
     pub to: &'a str,
     pub table_schema: Vec<ColumnSchema<'a>>,
     pub table_properties: Vec<Expression<'a>>,
 }
 
+// CREATE INDEX idx ON User(email) TYPE bloom_filter GRANULARITY 4
+#[derive(Debug, PartialEq, Clone)]
+pub struct CreateIndexClause<'a> {
+    pub index_name: &'a str,
+    pub table_name: &'a str,
+    pub columns: Vec<Expression<'a>>,
+    // e.g. bloom_filter or bloom_filter(0.01)
+    pub index_type: Expression<'a>,
+    pub granularity: Option<u64>,
+}
+
+// CREATE, REBUILD or DROP ADJ INDEX ON rel_table
+#[derive(Debug, PartialEq, Clone)]
+pub struct AdjIndexClause<'a> {
+    pub action: AdjIndexAction,
+    pub rel_table_name: &'a str,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AdjIndexAction {
+    Create,
+    // refills the index from the rows of the rel table
+    Rebuild,
+    Drop,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct SetClause<'a> {
     pub set_items: Vec<OperatorApplication<'a>>,
@@ -189,7 +233,8 @@ impl From<Direction> for String {
     fn from(value: Direction) -> Self {
         match value {
             Direction::Incoming => "incoming".to_string(),
-            Direction::Outgoing | Direction::Either => "outgoing".to_string(),
+            Direction::Outgoing => "outgoing".to_string(),
+            Direction::Either => "either".to_string(),
         }
     }
 }
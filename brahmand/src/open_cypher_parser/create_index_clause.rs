@@ -0,0 +1,126 @@
+use nom::character::complete::{char, u64};
+use nom::combinator::{cut, opt};
+use nom::error::context;
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, preceded};
+use nom::{IResult, Parser, bytes::complete::tag_no_case};
+
+use super::ast::{CreateIndexClause, Expression};
+use super::common::ws;
+use super::errors::OpenCypherParsingError;
+use super::expression::{parse_expression, parse_identifier};
+
+// (index_name, table_name, columns, index_type, granularity)
+type ParsedIndex<'a> = (
+    &'a str,
+    &'a str,
+    Vec<Expression<'a>>,
+    Expression<'a>,
+    Option<u64>,
+);
+
+// idx ON User(email) TYPE bloom_filter GRANULARITY 4
+fn parse_index(input: &'_ str) -> IResult<&'_ str, ParsedIndex<'_>> {
+    let (input, index_name) = ws(parse_identifier).parse(input)?;
+    let (input, _) = ws(tag_no_case("ON")).parse(input)?;
+    let (input, table_name) = ws(parse_identifier).parse(input)?;
+    let (input, columns) = delimited(
+        ws(char('(')),
+        separated_list1(ws(char(',')), ws(parse_expression)),
+        ws(char(')')),
+    )
+    .parse(input)?;
+    let (input, _) = ws(tag_no_case("TYPE")).parse(input)?;
+    let (input, index_type) = ws(parse_expression).parse(input)?;
+    let (input, granularity) =
+        opt(preceded(ws(tag_no_case("GRANULARITY")), ws(u64))).parse(input)?;
+    Ok((
+        input,
+        (index_name, table_name, columns, index_type, granularity),
+    ))
+}
+
+pub fn parse_create_index_clause(
+    input: &str,
+) -> IResult<&str, CreateIndexClause<'_>, OpenCypherParsingError<'_>> {
+    let (input, _) = ws(tag_no_case("CREATE")).parse(input)?;
+    let (input, _) = ws(tag_no_case("INDEX")).parse(input)?;
+
+    let (input, (index_name, table_name, columns, index_type, granularity)) =
+        context("Error in create index clause", cut(index_parser)).parse(input)?;
+
+    let create_index_clause = CreateIndexClause {
+        index_name,
+        table_name,
+        columns,
+        index_type,
+        granularity,
+    };
+
+    Ok((input, create_index_clause))
+}
+
+fn index_parser(input: &'_ str) -> IResult<&'_ str, ParsedIndex<'_>, OpenCypherParsingError<'_>> {
+    parse_index(input).map_err(|e| match e {
+        nom::Err::Incomplete(needed) => nom::Err::Incomplete(needed),
+        nom::Err::Error(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
+        nom::Err::Failure(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::open_cypher_parser::ast::{FunctionCall, Literal};
+
+    use super::*;
+
+    #[test]
+    fn test_create_index_clause() {
+        let input = "CREATE INDEX idx_email ON User(email) TYPE bloom_filter";
+        let (remaining, ast) = parse_create_index_clause(input).unwrap();
+
+        assert!(remaining.trim().is_empty());
+        assert_eq!(
+            ast,
+            CreateIndexClause {
+                index_name: "idx_email",
+                table_name: "User",
+                columns: vec![Expression::Variable("email")],
+                index_type: Expression::Variable("bloom_filter"),
+                granularity: None,
+            }
+        );
+
+        let input = "CREATE INDEX idx_name ON User(lower(name)) TYPE ngrambf_v1(3, 256, 2, 0) GRANULARITY 4";
+        let (remaining, ast) = parse_create_index_clause(input).unwrap();
+
+        assert!(remaining.trim().is_empty());
+        assert_eq!(
+            ast.columns,
+            vec![Expression::FunctionCallExp(FunctionCall {
+                name: "lower".to_string(),
+                args: vec![Expression::Variable("name")],
+            })]
+        );
+        assert_eq!(
+            ast.index_type,
+            Expression::FunctionCallExp(FunctionCall {
+                name: "ngrambf_v1".to_string(),
+                args: [3, 256, 2, 0]
+                    .into_iter()
+                    .map(|arg| Expression::Literal(Literal::Integer(arg)))
+                    .collect(),
+            })
+        );
+        assert_eq!(ast.granularity, Some(4));
+    }
+
+    #[test]
+    fn test_create_index_clause_missing_type() {
+        let input = "CREATE INDEX idx_email ON User(email)";
+        assert!(matches!(
+            parse_create_index_clause(input),
+            Err(nom::Err::Failure(_))
+        ));
+    }
+}
@@ -4,18 +4,23 @@ use nom::{IResult, Parser, bytes::complete::tag_no_case};
 
 use super::ast::{ColumnSchema, CreateNodeTableClause, Expression};
 use super::common::ws;
-use super::create_table_schema::parse_node_table_properties_list;
+use super::create_table_schema::{parse_node_table_properties_list, parse_on_cluster};
 use super::errors::OpenCypherParsingError;
 use super::expression::parse_identifier;
 
-// (table_name,(schema, properties))
-type ParsedNodeTableSchema<'a> = (&'a str, (Vec<ColumnSchema<'a>>, Vec<Expression<'a>>));
+// (table_name, on_cluster, (schema, properties))
+type ParsedNodeTableSchema<'a> = (
+    &'a str,
+    Option<&'a str>,
+    (Vec<ColumnSchema<'a>>, Vec<Expression<'a>>),
+);
 
 pub fn parse_node_table_schema(input: &'_ str) -> IResult<&'_ str, ParsedNodeTableSchema<'_>> {
     let (input, table_name) = ws(parse_identifier).parse(input)?;
+    let (input, on_cluster) = parse_on_cluster(input)?;
 
     let (input, (schema, properties)) = parse_node_table_properties_list(input)?;
-    Ok((input, (table_name, (schema, properties))))
+    Ok((input, (table_name, on_cluster, (schema, properties))))
 }
 
 pub fn parse_create_node_table_clause(
@@ -25,7 +30,7 @@ pub fn parse_create_node_table_clause(
     let (input, _) = ws(tag_no_case("NODE")).parse(input)?;
     let (input, _) = ws(tag_no_case("TABLE")).parse(input)?;
 
-    let (input, (table_name, (schema, properties))) = context(
+    let (input, (table_name, on_cluster, (schema, properties))) = context(
         "Error in create node table clause",
         cut(node_table_schema_parser),
     )
@@ -33,6 +38,7 @@ pub fn parse_create_node_table_clause(
 
     let create_node_table_clause = CreateNodeTableClause {
         table_name,
+        on_cluster,
         table_schema: schema,
         table_properties: properties,
     };
@@ -67,16 +73,19 @@ mod tests {
 
         let expected = CreateNodeTableClause {
             table_name: "Product",
+            on_cluster: None,
             table_schema: vec![
                 ColumnSchema {
                     column_name: "title",
                     column_dtype: "STRING",
                     default_value: None,
+                    codec: None,
                 },
                 ColumnSchema {
                     column_name: "price",
                     column_dtype: "INT64",
                     default_value: None,
+                    codec: None,
                 },
             ],
             table_properties: vec![Expression::FunctionCallExp(FunctionCall {
@@ -96,16 +105,19 @@ mod tests {
 
         let expected = CreateNodeTableClause {
             table_name: "User",
+            on_cluster: None,
             table_schema: vec![
                 ColumnSchema {
                     column_name: "name",
                     column_dtype: "STRING",
                     default_value: None,
+                    codec: None,
                 },
                 ColumnSchema {
                     column_name: "age",
                     column_dtype: "INT64",
                     default_value: Some(Expression::Literal(Literal::Integer(0))),
+                    codec: None,
                 },
             ],
             table_properties: vec![Expression::FunctionCallExp(FunctionCall {
@@ -117,6 +129,49 @@ mod tests {
         assert_eq!(ast, expected);
     }
 
+    #[test]
+    fn test_create_node_table_clause_on_cluster() {
+        let input = "CREATE NODE TABLE User ON CLUSTER graph (id UInt64, PRIMARY KEY (id), DISTRIBUTED (id))";
+        let (remaining, ast) = parse_create_node_table_clause(input).unwrap();
+
+        assert!(remaining.trim().is_empty());
+        assert_eq!(ast.table_name, "User");
+        assert_eq!(ast.on_cluster, Some("graph"));
+        assert_eq!(ast.table_schema.len(), 1);
+        assert_eq!(
+            ast.table_properties[1],
+            Expression::FunctionCallExp(FunctionCall {
+                name: "DISTRIBUTED".to_string(),
+                args: vec![Expression::Variable("id")],
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_node_table_clause_with_codec_and_order_by() {
+        let input = "CREATE NODE TABLE Event (id UInt64, ts DateTime CODEC(Delta, ZSTD(3)), PRIMARY KEY (id), ORDER BY (id, ts))";
+        let (remaining, ast) = parse_create_node_table_clause(input).unwrap();
+
+        assert!(remaining.trim().is_empty());
+        assert_eq!(
+            ast.table_schema[1].codec,
+            Some(vec![
+                Expression::Variable("Delta"),
+                Expression::FunctionCallExp(FunctionCall {
+                    name: "ZSTD".to_string(),
+                    args: vec![Expression::Literal(Literal::Integer(3))],
+                }),
+            ])
+        );
+        assert_eq!(
+            ast.table_properties[1],
+            Expression::FunctionCallExp(FunctionCall {
+                name: "ORDER BY".to_string(),
+                args: vec![Expression::Variable("id"), Expression::Variable("ts")],
+            })
+        );
+    }
+
     #[test]
     fn test_parse_create_node_table_clause_missing_table_schema() {
         let input = "CREATE NODE TABLE";
@@ -7,20 +7,22 @@ use nom::{IResult, Parser, bytes::complete::tag_no_case};
 
 use super::ast::{ColumnSchema, CreateRelTableClause, Expression};
 use super::common::ws;
-use super::create_table_schema::parse_rel_table_properties_list;
+use super::create_table_schema::{parse_on_cluster, parse_rel_table_properties_list};
 use super::errors::OpenCypherParsingError;
 // use super::create_table_schema::parse_table_properties_list;
 use super::expression::parse_identifier;
 
-// (table_name, (from, to), (schema, properties))
+// (table_name, on_cluster, (from, to), (schema, properties))
 type ParsedRelTableSchema<'a> = (
     &'a str,
+    Option<&'a str>,
     (&'a str, &'a str),
     (Vec<ColumnSchema<'a>>, Vec<Expression<'a>>),
 );
 
 fn parse_rel_table_schema(input: &'_ str) -> IResult<&'_ str, ParsedRelTableSchema<'_>> {
     let (input, table_name) = ws(parse_identifier).parse(input)?;
+    let (input, on_cluster) = parse_on_cluster(input)?;
     // Inside the parentheses, first parse the connection.
     let (input, (from_to, table_schema_prop)) = delimited(
         ws(char('(')),
@@ -43,7 +45,10 @@ fn parse_rel_table_schema(input: &'_ str) -> IResult<&'_ str, ParsedRelTableSche
 
     let (schema, properties) = table_schema_prop.unwrap_or((Vec::new(), Vec::new()));
     let (from, to) = from_to;
-    Ok((input, (table_name, (from, to), (schema, properties))))
+    Ok((
+        input,
+        (table_name, on_cluster, (from, to), (schema, properties)),
+    ))
 }
 
 /// Parse the relationship connection clause: "FROM table TO table"
@@ -62,7 +67,7 @@ pub fn parse_create_rel_table_clause(
     let (input, _) = ws(tag_no_case("REL")).parse(input)?;
     let (input, _) = ws(tag_no_case("TABLE")).parse(input)?;
 
-    let (input, (table_name, (from, to), (schema, properties))) = context(
+    let (input, (table_name, on_cluster, (from, to), (schema, properties))) = context(
         "Error in create rel table clause",
         cut(rel_table_schema_parser),
     )
@@ -70,6 +75,7 @@ pub fn parse_create_rel_table_clause(
 
     let create_rel_table_clause = CreateRelTableClause {
         table_name,
+        on_cluster,
         from,
         to,
         table_schema: schema,
@@ -103,6 +109,7 @@ mod tests {
         assert!(remaining.trim().is_empty());
         let expected = CreateRelTableClause {
             table_name: "Follows",
+            on_cluster: None,
             from: "User",
             to: "User",
             table_schema: vec![
@@ -110,11 +117,13 @@ mod tests {
                     column_name: "since",
                     column_dtype: "DATE",
                     default_value: None,
+                    codec: None,
                 },
                 ColumnSchema {
                     column_name: "age",
                     column_dtype: "INT64",
                     default_value: None,
+                    codec: None,
                 },
             ],
             table_properties: vec![Expression::FunctionCallExp(FunctionCall {
@@ -132,12 +141,14 @@ mod tests {
         assert!(remaining.trim().is_empty());
         let expected = CreateRelTableClause {
             table_name: "Follows",
+            on_cluster: None,
             from: "User",
             to: "User",
             table_schema: vec![ColumnSchema {
                 column_name: "since",
                 column_dtype: "DATE",
                 default_value: Some(Expression::Literal(Literal::String("today"))),
+                codec: None,
             }],
             table_properties: vec![Expression::FunctionCallExp(FunctionCall {
                 name: "PRIMARY KEY".to_string(),
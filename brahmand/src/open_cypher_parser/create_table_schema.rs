@@ -1,7 +1,7 @@
 use nom::branch::alt;
 use nom::bytes::complete::tag_no_case;
 use nom::character::complete::char;
-use nom::combinator::{map, opt};
+use nom::combinator::{map, opt, peek};
 use nom::sequence::preceded;
 use nom::{
     IResult, Parser, character::complete::multispace0, multi::separated_list1, sequence::delimited,
@@ -35,18 +35,39 @@ pub fn parse_property_function_call(input: &'_ str) -> IResult<&'_ str, Expressi
     ))
 }
 
+// Parse the optional "ON CLUSTER name" following the table name.
+pub fn parse_on_cluster(input: &str) -> IResult<&str, Option<&str>> {
+    opt(preceded(
+        (ws(tag_no_case("ON")), ws(tag_no_case("CLUSTER"))),
+        ws(parse_identifier),
+    ))
+    .parse(input)
+}
+
 //Parse a column schema item: e.g. "title STRING"
 fn parse_column_schema(input: &'_ str) -> IResult<&'_ str, ColumnSchema<'_>> {
     let (input, col_name) = ws(parse_identifier).parse(input)?;
     let (input, col_dtype) = ws(parse_identifier).parse(input)?;
     let (input, default_value) =
         opt(preceded(ws(tag_no_case("DEFAULT")), ws(parse_expression))).parse(input)?;
+    let (input, codec) = opt(preceded(
+        ws(tag_no_case("CODEC")),
+        delimited(
+            ws(char('(')),
+            separated_list1(ws(char(',')), ws(parse_expression)),
+            ws(char(')')),
+        ),
+    ))
+    .parse(input)?;
+    // the column must end here, otherwise it is a property like "ORDER BY (id)"
+    let (input, _) = peek(ws(alt((char(','), char(')'))))).parse(input)?;
     Ok((
         input,
         ColumnSchema {
             column_name: col_name,
             column_dtype: col_dtype,
             default_value,
+            codec,
         },
     ))
 }
@@ -59,11 +80,11 @@ pub enum SchemaItem<'a> {
 // We try to parse a column definition first; if that fails, we parse a property function call.
 pub fn parse_property_item(input: &'_ str) -> IResult<&'_ str, SchemaItem<'_>> {
     alt((
-        map(parse_property_function_call, SchemaItem::Property),
         map(parse_column_schema, |col| {
             // println!("col {:?}", col);
             SchemaItem::Column(col)
         }),
+        map(parse_property_function_call, SchemaItem::Property),
     ))
     .parse(input)
 }
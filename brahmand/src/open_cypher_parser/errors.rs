@@ -1,15 +1,20 @@
 use nom::error::{ContextError, ParseError};
+use serde::Serialize;
 use std::fmt;
 
 #[derive(Debug, PartialEq)]
 pub struct OpenCypherParsingError<'a> {
+    // remaining input at the point of failure along with the error context
     pub errors: Vec<(&'a str, &'static str)>,
+    // tokens that would have been accepted at the point of failure
+    pub expected: Vec<String>,
 }
 
 impl<'a> ParseError<&'a str> for OpenCypherParsingError<'a> {
     fn from_error_kind(input: &'a str, _kind: nom::error::ErrorKind) -> Self {
         OpenCypherParsingError {
             errors: vec![(input, "unknown error")],
+            expected: vec![],
         }
     }
 
@@ -17,6 +22,13 @@ impl<'a> ParseError<&'a str> for OpenCypherParsingError<'a> {
         other.errors.push((input, "unknown error (appended)"));
         other
     }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        OpenCypherParsingError {
+            errors: vec![(input, "unexpected input")],
+            expected: vec![c.to_string()],
+        }
+    }
 }
 
 impl<'a> ContextError<&'a str> for OpenCypherParsingError<'a> {
@@ -35,6 +47,9 @@ impl fmt::Display for OpenCypherParsingError<'_> {
         for (input, ctx) in &self.errors {
             writeln!(f, "{}: {:}", ctx, input)?;
         }
+        if !self.expected.is_empty() {
+            writeln!(f, "expected: {}", self.expected.join(", "))?;
+        }
         Ok(())
     }
 }
@@ -44,6 +59,149 @@ impl<'a> From<nom::error::Error<&'a str>> for OpenCypherParsingError<'a> {
         OpenCypherParsingError {
             // errors: vec![(err.input, "nom::error conversion")],
             errors: vec![(err.input, "Unable to parse")],
+            expected: vec![],
         }
     }
 }
+
+// contexts which do not say anything about what went wrong
+const GENERIC_CONTEXTS: [&str; 4] = [
+    "unknown error",
+    "unknown error (appended)",
+    "Unable to parse",
+    "unexpected input",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourcePosition {
+    // byte offset into the query
+    pub offset: usize,
+    // 1-based
+    pub line: usize,
+    // 1-based, counted in characters
+    pub column: usize,
+}
+
+impl SourcePosition {
+    pub fn from_offset(query: &str, offset: usize) -> Self {
+        let offset = offset.min(query.len());
+        let before = &query[..offset];
+        let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        SourcePosition {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    // AST nodes keep slices of the query, so their position can be found from the slice itself.
+    pub fn from_slice(query: &str, slice: &str) -> Option<Self> {
+        let query_start = query.as_ptr() as usize;
+        let slice_start = slice.as_ptr() as usize;
+        if slice_start < query_start || slice_start + slice.len() > query_start + query.len() {
+            return None;
+        }
+        Some(Self::from_offset(query, slice_start - query_start))
+    }
+
+    // Line of the query with a caret under the position, e.g.
+    //   MATCH (a:User RETURN a;
+    //                 ^
+    pub fn snippet(&self, query: &str) -> String {
+        let line_text = query.lines().nth(self.line - 1).unwrap_or_default();
+        format!("{}\n{}^", line_text, " ".repeat(self.column - 1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseErrorReport {
+    pub message: String,
+    pub position: SourcePosition,
+    pub expected: Vec<String>,
+    pub snippet: String,
+}
+
+impl OpenCypherParsingError<'_> {
+    pub fn report(&self, query: &str) -> ParseErrorReport {
+        // furthest point reached by the parser is where the query went wrong
+        let position = self
+            .errors
+            .iter()
+            .filter_map(|(input, _)| SourcePosition::from_slice(query, input))
+            .max_by_key(|position| position.offset)
+            .unwrap_or_else(|| SourcePosition::from_offset(query, query.len()));
+
+        let near = query[position.offset..]
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+
+        let message = match self
+            .errors
+            .iter()
+            .map(|(_, ctx)| *ctx)
+            .find(|ctx| !ctx.is_empty() && !GENERIC_CONTEXTS.contains(ctx))
+        {
+            Some(ctx) if near.is_empty() => format!("{} at end of query", ctx),
+            Some(ctx) => format!("{} near `{}`", ctx, near),
+            None if near.is_empty() => "Unexpected end of query".to_string(),
+            None => format!("Unexpected input `{}`", near),
+        };
+
+        ParseErrorReport {
+            message,
+            snippet: position.snippet(query),
+            position,
+            expected: self.expected.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_cypher_parser::parse_query;
+
+    #[test]
+    fn test_source_position() {
+        let query = "MATCH (a)\nRETURN a.name;";
+        let position = SourcePosition::from_offset(query, 17);
+        assert_eq!(position.line, 2);
+        assert_eq!(position.column, 8);
+        assert_eq!(position.snippet(query), "RETURN a.name;\n       ^");
+
+        assert_eq!(SourcePosition::from_slice(query, "RETURN"), None);
+        assert_eq!(
+            SourcePosition::from_slice(query, &query[10..])
+                .unwrap()
+                .line,
+            2
+        );
+    }
+
+    #[test]
+    fn test_report_points_at_failure() {
+        let query = "MATCH (a:User)\nRETURN a LIMIT 1.5;";
+        let report = parse_query(query).unwrap_err().report(query);
+
+        assert_eq!(report.position.line, 2);
+        assert_eq!(report.position.column, 16);
+        assert_eq!(report.expected, vec!["integer".to_string()]);
+        assert_eq!(
+            report.message,
+            "Value of limit clause should be integer near `1.5;`"
+        );
+        assert_eq!(report.snippet, "RETURN a LIMIT 1.5;\n               ^");
+    }
+
+    #[test]
+    fn test_report_unexpected_clause() {
+        let query = "MATCH (a) RETURN a RETRUN b;";
+        let report = parse_query(query).unwrap_err().report(query);
+
+        assert_eq!(report.position.column, 20);
+        assert!(report.expected.contains(&"LIMIT".to_string()));
+        assert!(report.expected.contains(&";".to_string()));
+        assert!(!report.expected.contains(&"MATCH".to_string()));
+    }
+}
@@ -12,10 +12,10 @@ pub fn parse_limit_clause(
 ) -> IResult<&'_ str, LimitClause, OpenCypherParsingError<'_>> {
     // Parse the MATCH statement
 
-    let (input, _) = ws(tag_no_case("LIMIT")).parse(input)?;
+    let (expression_input, _) = ws(tag_no_case("LIMIT")).parse(input)?;
 
     let (input, expression) =
-        context("Error in limit clause", cut(expression_parser)).parse(input)?;
+        context("Error in limit clause", cut(expression_parser)).parse(expression_input)?;
 
     if let Expression::Literal(Literal::Integer(limit)) = expression {
         let limit_clause = LimitClause { limit_item: limit };
@@ -24,10 +24,8 @@ pub fn parse_limit_clause(
     } else {
         // return error
         Err(nom::Err::Failure(OpenCypherParsingError {
-            errors: vec![(
-                "Value of limit clause should be integer",
-                "Error in limit clause",
-            )],
+            errors: vec![(expression_input, "Value of limit clause should be integer")],
+            expected: vec!["integer".to_string()],
         }))
     }
 }
@@ -1,12 +1,14 @@
 use nom::character::complete::char;
-use nom::combinator::cut;
+use nom::combinator::{cut, opt};
 use nom::error::context;
+use nom::sequence::{pair, terminated};
 use nom::{
     IResult, Parser, bytes::complete::tag_no_case, character::complete::multispace0,
     multi::separated_list1, sequence::delimited,
 };
 
 use super::ast::{MatchClause, PathPattern};
+use super::common::{self, ws};
 use super::errors::OpenCypherParsingError;
 use super::path_pattern;
 
@@ -19,7 +21,7 @@ pub fn parse_match_clause(
         "Error in match clause",
         separated_list1(
             delimited(multispace0, char(','), multispace0),
-            cut(path_parser),
+            pair(opt(path_variable_parser), cut(path_parser)),
         ),
     )
     .parse(input)?;
@@ -31,6 +33,13 @@ pub fn parse_match_clause(
     Ok((input, match_clause))
 }
 
+// Parses the path variable assignment e.g. `p =` in `MATCH p = (a)-[]->(b)`
+fn path_variable_parser(input: &str) -> IResult<&str, &str, OpenCypherParsingError<'_>> {
+    terminated(ws(common::parse_alphanumeric_with_underscore), char('='))
+        .parse(input)
+        .map_err(|e: nom::Err<nom::error::Error<&str>>| e.map(OpenCypherParsingError::from))
+}
+
 fn path_parser(input: &str) -> IResult<&str, PathPattern<'_>, OpenCypherParsingError<'_>> {
     path_pattern::parse_path_pattern(input).map_err(|e| match e {
         nom::Err::Incomplete(needed) => nom::Err::Incomplete(needed),
@@ -56,7 +65,7 @@ mod tests {
                 assert_eq!(remaining, "");
                 // We expect one path pattern.
                 assert_eq!(match_clause.path_patterns.len(), 1);
-                match &match_clause.path_patterns[0] {
+                match &match_clause.path_patterns[0].1 {
                     PathPattern::Node(node) => {
                         // Expected empty node: no name, no label, no properties.
                         let expected = NodePattern {
@@ -83,7 +92,7 @@ mod tests {
             Ok((remaining, match_clause)) => {
                 assert_eq!(remaining, "");
                 assert_eq!(match_clause.path_patterns.len(), 2);
-                for pattern in &match_clause.path_patterns {
+                for (_, pattern) in &match_clause.path_patterns {
                     match pattern {
                         PathPattern::Node(node) => {
                             let expected = NodePattern {
@@ -103,6 +112,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_match_clause_named_path() {
+        let input = "MATCH p = (a)-[]->(b), (c)";
+        let result = parse_match_clause(input);
+        match result {
+            Ok((remaining, match_clause)) => {
+                assert_eq!(remaining, "");
+                assert_eq!(match_clause.path_patterns.len(), 2);
+
+                let (path_variable, path_pattern) = &match_clause.path_patterns[0];
+                assert_eq!(*path_variable, Some("p"));
+                assert!(matches!(path_pattern, PathPattern::ConnectedPattern(_)));
+
+                let (path_variable, path_pattern) = &match_clause.path_patterns[1];
+                assert_eq!(*path_variable, None);
+                assert!(matches!(path_pattern, PathPattern::Node(_)));
+            }
+            Err(e) => panic!("Parsing failed unexpectedly: {:?}", e),
+        }
+    }
+
     #[test]
     fn test_parse_match_clause_missing_match_keyword() {
         let input = "MERGE ()";
@@ -1,20 +1,22 @@
 use ast::{
-    CreateClause, CreateNodeTableClause, CreateRelTableClause, DeleteClause, LimitClause,
-    MatchClause, OpenCypherQueryAst, OrderByClause, RemoveClause, ReturnClause, SetClause,
-    SkipClause, WhereClause, WithClause,
+    AdjIndexClause, CreateClause, CreateIndexClause, CreateNodeTableClause, CreateRelTableClause,
+    DeleteClause, LimitClause, MatchClause, OpenCypherQueryAst, OrderByClause, QueryPrefix,
+    RemoveClause, ReturnClause, SetClause, SkipClause, WhereClause, WithClause,
 };
 use common::ws;
 use errors::OpenCypherParsingError;
-use nom::bytes::complete::tag;
-use nom::character::complete::multispace0;
-use nom::combinator::{cut, opt};
-use nom::error::context;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, tag_no_case};
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{map, opt};
 use nom::sequence::terminated;
 use nom::{IResult, Parser};
 
+mod adj_index_clause;
 pub mod ast;
 mod common;
 mod create_clause;
+mod create_index_clause;
 mod create_node_table_clause;
 mod create_rel_table_clause;
 mod create_table_schema;
@@ -35,18 +37,83 @@ mod with_clause;
 pub fn parse_statement(
     input: &'_ str,
 ) -> IResult<&'_ str, OpenCypherQueryAst<'_>, OpenCypherParsingError<'_>> {
-    context(
-        "missing semicolon",
-        cut(terminated(parse_query_with_nom, ws(tag(";")))),
+    let (remaining, query_ast) = parse_query_with_nom(input)?;
+
+    match ws(tag::<_, _, OpenCypherParsingError>(";")).parse(remaining) {
+        Ok((remaining, _)) => Ok((remaining, query_ast)),
+        Err(nom::Err::Incomplete(needed)) => Err(nom::Err::Incomplete(needed)),
+        Err(_) => {
+            // Whatever is left could not be parsed as any of the clauses that can still follow.
+            let remaining = remaining.trim_start();
+            let ctx = if remaining.is_empty() {
+                "missing semicolon"
+            } else {
+                "unexpected input"
+            };
+            Err(nom::Err::Failure(OpenCypherParsingError {
+                errors: vec![(remaining, ctx)],
+                expected: get_expected_clauses(&query_ast),
+            }))
+        }
+    }
+}
+
+fn parse_query_prefix(input: &'_ str) -> IResult<&'_ str, QueryPrefix, OpenCypherParsingError<'_>> {
+    terminated(
+        alt((
+            map(tag_no_case("EXPLAIN"), |_| QueryPrefix::Explain),
+            map(tag_no_case("PROFILE"), |_| QueryPrefix::Profile),
+        )),
+        multispace1,
     )
     .parse(input)
 }
 
+fn get_expected_clauses(query_ast: &OpenCypherQueryAst<'_>) -> Vec<String> {
+    // in the same order as they are parsed in parse_query_with_nom
+    let clauses = [
+        ("MATCH", query_ast.match_clause.is_some()),
+        ("WITH", query_ast.with_clause.is_some()),
+        ("WHERE", query_ast.where_clause.is_some()),
+        (
+            "CREATE NODE TABLE",
+            query_ast.create_node_table_clause.is_some(),
+        ),
+        (
+            "CREATE REL TABLE",
+            query_ast.create_rel_table_clause.is_some(),
+        ),
+        ("CREATE INDEX", query_ast.create_index_clause.is_some()),
+        ("ADJ INDEX", query_ast.adj_index_clause.is_some()),
+        ("CREATE", query_ast.create_clause.is_some()),
+        ("SET", query_ast.set_clause.is_some()),
+        ("REMOVE", query_ast.remove_clause.is_some()),
+        ("DELETE", query_ast.delete_clause.is_some()),
+        ("RETURN", query_ast.return_clause.is_some()),
+        ("ORDER BY", query_ast.order_by_clause.is_some()),
+        ("SKIP", query_ast.skip_clause.is_some()),
+        ("LIMIT", query_ast.limit_clause.is_some()),
+    ];
+    let next_clause_idx = clauses
+        .iter()
+        .rposition(|(_, is_present)| *is_present)
+        .map_or(0, |idx| idx + 1);
+
+    clauses[next_clause_idx..]
+        .iter()
+        .map(|(keyword, _)| keyword.to_string())
+        .chain([";".to_string()])
+        .collect()
+}
+
 pub fn parse_query_with_nom(
     input: &'_ str,
 ) -> IResult<&'_ str, OpenCypherQueryAst<'_>, OpenCypherParsingError<'_>> {
+    let source = input;
     let (input, _) = multispace0.parse(input)?;
 
+    let (input, query_prefix): (&str, Option<QueryPrefix>) =
+        opt(parse_query_prefix).parse(input)?;
     let (input, match_clause): (&str, Option<MatchClause>) =
         opt(match_clause::parse_match_clause).parse(input)?;
     let (input, with_clause): (&str, Option<WithClause>) =
@@ -57,6 +124,10 @@ pub fn parse_query_with_nom(
         opt(create_node_table_clause::parse_create_node_table_clause).parse(input)?;
     let (input, create_rel_table_clause): (&str, Option<CreateRelTableClause>) =
         opt(create_rel_table_clause::parse_create_rel_table_clause).parse(input)?;
+    let (input, create_index_clause): (&str, Option<CreateIndexClause>) =
+        opt(create_index_clause::parse_create_index_clause).parse(input)?;
+    let (input, adj_index_clause): (&str, Option<AdjIndexClause>) =
+        opt(adj_index_clause::parse_adj_index_clause).parse(input)?;
     let (input, create_clause): (&str, Option<CreateClause>) =
         opt(create_clause::parse_create_clause).parse(input)?;
     let (input, set_clause): (&str, Option<SetClause>) =
@@ -75,12 +146,16 @@ pub fn parse_query_with_nom(
         opt(limit_clause::parse_limit_clause).parse(input)?;
 
     let cypher_query = OpenCypherQueryAst {
+        source,
+        query_prefix,
         match_clause,
         with_clause,
         where_clause,
         create_clause,
         create_node_table_clause,
         create_rel_table_clause,
+        create_index_clause,
+        adj_index_clause,
         set_clause,
         remove_clause,
         delete_clause,
@@ -99,11 +174,20 @@ pub fn parse_query(input: &'_ str) -> Result<OpenCypherQueryAst<'_>, OpenCypherP
         Ok((_, query_ast)) => Ok(query_ast),
         Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(e),
         Err(nom::Err::Incomplete(_)) => Err(OpenCypherParsingError {
-            errors: vec![("", "")],
+            errors: vec![(&input[input.len()..], "unexpected end of query")],
+            expected: vec![],
         }),
     }
 }
 
+// Parses a single expression outside of a query, e.g. the row filters of graph permissions.
+pub fn parse_standalone_expression(input: &'_ str) -> Option<ast::Expression<'_>> {
+    match expression::parse_expression(input.trim()) {
+        Ok((remaining, expression)) if remaining.trim().is_empty() => Some(expression),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{cell::RefCell, rc::Rc};
@@ -148,7 +232,7 @@ mod tests {
 
                 let match_clause = ast.match_clause.unwrap();
 
-                if let PathPattern::Node(node) = &match_clause.path_patterns[0] {
+                if let PathPattern::Node(node) = &match_clause.path_patterns[0].1 {
                     assert_eq!(node.name, Some("a"));
                 } else {
                     panic!("Expected MATCH clause to contain a Node pattern");
@@ -217,7 +301,7 @@ mod tests {
                 assert_eq!(remove_item.key, "temp");
 
                 let delete_clause = ast.delete_clause.unwrap();
-                assert_eq!(delete_clause.is_detach, false);
+                assert!(!delete_clause.is_detach);
                 assert_eq!(delete_clause.delete_items.len(), 1);
                 assert_eq!(delete_clause.delete_items[0], Expression::Variable("a"));
 
@@ -311,47 +395,50 @@ mod tests {
         let match_clause = query_ast.match_clause.unwrap();
 
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::ConnectedPattern(vec![
-                ConnectedPattern {
-                    start_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("david"),
-                        label: None,
-                        properties: Some(vec![Property::PropertyKV(PropertyKVPair {
-                            key: "name",
-                            value: Expression::Literal(Literal::String("David")),
-                        })]),
-                    })),
-                    relationship: RelationshipPattern {
-                        name: None,
-                        direction: Direction::Either,
-                        label: None,
-                        properties: None,
+            path_patterns: vec![(
+                None,
+                PathPattern::ConnectedPattern(vec![
+                    ConnectedPattern {
+                        start_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("david"),
+                            label: None,
+                            properties: Some(vec![Property::PropertyKV(PropertyKVPair {
+                                key: "name",
+                                value: Expression::Literal(Literal::String("David")),
+                            })]),
+                        })),
+                        relationship: RelationshipPattern {
+                            name: None,
+                            direction: Direction::Either,
+                            label: None,
+                            properties: None,
+                        },
+                        end_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("otherPerson"),
+                            label: None,
+                            properties: None,
+                        })),
                     },
-                    end_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("otherPerson"),
-                        label: None,
-                        properties: None,
-                    })),
-                },
-                ConnectedPattern {
-                    start_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("otherPerson"),
-                        label: None,
-                        properties: None,
-                    })),
-                    relationship: RelationshipPattern {
-                        name: None,
-                        direction: Direction::Outgoing,
-                        label: None,
-                        properties: None,
+                    ConnectedPattern {
+                        start_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("otherPerson"),
+                            label: None,
+                            properties: None,
+                        })),
+                        relationship: RelationshipPattern {
+                            name: None,
+                            direction: Direction::Outgoing,
+                            label: None,
+                            properties: None,
+                        },
+                        end_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("b"),
+                            label: None,
+                            properties: None,
+                        })),
                     },
-                    end_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("b"),
-                        label: None,
-                        properties: None,
-                    })),
-                },
-            ])],
+                ]),
+            )],
         };
 
         assert_eq!(match_clause, expected_match_clause);
@@ -480,49 +567,52 @@ mod tests {
         assert!(query_ast.match_clause.is_some(), "Expected MATCH clause");
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::ConnectedPattern(vec![
-                // (p:Person {name: 'Tom Hardy'})-[r:ACTED_IN]->(movie:Movie)
-                ConnectedPattern {
-                    start_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("p"),
-                        label: Some("Person"),
-                        properties: Some(vec![Property::PropertyKV(PropertyKVPair {
-                            key: "name",
-                            value: Expression::Literal(Literal::String("Tom Hardy")),
-                        })]),
-                    })),
-                    relationship: RelationshipPattern {
-                        name: Some("r"),
-                        direction: Direction::Outgoing,
-                        label: Some("ACTED_IN"),
-                        properties: None,
+            path_patterns: vec![(
+                None,
+                PathPattern::ConnectedPattern(vec![
+                    // (p:Person {name: 'Tom Hardy'})-[r:ACTED_IN]->(movie:Movie)
+                    ConnectedPattern {
+                        start_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("p"),
+                            label: Some("Person"),
+                            properties: Some(vec![Property::PropertyKV(PropertyKVPair {
+                                key: "name",
+                                value: Expression::Literal(Literal::String("Tom Hardy")),
+                            })]),
+                        })),
+                        relationship: RelationshipPattern {
+                            name: Some("r"),
+                            direction: Direction::Outgoing,
+                            label: Some("ACTED_IN"),
+                            properties: None,
+                        },
+                        end_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("movie"),
+                            label: Some("Movie"),
+                            properties: None,
+                        })),
                     },
-                    end_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("movie"),
-                        label: Some("Movie"),
-                        properties: None,
-                    })),
-                },
-                // (movie:Movie)<-[:DIRECTED]-(director:Person)
-                ConnectedPattern {
-                    start_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("movie"),
-                        label: Some("Movie"),
-                        properties: None,
-                    })),
-                    relationship: RelationshipPattern {
-                        name: None,
-                        direction: Direction::Incoming,
-                        label: Some("DIRECTED"),
-                        properties: None,
+                    // (movie:Movie)<-[:DIRECTED]-(director:Person)
+                    ConnectedPattern {
+                        start_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("movie"),
+                            label: Some("Movie"),
+                            properties: None,
+                        })),
+                        relationship: RelationshipPattern {
+                            name: None,
+                            direction: Direction::Incoming,
+                            label: Some("DIRECTED"),
+                            properties: None,
+                        },
+                        end_node: Rc::new(RefCell::new(NodePattern {
+                            name: Some("director"),
+                            label: Some("Person"),
+                            properties: None,
+                        })),
                     },
-                    end_node: Rc::new(RefCell::new(NodePattern {
-                        name: Some("director"),
-                        label: Some("Person"),
-                        properties: None,
-                    })),
-                },
-            ])],
+                ]),
+            )],
         };
         assert_eq!(match_clause, expected_match_clause);
 
@@ -647,16 +737,22 @@ mod tests {
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
             path_patterns: vec![
-                PathPattern::Node(NodePattern {
-                    name: Some("a"),
-                    label: Some("Person"),
-                    properties: None,
-                }),
-                PathPattern::Node(NodePattern {
-                    name: Some("b"),
-                    label: Some("Person"),
-                    properties: None,
-                }),
+                (
+                    None,
+                    PathPattern::Node(NodePattern {
+                        name: Some("a"),
+                        label: Some("Person"),
+                        properties: None,
+                    }),
+                ),
+                (
+                    None,
+                    PathPattern::Node(NodePattern {
+                        name: Some("b"),
+                        label: Some("Person"),
+                        properties: None,
+                    }),
+                ),
             ],
         };
         assert_eq!(match_clause, expected_match_clause);
@@ -775,14 +871,17 @@ mod tests {
         assert!(query_ast.match_clause.is_some(), "Expected MATCH clause");
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::Node(NodePattern {
-                name: Some("n"),
-                label: None,
-                properties: Some(vec![Property::PropertyKV(PropertyKVPair {
-                    key: "name",
-                    value: Expression::Literal(Literal::String("Andres")),
-                })]),
-            })],
+            path_patterns: vec![(
+                None,
+                PathPattern::Node(NodePattern {
+                    name: Some("n"),
+                    label: None,
+                    properties: Some(vec![Property::PropertyKV(PropertyKVPair {
+                        key: "name",
+                        value: Expression::Literal(Literal::String("Andres")),
+                    })]),
+                }),
+            )],
         };
         assert_eq!(match_clause, expected_match_clause);
 
@@ -864,14 +963,17 @@ mod tests {
         assert!(query_ast.match_clause.is_some(), "Expected MATCH clause");
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::Node(NodePattern {
-                name: Some("n"),
-                label: None,
-                properties: Some(vec![Property::PropertyKV(PropertyKVPair {
-                    key: "name",
-                    value: Expression::Literal(Literal::String("Andres")),
-                })]),
-            })],
+            path_patterns: vec![(
+                None,
+                PathPattern::Node(NodePattern {
+                    name: Some("n"),
+                    label: None,
+                    properties: Some(vec![Property::PropertyKV(PropertyKVPair {
+                        key: "name",
+                        value: Expression::Literal(Literal::String("Andres")),
+                    })]),
+                }),
+            )],
         };
         assert_eq!(match_clause, expected_match_clause);
 
@@ -934,14 +1036,17 @@ mod tests {
         assert!(query_ast.match_clause.is_some(), "Expected MATCH clause");
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::Node(NodePattern {
-                name: Some("andres"),
-                label: None,
-                properties: Some(vec![Property::PropertyKV(PropertyKVPair {
-                    key: "name",
-                    value: Expression::Literal(Literal::String("Andres")),
-                })]),
-            })],
+            path_patterns: vec![(
+                None,
+                PathPattern::Node(NodePattern {
+                    name: Some("andres"),
+                    label: None,
+                    properties: Some(vec![Property::PropertyKV(PropertyKVPair {
+                        key: "name",
+                        value: Expression::Literal(Literal::String("Andres")),
+                    })]),
+                }),
+            )],
         };
         assert_eq!(match_clause, expected_match_clause);
 
@@ -1018,11 +1123,14 @@ mod tests {
         assert!(query_ast.match_clause.is_some(), "Expected MATCH clause");
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::Node(NodePattern {
-                name: Some("p"),
-                label: Some("Person"),
-                properties: None,
-            })],
+            path_patterns: vec![(
+                None,
+                PathPattern::Node(NodePattern {
+                    name: Some("p"),
+                    label: Some("Person"),
+                    properties: None,
+                }),
+            )],
         };
         assert_eq!(match_clause, expected_match_clause);
 
@@ -1139,11 +1247,14 @@ mod tests {
         assert!(query_ast.match_clause.is_some(), "Expected MATCH clause");
         let match_clause = query_ast.match_clause.unwrap();
         let expected_match_clause = MatchClause {
-            path_patterns: vec![PathPattern::Node(NodePattern {
-                name: Some("p"),
-                label: Some("Person"),
-                properties: None,
-            })],
+            path_patterns: vec![(
+                None,
+                PathPattern::Node(NodePattern {
+                    name: Some("p"),
+                    label: Some("Person"),
+                    properties: None,
+                }),
+            )],
         };
         assert_eq!(match_clause, expected_match_clause);
 
@@ -1275,16 +1386,19 @@ mod tests {
         let create_node_table_clause = query_ast.create_node_table_clause.unwrap();
         let expected_created_node_table_clause = CreateNodeTableClause {
             table_name: "Product",
+            on_cluster: None,
             table_schema: vec![
                 ColumnSchema {
                     column_name: "title",
                     column_dtype: "STRING",
                     default_value: None,
+                    codec: None,
                 },
                 ColumnSchema {
                     column_name: "price",
                     column_dtype: "INT64",
                     default_value: None,
+                    codec: None,
                 },
             ],
             table_properties: vec![Expression::FunctionCallExp(FunctionCall {
@@ -1309,6 +1423,7 @@ mod tests {
 
         let expected_create_rel_table_clause = CreateRelTableClause {
             table_name: "Follows",
+            on_cluster: None,
             from: "User",
             to: "User",
             table_schema: vec![
@@ -1316,11 +1431,13 @@ mod tests {
                     column_name: "since",
                     column_dtype: "DATE",
                     default_value: None,
+                    codec: None,
                 },
                 ColumnSchema {
                     column_name: "age",
                     column_dtype: "INT64",
                     default_value: None,
+                    codec: None,
                 },
             ],
             table_properties: vec![Expression::FunctionCallExp(FunctionCall {
@@ -1331,4 +1448,20 @@ mod tests {
 
         assert_eq!(create_rel_table_clause, expected_create_rel_table_clause);
     }
+
+    #[test]
+    fn test_parse_query_prefix() {
+        let query_ast = parse_query("EXPLAIN MATCH (a) RETURN a;").unwrap();
+        assert_eq!(query_ast.query_prefix, Some(QueryPrefix::Explain));
+        assert!(query_ast.match_clause.is_some());
+
+        let query_ast = parse_query("profile\nMATCH (a) RETURN a;").unwrap();
+        assert_eq!(query_ast.query_prefix, Some(QueryPrefix::Profile));
+
+        let query_ast = parse_query("MATCH (a) RETURN a;").unwrap();
+        assert_eq!(query_ast.query_prefix, None);
+
+        // prefix must be followed by the query
+        assert!(parse_query("EXPLAINMATCH (a) RETURN a;").is_err());
+    }
 }
@@ -37,6 +37,7 @@ pub fn parse_remove_clause(
 fn remove_item_parser(
     input: &'_ str,
 ) -> IResult<&'_ str, PropertyAccess<'_>, OpenCypherParsingError<'_>> {
+    let expression_input = input;
     let (input, expression) = parse_expression.parse(input).map_err(|e| match e {
         nom::Err::Incomplete(needed) => nom::Err::Incomplete(needed),
         nom::Err::Error(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
@@ -49,9 +50,10 @@ fn remove_item_parser(
         // return error
         Err(nom::Err::Failure(OpenCypherParsingError {
             errors: vec![(
+                expression_input,
                 "Value of remove clause should be property access",
-                "Error in remove clause",
             )],
+            expected: vec!["property access".to_string()],
         }))
     }
 }
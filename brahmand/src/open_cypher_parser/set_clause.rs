@@ -37,6 +37,7 @@ pub fn parse_set_clause(
 fn set_item_parser(
     input: &'_ str,
 ) -> IResult<&'_ str, OperatorApplication<'_>, OpenCypherParsingError<'_>> {
+    let expression_input = input;
     let (input, expression) = parse_expression.parse(input).map_err(|e| match e {
         nom::Err::Incomplete(needed) => nom::Err::Incomplete(needed),
         nom::Err::Error(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
@@ -49,9 +50,10 @@ fn set_item_parser(
         // return error
         Err(nom::Err::Failure(OpenCypherParsingError {
             errors: vec![(
+                expression_input,
                 "Value of set clause should be binary application",
-                "Error in set clause",
             )],
+            expected: vec!["property assignment".to_string()],
         }))
     }
 }
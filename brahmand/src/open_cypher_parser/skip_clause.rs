@@ -10,10 +10,10 @@ use super::{
 pub fn parse_skip_clause(
     input: &'_ str,
 ) -> IResult<&'_ str, SkipClause, OpenCypherParsingError<'_>> {
-    let (input, _) = ws(tag_no_case("SKIP")).parse(input)?;
+    let (expression_input, _) = ws(tag_no_case("SKIP")).parse(input)?;
 
     let (input, expression) = context("Error in skip clause", cut(parse_expression))
-        .parse(input)
+        .parse(expression_input)
         .map_err(|e| match e {
             nom::Err::Incomplete(needed) => nom::Err::Incomplete(needed),
             nom::Err::Error(err) => nom::Err::Failure(OpenCypherParsingError::from(err)),
@@ -27,10 +27,8 @@ pub fn parse_skip_clause(
     } else {
         // return error
         Err(nom::Err::Failure(OpenCypherParsingError {
-            errors: vec![(
-                "Value of skip clause should be integer",
-                "Error in skip clause",
-            )],
+            errors: vec![(expression_input, "Value of skip clause should be integer")],
+            expected: vec!["integer".to_string()],
         }))
     }
 }
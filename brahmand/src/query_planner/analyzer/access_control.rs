@@ -0,0 +1,340 @@
+use std::sync::Arc;
+
+use crate::{
+    graph_catalog::graph_permissions::{ElementPermissions, GraphPermissions, referenced_columns},
+    query_planner::{
+        analyzer::{
+            analyzer_pass::{AnalyzerPass, AnalyzerResult},
+            errors::{AnalyzerError, Pass},
+        },
+        logical_expr::{LogicalExpr, PropertyAccess},
+        logical_plan::LogicalPlan,
+        plan_ctx::{PlanCtx, TableCtx},
+        transformed::Transformed,
+    },
+};
+
+// Enforces the graph permissions of the caller. Every node label and relationship type of the
+// query has to be readable and no hidden property can be used. Row filters of the readable labels
+// are tagged to their tables like the predicates of a WHERE clause.
+pub struct AccessControl;
+
+impl AnalyzerPass for AccessControl {
+    fn analyze(
+        &self,
+        logical_plan: Arc<LogicalPlan>,
+        plan_ctx: &mut PlanCtx,
+    ) -> AnalyzerResult<Transformed<Arc<LogicalPlan>>> {
+        let Some(permissions) = plan_ctx.get_permissions().cloned() else {
+            return Ok(Transformed::No(logical_plan));
+        };
+
+        for (alias, table_ctx) in plan_ctx.get_alias_table_ctx_map() {
+            if let Some(element_permissions) = Self::element_permissions(table_ctx, &permissions)? {
+                Self::check_table_ctx(alias, table_ctx, element_permissions)?;
+            }
+        }
+        Self::check_plan(&logical_plan, plan_ctx, &permissions)?;
+
+        for table_ctx in plan_ctx.get_mut_alias_table_ctx_map().values_mut() {
+            let Some(element_permissions) = Self::element_permissions(table_ctx, &permissions)?
+            else {
+                continue;
+            };
+            let row_filter = element_permissions.row_filter_expr().map_err(|message| {
+                AnalyzerError::InvalidPermissions {
+                    pass: Pass::AccessControl,
+                    message,
+                }
+            })?;
+            if let Some(row_filter) = row_filter {
+                table_ctx.insert_filter(row_filter);
+                // same as the filters of a WHERE clause, filtered relationships use the edge list
+                if table_ctx.is_relation() {
+                    table_ctx.set_use_edge_list(true);
+                }
+            }
+        }
+
+        Ok(Transformed::No(logical_plan))
+    }
+}
+
+impl AccessControl {
+    pub fn new() -> Self {
+        AccessControl
+    }
+
+    fn element_permissions<'a>(
+        table_ctx: &TableCtx,
+        permissions: &'a GraphPermissions,
+    ) -> AnalyzerResult<Option<&'a ElementPermissions>> {
+        let Some(label) = table_ctx.get_label_opt() else {
+            return Ok(None);
+        };
+        let (elements, element) = if table_ctx.is_relation() {
+            (
+                &permissions.relationships,
+                format!("relationship `{}`", label),
+            )
+        } else {
+            (&permissions.nodes, format!("label `{}`", label))
+        };
+        elements
+            .get(&label)
+            .map(Some)
+            .ok_or(AnalyzerError::AccessDenied {
+                pass: Pass::AccessControl,
+                element,
+            })
+    }
+
+    // Filters and projections tagged to the table by the previous passes.
+    fn check_table_ctx(
+        alias: &str,
+        table_ctx: &TableCtx,
+        element_permissions: &ElementPermissions,
+    ) -> AnalyzerResult<()> {
+        let filter_columns = table_ctx
+            .get_filters()
+            .iter()
+            .flat_map(referenced_columns)
+            .map(|column| column.0.as_str());
+        let projection_columns = table_ctx
+            .get_projections()
+            .iter()
+            .flat_map(|item| property_accesses(&item.expression))
+            .filter(|property_access| property_access.table_alias.0 == alias)
+            .map(|property_access| property_access.column.0.as_str());
+
+        for column in filter_columns.chain(projection_columns) {
+            if element_permissions.hidden_properties.contains(column) {
+                return Err(Self::hidden_property(table_ctx, column));
+            }
+        }
+        Ok(())
+    }
+
+    // Expressions which are kept in the plan e.g. RETURN items and multi table conditions.
+    fn check_plan(
+        logical_plan: &LogicalPlan,
+        plan_ctx: &PlanCtx,
+        permissions: &GraphPermissions,
+    ) -> AnalyzerResult<()> {
+        let (inputs, expressions): (Vec<&Arc<LogicalPlan>>, Vec<&LogicalExpr>) = match logical_plan
+        {
+            LogicalPlan::Empty | LogicalPlan::Scan(_) => (vec![], vec![]),
+            LogicalPlan::GraphNode(graph_node) => (vec![&graph_node.input], vec![]),
+            LogicalPlan::GraphRel(graph_rel) => (
+                vec![&graph_rel.left, &graph_rel.center, &graph_rel.right],
+                vec![],
+            ),
+            LogicalPlan::Cte(cte) => (vec![&cte.input], vec![]),
+            LogicalPlan::GraphJoins(graph_joins) => (vec![&graph_joins.input], vec![]),
+            LogicalPlan::Filter(filter) => (vec![&filter.input], vec![&filter.predicate]),
+            LogicalPlan::Projection(projection) => (
+                vec![&projection.input],
+                projection
+                    .items
+                    .iter()
+                    .map(|item| &item.expression)
+                    .collect(),
+            ),
+            LogicalPlan::GroupBy(group_by) => {
+                (vec![&group_by.input], group_by.expressions.iter().collect())
+            }
+            LogicalPlan::OrderBy(order_by) => (
+                vec![&order_by.input],
+                order_by.items.iter().map(|item| &item.expression).collect(),
+            ),
+            LogicalPlan::Skip(skip) => (vec![&skip.input], vec![]),
+            LogicalPlan::Limit(limit) => (vec![&limit.input], vec![]),
+            LogicalPlan::Union(union) => (union.inputs.iter().collect(), vec![]),
+        };
+
+        for expression in expressions {
+            if let LogicalExpr::InSubquery(in_subquery) = expression {
+                Self::check_plan(&in_subquery.subplan, plan_ctx, permissions)?;
+            }
+            for property_access in property_accesses(expression) {
+                let Some(table_ctx) = plan_ctx
+                    .get_alias_table_ctx_map()
+                    .get(&property_access.table_alias.0)
+                else {
+                    continue;
+                };
+                if let Some(element_permissions) =
+                    Self::element_permissions(table_ctx, permissions)?
+                {
+                    let column = &property_access.column.0;
+                    if element_permissions.hidden_properties.contains(column) {
+                        return Err(Self::hidden_property(table_ctx, column));
+                    }
+                }
+            }
+        }
+
+        for input in inputs {
+            Self::check_plan(input, plan_ctx, permissions)?;
+        }
+        Ok(())
+    }
+
+    fn hidden_property(table_ctx: &TableCtx, column: &str) -> AnalyzerError {
+        AnalyzerError::AccessDenied {
+            pass: Pass::AccessControl,
+            element: format!(
+                "property `{}.{}`",
+                table_ctx.get_label_opt().unwrap_or_default(),
+                column
+            ),
+        }
+    }
+}
+
+fn property_accesses(expr: &LogicalExpr) -> Vec<&PropertyAccess> {
+    match expr {
+        LogicalExpr::PropertyAccessExp(property_access) => vec![property_access],
+        LogicalExpr::OperatorApplicationExp(op_app) => {
+            op_app.operands.iter().flat_map(property_accesses).collect()
+        }
+        LogicalExpr::ScalarFnCall(fc) => fc.args.iter().flat_map(property_accesses).collect(),
+        LogicalExpr::AggregateFnCall(fc) => fc.args.iter().flat_map(property_accesses).collect(),
+        LogicalExpr::List(exprs) => exprs.iter().flat_map(property_accesses).collect(),
+        LogicalExpr::InSubquery(in_subquery) => property_accesses(&in_subquery.expr),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query_planner::{
+        logical_expr::{Column, Literal, Operator, OperatorApplication, TableAlias},
+        logical_plan::{Projection, ProjectionItem},
+    };
+    use serde_json::json;
+
+    fn setup_plan_ctx() -> PlanCtx {
+        let mut plan_ctx = PlanCtx::default();
+        plan_ctx.insert_table_ctx(
+            "u".to_string(),
+            TableCtx::build(
+                "u".to_string(),
+                Some("User".to_string()),
+                vec![],
+                false,
+                true,
+            ),
+        );
+        plan_ctx.insert_table_ctx(
+            "f".to_string(),
+            TableCtx::build(
+                "f".to_string(),
+                Some("FOLLOWS".to_string()),
+                vec![],
+                true,
+                true,
+            ),
+        );
+        plan_ctx.set_permissions(
+            serde_json::from_value(json!({
+                "nodes": {"User": {"hidden_properties": ["email"], "row_filter": "n.country = 'IN'"}},
+                "relationships": {"FOLLOWS": {}},
+            }))
+            .unwrap(),
+        );
+        plan_ctx
+    }
+
+    fn projection(column: &str) -> Arc<LogicalPlan> {
+        Arc::new(LogicalPlan::Projection(Projection {
+            input: Arc::new(LogicalPlan::Empty),
+            items: vec![ProjectionItem {
+                expression: LogicalExpr::PropertyAccessExp(PropertyAccess {
+                    table_alias: TableAlias("u".to_string()),
+                    column: Column(column.to_string()),
+                }),
+                col_alias: None,
+            }],
+        }))
+    }
+
+    #[test]
+    fn test_row_filter_is_tagged() {
+        let mut plan_ctx = setup_plan_ctx();
+        AccessControl::new()
+            .analyze(projection("name"), &mut plan_ctx)
+            .unwrap();
+
+        assert_eq!(
+            plan_ctx.get_table_ctx("u").unwrap().get_filters(),
+            &vec![LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::Equal,
+                operands: vec![
+                    LogicalExpr::Column(Column("country".to_string())),
+                    LogicalExpr::Literal(Literal::String("IN".to_string())),
+                ],
+            })]
+        );
+        assert!(
+            plan_ctx
+                .get_table_ctx("f")
+                .unwrap()
+                .get_filters()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_access_denied() {
+        let mut plan_ctx = setup_plan_ctx();
+        assert_eq!(
+            AccessControl::new()
+                .analyze(projection("email"), &mut plan_ctx)
+                .err(),
+            Some(AnalyzerError::AccessDenied {
+                pass: Pass::AccessControl,
+                element: "property `User.email`".to_string(),
+            })
+        );
+
+        // WHERE u.email = '..' is tagged to the table as a column filter
+        let mut plan_ctx = setup_plan_ctx();
+        plan_ctx.get_mut_table_ctx("u").unwrap().insert_filter(
+            LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::Equal,
+                operands: vec![
+                    LogicalExpr::Column(Column("email".to_string())),
+                    LogicalExpr::Literal(Literal::String("a@b.c".to_string())),
+                ],
+            }),
+        );
+        assert!(
+            AccessControl::new()
+                .analyze(projection("name"), &mut plan_ctx)
+                .is_err()
+        );
+
+        let mut plan_ctx = setup_plan_ctx();
+        plan_ctx.insert_table_ctx(
+            "p".to_string(),
+            TableCtx::build(
+                "p".to_string(),
+                Some("PAYS".to_string()),
+                vec![],
+                true,
+                true,
+            ),
+        );
+        assert_eq!(
+            AccessControl::new()
+                .analyze(projection("name"), &mut plan_ctx)
+                .err(),
+            Some(AnalyzerError::AccessDenied {
+                pass: Pass::AccessControl,
+                element: "relationship `PAYS`".to_string(),
+            })
+        );
+    }
+}
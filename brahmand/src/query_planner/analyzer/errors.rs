@@ -8,6 +8,7 @@ use crate::{
 
 #[derive(Debug, Clone, Error, PartialEq)]
 pub enum Pass {
+    AccessControl,
     // DuplicateScansRemoving,
     FilterTagging,
     GraphJoinInference,
@@ -22,6 +23,7 @@ pub enum Pass {
 impl Display for Pass {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Pass::AccessControl => write!(f, "AccessControl"),
             Pass::FilterTagging => write!(f, "FilterTagging"),
             // Pass::DuplicateScansRemoving => write!(f, "DuplicateScansRemoving"),
             Pass::GraphJoinInference => write!(f, "GraphJoinInference"),
@@ -68,4 +70,10 @@ pub enum AnalyzerError {
 
     #[error("Invalid relation query - {rel}")]
     InvalidRelationInQuery { rel: String },
+
+    #[error("Access denied, {element} can not be read.")]
+    AccessDenied { pass: Pass, element: String },
+
+    #[error(" {pass}: {message}")]
+    InvalidPermissions { pass: Pass, message: String },
 }
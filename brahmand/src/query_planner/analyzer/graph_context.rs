@@ -37,6 +37,16 @@ pub struct GraphRelContext<'a> {
     pub cte_name: String, // id_column: String,
 }
 
+impl GraphContext<'_> {
+    // Subqueries and joins over distributed tables must be GLOBAL, otherwise every shard runs
+    // them against all the shards again.
+    pub fn is_distributed(&self) -> bool {
+        self.left.schema.engine.is_distributed()
+            || self.rel.schema.engine.is_distributed()
+            || self.right.schema.engine.is_distributed()
+    }
+}
+
 pub fn get_graph_context<'a>(
     graph_rel: &'a GraphRel,
     plan_ctx: &'a mut PlanCtx,
@@ -291,6 +291,7 @@ impl GraphJoinInference {
         collected_graph_joins: &mut Vec<Join>,
         joined_entities: &mut HashSet<String>,
     ) -> AnalyzerResult<()> {
+        let global = graph_context.is_distributed();
         let left_alias = graph_context.left.alias;
         let rel_alias = graph_context.rel.alias;
         let right_alias = graph_context.right.alias;
@@ -300,14 +301,16 @@ impl GraphJoinInference {
         let right_cte_name = graph_context.right.cte_name;
 
         // If both nodes are of the same type then check the direction to determine where are the left and right nodes present in the edgelist.
+        // Right node is the start node of the pattern. For either direction, the rel cte is a union of both orientations
+        // with the right node at "from_id", so it is joined in the same way as outgoing.
         if graph_context.left.schema.table_name == graph_context.right.schema.table_name {
             if joined_entities.contains(right_alias) {
                 // join the rel with right first and then join the left with rel
                 let (rel_conn_with_right_node, left_conn_with_rel) =
                     if graph_rel.direction == Direction::Incoming {
-                        ("from_id".to_string(), "to_id".to_string())
-                    } else {
                         ("to_id".to_string(), "from_id".to_string())
+                    } else {
+                        ("from_id".to_string(), "to_id".to_string())
                     };
                 let mut rel_graph_join = Join {
                     table_name: rel_cte_name,
@@ -326,6 +329,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 let left_graph_join = Join {
@@ -345,6 +349,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 if is_standalone_rel {
@@ -406,6 +411,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 let right_graph_join = Join {
@@ -425,6 +431,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 if is_standalone_rel {
@@ -482,6 +489,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 let left_graph_join = Join {
@@ -501,6 +509,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 if is_standalone_rel {
@@ -554,6 +563,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 let right_graph_join = Join {
@@ -573,6 +583,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 if is_standalone_rel {
@@ -628,6 +639,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 let left_graph_join = Join {
@@ -647,6 +659,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 if is_standalone_rel {
@@ -700,6 +713,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 let right_graph_join = Join {
@@ -719,6 +733,7 @@ impl GraphJoinInference {
                         ],
                     }],
                     join_type: JoinType::Inner,
+                    global,
                 };
 
                 if is_standalone_rel {
@@ -763,6 +778,7 @@ impl GraphJoinInference {
         collected_graph_joins: &mut Vec<Join>,
         joined_entities: &mut HashSet<String>,
     ) -> AnalyzerResult<()> {
+        let global = graph_context.is_distributed();
         let left_alias = graph_context.left.alias;
         let rel_alias = graph_context.rel.alias;
         let right_alias = graph_context.right.alias;
@@ -792,6 +808,7 @@ impl GraphJoinInference {
                     ],
                 }],
                 join_type: JoinType::Inner,
+                global,
             };
 
             let left_graph_join = Join {
@@ -811,6 +828,7 @@ impl GraphJoinInference {
                     ],
                 }],
                 join_type: JoinType::Inner,
+                global,
             };
 
             if is_standalone_rel {
@@ -864,6 +882,7 @@ impl GraphJoinInference {
                     ],
                 }],
                 join_type: JoinType::Inner,
+                global,
             };
 
             let right_graph_join = Join {
@@ -883,6 +902,7 @@ impl GraphJoinInference {
                     ],
                 }],
                 join_type: JoinType::Inner,
+                global,
             };
 
             if is_standalone_rel {
@@ -922,7 +942,9 @@ impl GraphJoinInference {
 mod tests {
     use super::*;
     use crate::{
-        graph_catalog::graph_schema::{GraphSchema, NodeIdSchema, NodeSchema, RelationshipSchema},
+        graph_catalog::graph_schema::{
+            GraphSchema, NodeIdSchema, NodeSchema, RelationshipSchema, TableEngineSchema,
+        },
         query_planner::{
             logical_expr::{Column, Direction, LogicalExpr, Operator, PropertyAccess, TableAlias},
             logical_plan::{
@@ -948,6 +970,7 @@ mod tests {
                     column: "id".to_string(),
                     dtype: "UInt64".to_string(),
                 },
+                engine: TableEngineSchema::default(),
             },
         );
 
@@ -962,6 +985,7 @@ mod tests {
                     column: "id".to_string(),
                     dtype: "UInt64".to_string(),
                 },
+                engine: TableEngineSchema::default(),
             },
         );
 
@@ -979,6 +1003,7 @@ mod tests {
                 to_node: "Person".to_string(),
                 from_node_id_dtype: "UInt64".to_string(),
                 to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
             },
         );
 
@@ -996,6 +1021,7 @@ mod tests {
                 to_node: "Company".to_string(),
                 from_node_id_dtype: "UInt64".to_string(),
                 to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
             },
         );
 
@@ -1994,4 +2020,89 @@ mod tests {
             _ => panic!("Expected transformation"),
         }
     }
+
+    #[test]
+    fn test_either_direction_edge_list() {
+        let analyzer = GraphJoinInference::new();
+        let graph_schema = create_test_graph_schema();
+        let mut plan_ctx = setup_plan_ctx_with_graph_entities();
+
+        plan_ctx
+            .get_mut_table_ctx("f1")
+            .unwrap()
+            .set_use_edge_list(true);
+
+        // Create plan: (p1)-[f1:FOLLOWS]-(p2)
+        let p1_node = create_graph_node(create_scan_plan("p1", "Person"), "p1");
+        let f1_scan = create_scan_plan("f1", "FOLLOWS");
+        let p2_node = create_graph_node(create_scan_plan("p2", "Person"), "p2");
+
+        let graph_rel = create_graph_rel(
+            p2_node,
+            f1_scan,
+            p1_node,
+            "f1",
+            Direction::Either,
+            "p2",
+            "p1",
+        );
+        let input_logical_plan = Arc::new(LogicalPlan::Projection(Projection {
+            input: graph_rel,
+            items: vec![ProjectionItem {
+                expression: LogicalExpr::PropertyAccessExp(PropertyAccess {
+                    table_alias: TableAlias("p2".to_string()),
+                    column: Column("name".to_string()),
+                }),
+                col_alias: None,
+            }],
+        }));
+
+        let result = analyzer
+            .analyze_with_graph_schema(input_logical_plan, &mut plan_ctx, &graph_schema)
+            .unwrap();
+
+        // The either direction rel cte has both orientations with the start node (p1) at from_id.
+        let Transformed::Yes(plan) = result else {
+            panic!("Expected transformation");
+        };
+        let LogicalPlan::GraphJoins(graph_joins) = plan.as_ref() else {
+            panic!("Expected GraphJoins node");
+        };
+        assert_eq!(graph_joins.joins.len(), 2);
+
+        let join_columns: Vec<(String, String, String, String)> = graph_joins
+            .joins
+            .iter()
+            .map(|join| match &join.joining_on[0].operands[..] {
+                [
+                    LogicalExpr::PropertyAccessExp(first),
+                    LogicalExpr::PropertyAccessExp(second),
+                ] => (
+                    first.table_alias.0.clone(),
+                    first.column.0.clone(),
+                    second.table_alias.0.clone(),
+                    second.column.0.clone(),
+                ),
+                _ => panic!("Expected PropertyAccessExp operands"),
+            })
+            .collect();
+
+        assert_eq!(
+            join_columns,
+            vec![
+                (
+                    "f1".to_string(),
+                    "to_id".to_string(),
+                    "p2".to_string(),
+                    "id".to_string()
+                ),
+                (
+                    "p1".to_string(),
+                    "id".to_string(),
+                    "f1".to_string(),
+                    "from_id".to_string()
+                ),
+            ]
+        );
+    }
 }
@@ -8,7 +8,10 @@ use crate::{
             errors::Pass,
             graph_context::{self, GraphContext},
         },
-        logical_expr::{Column, ColumnAlias, Direction, InSubquery, LogicalExpr, PropertyAccess},
+        logical_expr::{
+            Column, ColumnAlias, Direction, InSubquery, LogicalExpr, Operator, OperatorApplication,
+            PropertyAccess,
+        },
         logical_plan::{
             self, {Cte, GraphRel, LogicalPlan, Projection, ProjectionItem, Scan, Union, UnionType},
         },
@@ -290,16 +293,17 @@ impl GraphTRaversalPlanning {
         right_projections: Vec<ProjectionItem>,
         is_anchor_traversal: bool,
     ) -> AnalyzerResult<(GraphRel, Vec<CtxToUpdate>)> {
+        let global = graph_context.is_distributed();
         let mut ctxs_to_update: Vec<CtxToUpdate> = vec![];
 
         let mut rel_ctxs_to_update: Vec<CtxToUpdate>;
 
+        // anchor relation is scanned as it is, in case of either direction the reversed scan only skips self-loops
         let (r_cte_name, r_plan, r_ctxs_to_update) = self.get_rel_ctx_for_edge_list(
             graph_rel,
             &graph_context,
             graph_context.right.cte_name.clone(),
             graph_context.right.id_column.clone(),
-            graph_rel.is_rel_anchor,
         );
         let rel_cte_name: String = r_cte_name;
         rel_ctxs_to_update = r_ctxs_to_update;
@@ -308,22 +312,20 @@ impl GraphTRaversalPlanning {
         // when using edge list, we need to check which node joins to "from_id" and which node joins to "to_id" of the relationship.
         // Based on that we decide, how the left and right nodes are connected with relationship in subqueries.
         let (right_sub_plan_column, left_sub_plan_column) =
-            if graph_context.rel.schema.from_node == graph_context.right.schema.table_name {
-                ("from_id".to_string(), "to_id".to_string())
-            } else {
-                ("to_id".to_string(), "from_id".to_string())
-            };
+            self.get_edge_list_node_columns(graph_rel, &graph_context);
 
         let right_insubquery: LogicalExpr = self.build_insubquery(
             graph_context.right.id_column.clone(),
             rel_cte_name.clone(),
             right_sub_plan_column,
+            global,
         );
 
         let left_insubquery: LogicalExpr = self.build_insubquery(
             graph_context.left.id_column,
             rel_cte_name.clone(),
             left_sub_plan_column,
+            global,
         );
 
         if graph_rel.is_rel_anchor {
@@ -337,8 +339,6 @@ impl GraphTRaversalPlanning {
             };
             ctxs_to_update.push(right_ctx_to_update);
 
-            rel_ctxs_to_update.first_mut().unwrap().insubquery = None;
-
             ctxs_to_update.append(&mut rel_ctxs_to_update);
 
             let left_ctx_to_update = CtxToUpdate {
@@ -435,6 +435,7 @@ impl GraphTRaversalPlanning {
         right_projections: Vec<ProjectionItem>,
         is_anchor_traversal: bool,
     ) -> AnalyzerResult<(GraphRel, Vec<CtxToUpdate>)> {
+        let global = graph_context.is_distributed();
         let mut ctxs_to_update: Vec<CtxToUpdate> = vec![];
 
         let (rel_cte_name, rel_plan, mut rel_ctxs_to_update) = self.get_rel_ctx_for_bitmaps(
@@ -450,6 +451,7 @@ impl GraphTRaversalPlanning {
             graph_context.left.id_column,
             rel_cte_name.clone(),
             "to_id".to_string(),
+            global,
         );
         let left_ctx_to_update = CtxToUpdate {
             alias: graph_context.left.alias.to_string(),
@@ -512,8 +514,8 @@ impl GraphTRaversalPlanning {
         graph_context: &GraphContext,
         connected_node_cte_name: String,
         connected_node_id_column: String,
-        is_rel_anchor: bool,
     ) -> (String, Arc<LogicalPlan>, Vec<CtxToUpdate>) {
+        let global = graph_context.is_distributed();
         let star_found = graph_context
             .rel
             .table_ctx
@@ -522,13 +524,11 @@ impl GraphTRaversalPlanning {
             .any(|item| item.expression == LogicalExpr::Star);
 
         // if direction == Direction::Either and both nodes are of same types then use UNION of both.
-        // TODO - currently Either direction on anchor relation is not supported. FIX this
+        // Each edge is read once as it is and once reversed, so the connected node is always at "from_id".
+        // UNION ALL keeps mutual and parallel edges, the reversed read skips self-loops which the first one already has.
         if graph_rel.direction == Direction::Either
             && graph_context.left.label == graph_context.right.label
-            && !is_rel_anchor
         {
-            // let new_rel_label = format!("{}_{}", graph_context.rel.label, Direction::Either); //"Direction::Either);
-
             let rel_cte_name = format!("{}_{}", graph_context.rel.label, graph_context.rel.alias);
 
             let outgoing_alias = logical_plan::generate_id();
@@ -548,63 +548,59 @@ impl GraphTRaversalPlanning {
                         table_name: Some(graph_context.rel.label.clone()),
                     })),
                 ],
-                union_type: UnionType::Distinct,
+                union_type: UnionType::All,
             }));
 
-            let rel_insubquery: LogicalExpr = self.build_insubquery(
-                "from_id".to_string(),
-                connected_node_cte_name.clone(),
-                connected_node_id_column.clone(),
-            );
+            let rel_insubquery: Option<LogicalExpr> = (!graph_rel.is_rel_anchor).then(|| {
+                self.build_insubquery(
+                    "from_id".to_string(),
+                    connected_node_cte_name.clone(),
+                    connected_node_id_column.clone(),
+                    global,
+                )
+            });
 
-            let from_edge_proj_input: Vec<(String, Option<ColumnAlias>)> = if !star_found {
-                vec![
-                    (
-                        format!("from_{}", graph_context.rel.schema.from_node),
-                        Some(ColumnAlias("from_id".to_string())),
-                    ),
-                    (
-                        format!("to_{}", graph_context.rel.schema.to_node),
-                        Some(ColumnAlias("to_id".to_string())),
-                    ),
-                ]
-            } else {
-                vec![]
-            };
+            let from_edge_proj_input: Vec<(String, Option<ColumnAlias>)> = vec![
+                (
+                    format!("from_{}", graph_context.rel.schema.from_node),
+                    Some(ColumnAlias("from_id".to_string())),
+                ),
+                (
+                    format!("to_{}", graph_context.rel.schema.to_node),
+                    Some(ColumnAlias("to_id".to_string())),
+                ),
+            ];
 
-            let from_edge_projections = self.build_projections(from_edge_proj_input);
+            let from_edge_projections =
+                self.build_edge_projections(from_edge_proj_input, star_found);
 
             let from_edge_ctx_to_update = CtxToUpdate {
                 alias: outgoing_alias,
                 label: graph_context.rel.label.clone(),
                 projections: from_edge_projections,
-                insubquery: Some(rel_insubquery.clone()),
+                insubquery: rel_insubquery.clone(),
                 override_projections: false,
                 is_rel: true,
             };
 
-            let to_edge_proj_input: Vec<(String, Option<ColumnAlias>)> = if !star_found {
-                vec![
-                    (
-                        format!("to_{}", graph_context.rel.schema.from_node),
-                        Some(ColumnAlias("from_id".to_string())),
-                    ),
-                    (
-                        format!("from_{}", graph_context.rel.schema.to_node),
-                        Some(ColumnAlias("to_id".to_string())),
-                    ),
-                ]
-            } else {
-                vec![]
-            };
+            let to_edge_proj_input: Vec<(String, Option<ColumnAlias>)> = vec![
+                (
+                    format!("to_{}", graph_context.rel.schema.from_node),
+                    Some(ColumnAlias("from_id".to_string())),
+                ),
+                (
+                    format!("from_{}", graph_context.rel.schema.to_node),
+                    Some(ColumnAlias("to_id".to_string())),
+                ),
+            ];
 
-            let to_edge_projections = self.build_projections(to_edge_proj_input);
+            let to_edge_projections = self.build_edge_projections(to_edge_proj_input, star_found);
 
             let to_edge_ctx_to_update = CtxToUpdate {
                 alias: incoming_alias,
                 label: graph_context.rel.label.clone(),
                 projections: to_edge_projections,
-                insubquery: Some(rel_insubquery),
+                insubquery: Some(self.exclude_self_loops(rel_insubquery)),
                 override_projections: false,
                 is_rel: true,
             };
@@ -621,44 +617,33 @@ impl GraphTRaversalPlanning {
                 graph_context.rel.alias
             );
 
-            let rel_proj_input: Vec<(String, Option<ColumnAlias>)> = if !star_found {
-                vec![
-                    (
-                        format!("from_{}", graph_context.rel.schema.from_node),
-                        Some(ColumnAlias("from_id".to_string())),
-                    ),
-                    (
-                        format!("to_{}", graph_context.rel.schema.to_node),
-                        Some(ColumnAlias("to_id".to_string())),
-                    ),
-                ]
-            } else {
-                vec![]
-            };
+            // rel's table ctx already has the Star projection if present, so only from_id and to_id are appended.
+            let rel_proj_input: Vec<(String, Option<ColumnAlias>)> = vec![
+                (
+                    format!("from_{}", graph_context.rel.schema.from_node),
+                    Some(ColumnAlias("from_id".to_string())),
+                ),
+                (
+                    format!("to_{}", graph_context.rel.schema.to_node),
+                    Some(ColumnAlias("to_id".to_string())),
+                ),
+            ];
 
             let rel_projections = self.build_projections(rel_proj_input);
 
             // when using edge list, we need to check which node joins to "from_id" and which node joins to "to_id" of the relationship.
             // Based on that we decide, how the relationship is connected with right node as we traverse in graph traversal planning from right to left i.e. bottom to top.
-            // Relationship direction integrity is already checked during query validation. If there is wrong direction then plan won't come to this stage. So we don't have to check direction here.
-            let sub_in_expr_str =
-                if graph_context.rel.schema.from_node == graph_context.right.schema.table_name {
-                    "from_id".to_string()
-                } else {
-                    "to_id".to_string()
-                };
-
-            // let sub_in_expr_str = if graph_rel.direction == Direction::Outgoing {
-            //     "from_id".to_string()
-            // } else {
-            //     "to_id".to_string()
-            // };
-
-            let rel_insubquery = self.build_insubquery(
-                sub_in_expr_str,
-                connected_node_cte_name,
-                connected_node_id_column,
-            );
+            // Relationship direction integrity is already checked during query validation. If there is wrong direction then plan won't come to this stage.
+            let (sub_in_expr_str, _) = self.get_edge_list_node_columns(graph_rel, graph_context);
+
+            let rel_insubquery = (!graph_rel.is_rel_anchor).then(|| {
+                self.build_insubquery(
+                    sub_in_expr_str,
+                    connected_node_cte_name,
+                    connected_node_id_column,
+                    global,
+                )
+            });
 
             let rel_plan = graph_rel.center.clone();
 
@@ -666,7 +651,7 @@ impl GraphTRaversalPlanning {
                 alias: graph_context.rel.alias.to_string(),
                 label: graph_context.rel.label.clone(),
                 projections: rel_projections,
-                insubquery: Some(rel_insubquery),
+                insubquery: rel_insubquery,
                 override_projections: false,
                 is_rel: true,
             };
@@ -682,6 +667,7 @@ impl GraphTRaversalPlanning {
         connected_node_cte_name: String,
         connected_node_id_column: String,
     ) -> (String, Arc<LogicalPlan>, Vec<CtxToUpdate>) {
+        let global = graph_context.is_distributed();
         let rel_proj_input: Vec<(String, Option<ColumnAlias>)> = vec![
             ("from_id".to_string(), None),
             (
@@ -691,7 +677,9 @@ impl GraphTRaversalPlanning {
         ];
         let rel_projections = self.build_projections(rel_proj_input);
 
-        // if direction == Direction::Either and both nodes are of same types then use UNION of both.
+        // if direction == Direction::Either and both nodes are of same types then use UNION ALL of both indexes.
+        // A self-loop is in both of them, so it is removed from the expanded ids of the incoming one. The expanded
+        // value is compared explicitly as it is aliased to the name of the bitmap column.
         if graph_rel.direction == Direction::Either
             && graph_context.left.label == graph_context.right.label
         {
@@ -716,13 +704,14 @@ impl GraphTRaversalPlanning {
                         table_name: Some(incoming_label.clone()),
                     })),
                 ],
-                union_type: UnionType::Distinct,
+                union_type: UnionType::All,
             }));
 
             let rel_insubquery = self.build_insubquery(
                 "from_id".to_string(),
                 connected_node_cte_name,
                 connected_node_id_column,
+                global,
             );
 
             let outgoing_ctx_to_update = CtxToUpdate {
@@ -734,10 +723,18 @@ impl GraphTRaversalPlanning {
                 is_rel: true,
             };
 
+            let incoming_proj_input: Vec<(String, Option<ColumnAlias>)> = vec![
+                ("from_id".to_string(), None),
+                (
+                    "arrayJoin(arrayFilter(x -> x != from_id, bitmapToArray(to_id)))".to_string(),
+                    Some(ColumnAlias("to_id".to_string())),
+                ),
+            ];
+
             let incoming_ctx_to_update = CtxToUpdate {
                 alias: incoming_alias.clone(),
                 label: incoming_label,
-                projections: rel_projections.clone(),
+                projections: self.build_projections(incoming_proj_input),
                 insubquery: Some(rel_insubquery),
                 override_projections: false,
                 is_rel: true,
@@ -789,6 +786,7 @@ impl GraphTRaversalPlanning {
                 "from_id".to_string(),
                 connected_node_cte_name,
                 connected_node_id_column,
+                global,
             );
 
             let rel_plan = graph_rel.center.clone();
@@ -806,6 +804,46 @@ impl GraphTRaversalPlanning {
         }
     }
 
+    // Returns the edge list columns to which the right and the left node of the graph_rel are connected respectively.
+    // If both nodes are of same type then the direction decides it. Either direction is planned as a union of both
+    // orientations where the right (connected) node is always present at "from_id".
+    // Otherwise the relationship schema decides it.
+    fn get_edge_list_node_columns(
+        &self,
+        graph_rel: &GraphRel,
+        graph_context: &GraphContext,
+    ) -> (String, String) {
+        let right_is_from_node = if graph_context.left.label == graph_context.right.label {
+            graph_rel.direction != Direction::Incoming
+        } else {
+            graph_context.rel.schema.from_node == graph_context.right.schema.table_name
+        };
+
+        if right_is_from_node {
+            ("from_id".to_string(), "to_id".to_string())
+        } else {
+            ("to_id".to_string(), "from_id".to_string())
+        }
+    }
+
+    // Projections of the union scans of either direction. If rel is returned as a whole then keep all the columns
+    // along with the oriented from_id and to_id.
+    fn build_edge_projections(
+        &self,
+        items: Vec<(String, Option<ColumnAlias>)>,
+        star_found: bool,
+    ) -> Vec<ProjectionItem> {
+        let mut projections = vec![];
+        if star_found {
+            projections.push(ProjectionItem {
+                expression: LogicalExpr::Star,
+                col_alias: None,
+            });
+        }
+        projections.append(&mut self.build_projections(items));
+        projections
+    }
+
     fn build_projections(&self, items: Vec<(String, Option<ColumnAlias>)>) -> Vec<ProjectionItem> {
         items
             .into_iter()
@@ -821,13 +859,34 @@ impl GraphTRaversalPlanning {
         sub_in_exp: String,
         sub_plan_table: String,
         sub_plan_column: String,
+        global: bool,
     ) -> LogicalExpr {
         LogicalExpr::InSubquery(InSubquery {
             expr: Box::new(LogicalExpr::Column(Column(sub_in_exp))),
             subplan: self.get_subplan(sub_plan_table, sub_plan_column),
+            global,
         })
     }
 
+    // `filter AND from_id != to_id`, for the reversed read of an either direction union. Only `from_id != to_id`
+    // when the relation is the anchor and has no filter.
+    fn exclude_self_loops(&self, filter: Option<LogicalExpr>) -> LogicalExpr {
+        let not_self_loop = LogicalExpr::OperatorApplicationExp(OperatorApplication {
+            operator: Operator::NotEqual,
+            operands: vec![
+                LogicalExpr::Column(Column("from_id".to_string())),
+                LogicalExpr::Column(Column("to_id".to_string())),
+            ],
+        });
+        match filter {
+            Some(filter) => LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::And,
+                operands: vec![filter, not_self_loop],
+            }),
+            None => not_self_loop,
+        }
+    }
+
     fn get_subplan(&self, table_name: String, table_column: String) -> Arc<LogicalPlan> {
         Arc::new(LogicalPlan::Projection(Projection {
             input: Arc::new(LogicalPlan::Scan(Scan {
@@ -841,3 +900,374 @@ impl GraphTRaversalPlanning {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clickhouse_query_generator,
+        graph_catalog::graph_schema::{
+            self, IndexType, NodeIdSchema, NodeSchema, RelationshipIndexSchema, RelationshipSchema,
+            TableEngineSchema,
+        },
+        open_cypher_parser, query_planner,
+        query_planner::logical_plan::GraphNode,
+        render_plan::plan_builder::RenderPlanBuilder,
+    };
+    use std::collections::HashMap;
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut nodes = HashMap::new();
+        let mut relationships = HashMap::new();
+
+        nodes.insert(
+            "User".to_string(),
+            NodeSchema {
+                table_name: "User".to_string(),
+                column_names: vec!["id".to_string(), "name".to_string()],
+                primary_keys: "id".to_string(),
+                node_id: NodeIdSchema {
+                    column: "id".to_string(),
+                    dtype: "UInt64".to_string(),
+                },
+                engine: TableEngineSchema::default(),
+            },
+        );
+
+        relationships.insert(
+            "FOLLOWS".to_string(),
+            RelationshipSchema {
+                table_name: "FOLLOWS".to_string(),
+                column_names: vec!["from_User".to_string(), "to_User".to_string()],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+
+        GraphSchema::build(1, nodes, relationships, HashMap::new())
+    }
+
+    fn setup_plan_ctx(use_edge_list: bool) -> PlanCtx {
+        let mut plan_ctx = PlanCtx::default();
+        for alias in ["a", "b"] {
+            plan_ctx.insert_table_ctx(
+                alias.to_string(),
+                TableCtx::build(
+                    alias.to_string(),
+                    Some("User".to_string()),
+                    vec![],
+                    false,
+                    true,
+                ),
+            );
+        }
+        let mut rel_ctx = TableCtx::build(
+            "f".to_string(),
+            Some("FOLLOWS".to_string()),
+            vec![],
+            true,
+            true,
+        );
+        rel_ctx.set_use_edge_list(use_edge_list);
+        plan_ctx.insert_table_ctx("f".to_string(), rel_ctx);
+        plan_ctx
+    }
+
+    fn create_graph_node(alias: &str) -> Arc<LogicalPlan> {
+        Arc::new(LogicalPlan::GraphNode(GraphNode {
+            input: Arc::new(LogicalPlan::Scan(Scan {
+                table_alias: Some(alias.to_string()),
+                table_name: Some("User".to_string()),
+            })),
+            alias: alias.to_string(),
+        }))
+    }
+
+    // MATCH (a:User)-[f:FOLLOWS]-(b:User)
+    fn create_either_graph_rel(is_rel_anchor: bool) -> Arc<LogicalPlan> {
+        Arc::new(LogicalPlan::GraphRel(GraphRel {
+            left: create_graph_node("b"),
+            center: Arc::new(LogicalPlan::Scan(Scan {
+                table_alias: Some("f".to_string()),
+                table_name: Some("FOLLOWS".to_string()),
+            })),
+            right: create_graph_node("a"),
+            alias: "f".to_string(),
+            direction: Direction::Either,
+            left_connection: "b".to_string(),
+            right_connection: "a".to_string(),
+            is_rel_anchor,
+        }))
+    }
+
+    fn get_rel_union(plan: &Arc<LogicalPlan>, is_rel_anchor: bool) -> (String, Union) {
+        let LogicalPlan::GraphRel(graph_rel) = plan.as_ref() else {
+            panic!("Expected GraphRel");
+        };
+        let rel_cte = if is_rel_anchor {
+            &graph_rel.right
+        } else {
+            &graph_rel.center
+        };
+        let LogicalPlan::Cte(cte) = rel_cte.as_ref() else {
+            panic!("Expected relationship Cte");
+        };
+        let LogicalPlan::Union(union) = cte.input.as_ref() else {
+            panic!("Expected Union for either direction");
+        };
+        (cte.name.clone(), union.clone())
+    }
+
+    fn get_scan_alias(plan: &Arc<LogicalPlan>) -> String {
+        match plan.as_ref() {
+            LogicalPlan::Scan(scan) => scan.table_alias.clone().unwrap(),
+            _ => panic!("Expected Scan"),
+        }
+    }
+
+    fn get_projected_columns(plan_ctx: &PlanCtx, alias: &str) -> Vec<(String, Option<String>)> {
+        plan_ctx
+            .get_table_ctx(alias)
+            .unwrap()
+            .get_projections()
+            .iter()
+            .map(|item| match &item.expression {
+                LogicalExpr::Column(Column(col)) => (
+                    col.clone(),
+                    item.col_alias.as_ref().map(|alias| alias.0.clone()),
+                ),
+                LogicalExpr::Star => ("*".to_string(), None),
+                other => panic!("Unexpected projection {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_edge_list_either_direction_unions_both_orientations() {
+        let analyzer = GraphTRaversalPlanning::new();
+        let graph_schema = create_test_graph_schema();
+        let mut plan_ctx = setup_plan_ctx(true);
+
+        let result = analyzer
+            .analyze_with_graph_schema(create_either_graph_rel(false), &mut plan_ctx, &graph_schema)
+            .unwrap();
+
+        let (cte_name, union) = get_rel_union(&result.get_plan(), false);
+        assert_eq!(cte_name, "FOLLOWS_f");
+        assert_eq!(union.union_type, UnionType::All);
+        assert_eq!(union.inputs.len(), 2);
+
+        let outgoing_alias = get_scan_alias(&union.inputs[0]);
+        let incoming_alias = get_scan_alias(&union.inputs[1]);
+
+        // connected node is always at from_id
+        assert_eq!(
+            get_projected_columns(&plan_ctx, &outgoing_alias),
+            vec![
+                ("from_User".to_string(), Some("from_id".to_string())),
+                ("to_User".to_string(), Some("to_id".to_string())),
+            ]
+        );
+        assert_eq!(
+            get_projected_columns(&plan_ctx, &incoming_alias),
+            vec![
+                ("to_User".to_string(), Some("from_id".to_string())),
+                ("from_User".to_string(), Some("to_id".to_string())),
+            ]
+        );
+        assert_eq!(
+            plan_ctx
+                .get_table_ctx(&outgoing_alias)
+                .unwrap()
+                .get_filters()
+                .len(),
+            1
+        );
+        // self-loops are only read by the outgoing scan
+        let incoming_filters = plan_ctx
+            .get_table_ctx(&incoming_alias)
+            .unwrap()
+            .get_filters();
+        assert!(matches!(
+            &incoming_filters[..],
+            [LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::And,
+                operands,
+            })] if matches!(&operands[1], LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::NotEqual,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_distributed_tables_use_global_in() {
+        let analyzer = GraphTRaversalPlanning::new();
+        let mut graph_schema = create_test_graph_schema();
+        let mut user_schema = graph_schema.get_node_schema("User").unwrap().clone();
+        user_schema.engine = TableEngineSchema {
+            cluster: Some("graph".to_string()),
+            sharding_key: Some("id".to_string()),
+            ..TableEngineSchema::default()
+        };
+        graph_schema.insert_node_schema("User".to_string(), user_schema);
+        let mut plan_ctx = setup_plan_ctx(true);
+
+        let result = analyzer
+            .analyze_with_graph_schema(create_either_graph_rel(false), &mut plan_ctx, &graph_schema)
+            .unwrap();
+
+        let (_, union) = get_rel_union(&result.get_plan(), false);
+        let outgoing_alias = get_scan_alias(&union.inputs[0]);
+        for alias in [outgoing_alias.as_str(), "b"] {
+            let filters = plan_ctx.get_table_ctx(alias).unwrap().get_filters();
+            assert!(
+                matches!(&filters[0], LogicalExpr::InSubquery(in_subquery) if in_subquery.global)
+            );
+        }
+    }
+
+    #[test]
+    fn test_edge_list_either_direction_on_anchor_relation() {
+        let analyzer = GraphTRaversalPlanning::new();
+        let graph_schema = create_test_graph_schema();
+        let mut plan_ctx = setup_plan_ctx(true);
+
+        let result = analyzer
+            .analyze_with_graph_schema(create_either_graph_rel(true), &mut plan_ctx, &graph_schema)
+            .unwrap();
+
+        let (_, union) = get_rel_union(&result.get_plan(), true);
+        assert_eq!(union.inputs.len(), 2);
+
+        // anchor relation is not filtered by any node, the reversed scan still skips self-loops
+        let outgoing_alias = get_scan_alias(&union.inputs[0]);
+        let incoming_alias = get_scan_alias(&union.inputs[1]);
+        assert!(
+            plan_ctx
+                .get_table_ctx(&outgoing_alias)
+                .unwrap()
+                .get_filters()
+                .is_empty()
+        );
+        let incoming_filters = plan_ctx
+            .get_table_ctx(&incoming_alias)
+            .unwrap()
+            .get_filters();
+        assert!(matches!(
+            &incoming_filters[..],
+            [LogicalExpr::OperatorApplicationExp(OperatorApplication {
+                operator: Operator::NotEqual,
+                operands,
+            })] if operands == &vec![
+                LogicalExpr::Column(Column("from_id".to_string())),
+                LogicalExpr::Column(Column("to_id".to_string())),
+            ]
+        ));
+        // both nodes are filtered by the relation
+        assert_eq!(plan_ctx.get_table_ctx("a").unwrap().get_filters().len(), 1);
+        assert_eq!(plan_ctx.get_table_ctx("b").unwrap().get_filters().len(), 1);
+    }
+
+    #[test]
+    fn test_edge_list_either_direction_keeps_star_projection() {
+        let analyzer = GraphTRaversalPlanning::new();
+        let graph_schema = create_test_graph_schema();
+        let mut plan_ctx = setup_plan_ctx(true);
+        plan_ctx
+            .get_mut_table_ctx("f")
+            .unwrap()
+            .set_projections(vec![ProjectionItem {
+                expression: LogicalExpr::Star,
+                col_alias: None,
+            }]);
+
+        let result = analyzer
+            .analyze_with_graph_schema(create_either_graph_rel(false), &mut plan_ctx, &graph_schema)
+            .unwrap();
+
+        let (_, union) = get_rel_union(&result.get_plan(), false);
+        let incoming_alias = get_scan_alias(&union.inputs[1]);
+        assert_eq!(
+            get_projected_columns(&plan_ctx, &incoming_alias),
+            vec![
+                ("*".to_string(), None),
+                ("to_User".to_string(), Some("from_id".to_string())),
+                ("from_User".to_string(), Some("to_id".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitmap_either_direction_uses_both_indexes() {
+        let analyzer = GraphTRaversalPlanning::new();
+        let graph_schema = create_test_graph_schema();
+        let mut plan_ctx = setup_plan_ctx(false);
+
+        let result = analyzer
+            .analyze_with_graph_schema(create_either_graph_rel(false), &mut plan_ctx, &graph_schema)
+            .unwrap();
+
+        let (cte_name, union) = get_rel_union(&result.get_plan(), false);
+        assert_eq!(cte_name, "FOLLOWS_either_f");
+        assert_eq!(union.union_type, UnionType::All);
+
+        let table_names: Vec<String> = union
+            .inputs
+            .iter()
+            .map(|input| match input.as_ref() {
+                LogicalPlan::Scan(scan) => scan.table_name.clone().unwrap(),
+                _ => panic!("Expected Scan"),
+            })
+            .collect();
+        assert_eq!(table_names, vec!["FOLLOWS_outgoing", "FOLLOWS_incoming"]);
+
+        assert_eq!(
+            plan_ctx
+                .get_table_ctx("f")
+                .unwrap()
+                .get_label_str()
+                .unwrap(),
+            "FOLLOWS_either"
+        );
+    }
+
+    #[test]
+    fn test_bitmap_either_direction_sql_skips_self_loops_once() {
+        let mut graph_schema = create_test_graph_schema();
+        for direction in [
+            graph_schema::Direction::Outgoing,
+            graph_schema::Direction::Incoming,
+        ] {
+            let table_name = format!("FOLLOWS_{}", direction);
+            graph_schema.insert_rel_index_schema(
+                table_name.clone(),
+                RelationshipIndexSchema {
+                    base_rel_table_name: "FOLLOWS".to_string(),
+                    table_name,
+                    direction,
+                    index_type: IndexType::Bitmap,
+                },
+            );
+        }
+        let query = "MATCH (a:User)-[:FOLLOWS]-(b:User) RETURN a.name, b.name;";
+        let ast = open_cypher_parser::parse_query(query).unwrap();
+        let logical_plan = query_planner::evaluate_read_query(ast, &graph_schema, None).unwrap();
+        let sql = clickhouse_query_generator::generate_sql(logical_plan.to_render_plan().unwrap());
+
+        assert_eq!(
+            sql.matches("arrayJoin(bitmapToArray(to_id)) AS to_id")
+                .count(),
+            1
+        );
+        assert_eq!(
+            sql.matches("arrayJoin(arrayFilter(x -> x != from_id, bitmapToArray(to_id))) AS to_id")
+                .count(),
+            1
+        );
+        assert!(!sql.contains("from_id != to_id"));
+    }
+}
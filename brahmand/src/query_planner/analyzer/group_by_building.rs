@@ -125,7 +125,7 @@ mod tests {
     fn create_scan(alias: Option<String>, table_name: Option<String>) -> Arc<LogicalPlan> {
         Arc::new(LogicalPlan::Scan(Scan {
             table_alias: alias,
-            table_name: table_name,
+            table_name,
         }))
     }
 
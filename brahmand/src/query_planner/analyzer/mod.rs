@@ -1,13 +1,15 @@
 use std::sync::Arc;
 
 use analyzer_pass::AnalyzerResult;
+use tracing::debug_span;
 
 use crate::{
     graph_catalog::graph_schema::GraphSchema,
     query_planner::{
         analyzer::{
-            analyzer_pass::AnalyzerPass, duplicate_scans_removing::DuplicateScansRemoving,
-            filter_tagging::FilterTagging, graph_join_inference::GraphJoinInference,
+            access_control::AccessControl, analyzer_pass::AnalyzerPass,
+            duplicate_scans_removing::DuplicateScansRemoving, filter_tagging::FilterTagging,
+            graph_join_inference::GraphJoinInference,
             graph_traversal_planning::GraphTRaversalPlanning, group_by_building::GroupByBuilding,
             plan_sanitization::PlanSanitization, projection_tagging::ProjectionTagging,
             query_validation::QueryValidation, schema_inference::SchemaInference,
@@ -18,6 +20,7 @@ use crate::{
 
 use super::plan_ctx::PlanCtx;
 
+mod access_control;
 mod analyzer_pass;
 mod duplicate_scans_removing;
 pub mod errors;
@@ -41,29 +44,39 @@ pub fn initial_analyzing(
 
     // For initial schema inference, we do not propogate the error. We will try to infer schema in this initial pass. If not able to infer then it will be done in the later pass after projection and filter tagging.
     let schema_inference = SchemaInference::new();
-    let plan = if let Ok(transformed_plan) =
-        schema_inference.analyze_with_graph_schema(plan.clone(), plan_ctx, current_graph_schema)
-    {
+    let plan = if let Ok(transformed_plan) = debug_span!("analyzer_pass", pass = "SchemaInference")
+        .in_scope(|| {
+            schema_inference.analyze_with_graph_schema(plan.clone(), plan_ctx, current_graph_schema)
+        }) {
         transformed_plan.get_plan()
     } else {
         plan
     };
+    plan_ctx.record_plan_snapshot("SchemaInference", &plan);
 
     let filter_tagging = FilterTagging::new();
-    let transformed_plan = filter_tagging.analyze(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("analyzer_pass", pass = "FilterTagging")
+        .in_scope(|| filter_tagging.analyze(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("FilterTagging", &plan);
 
     let projection_tagging = ProjectionTagging::new();
-    let transformed_plan = projection_tagging.analyze_with_graph_schema(
-        plan.clone(),
-        plan_ctx,
-        current_graph_schema,
-    )?;
+    let transformed_plan =
+        debug_span!("analyzer_pass", pass = "ProjectionTagging").in_scope(|| {
+            projection_tagging.analyze_with_graph_schema(
+                plan.clone(),
+                plan_ctx,
+                current_graph_schema,
+            )
+        })?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("ProjectionTagging", &plan);
 
     let group_by_building = GroupByBuilding::new();
-    let transformed_plan = group_by_building.analyze(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("analyzer_pass", pass = "GroupByBuilding")
+        .in_scope(|| group_by_building.analyze(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("GroupByBuilding", &plan);
 
     // println!("\n\n PLAN After  {:#?} \n\n", plan);
 
@@ -85,36 +98,61 @@ pub fn intermediate_analyzing(
 
     let schema_inference = SchemaInference::new();
     let transformed_plan =
-        schema_inference.analyze_with_graph_schema(plan.clone(), plan_ctx, current_graph_schema)?;
+        debug_span!("analyzer_pass", pass = "SchemaInference").in_scope(|| {
+            schema_inference.analyze_with_graph_schema(plan.clone(), plan_ctx, current_graph_schema)
+        })?;
+    let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("SchemaInference", &plan);
+
+    // labels are known from here, permissions are checked before any table is planned
+    let access_control = AccessControl::new();
+    let transformed_plan = debug_span!("analyzer_pass", pass = "AccessControl")
+        .in_scope(|| access_control.analyze(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("AccessControl", &plan);
 
     let query_validation = QueryValidation::new();
     let transformed_plan =
-        query_validation.analyze_with_graph_schema(plan.clone(), plan_ctx, current_graph_schema)?;
+        debug_span!("analyzer_pass", pass = "QueryValidation").in_scope(|| {
+            query_validation.analyze_with_graph_schema(plan.clone(), plan_ctx, current_graph_schema)
+        })?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("QueryValidation", &plan);
 
     let graph_traversal_planning = GraphTRaversalPlanning::new();
-    let transformed_plan = graph_traversal_planning.analyze_with_graph_schema(
-        plan.clone(),
-        plan_ctx,
-        current_graph_schema,
-    )?;
+    let transformed_plan =
+        debug_span!("analyzer_pass", pass = "GraphTraversalPlanning").in_scope(|| {
+            graph_traversal_planning.analyze_with_graph_schema(
+                plan.clone(),
+                plan_ctx,
+                current_graph_schema,
+            )
+        })?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("GraphTraversalPlanning", &plan);
 
-    let transformed_plan = SchemaInference::push_inferred_table_names_to_scan(plan, plan_ctx)?;
+    let transformed_plan = debug_span!("analyzer_pass", pass = "PushInferredTableNamesToScan")
+        .in_scope(|| SchemaInference::push_inferred_table_names_to_scan(plan, plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("PushInferredTableNamesToScan", &plan);
 
     let duplicate_scans_removing = DuplicateScansRemoving::new();
-    let transformed_plan = duplicate_scans_removing.analyze(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("analyzer_pass", pass = "DuplicateScansRemoving")
+        .in_scope(|| duplicate_scans_removing.analyze(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("DuplicateScansRemoving", &plan);
 
     let graph_join_inference = GraphJoinInference::new();
-    let transformed_plan = graph_join_inference.analyze_with_graph_schema(
-        plan.clone(),
-        plan_ctx,
-        current_graph_schema,
-    )?;
+    let transformed_plan =
+        debug_span!("analyzer_pass", pass = "GraphJoinInference").in_scope(|| {
+            graph_join_inference.analyze_with_graph_schema(
+                plan.clone(),
+                plan_ctx,
+                current_graph_schema,
+            )
+        })?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("GraphJoinInference", &plan);
 
     // println!("\n plan_ctx After intermediate_analyzing {} \n\n", plan_ctx);
     // println!("\n\n PLAN After intermediate_analyzing {} \n\n", plan);
@@ -128,8 +166,10 @@ pub fn final_analyzing(
     _: &GraphSchema,
 ) -> AnalyzerResult<Arc<LogicalPlan>> {
     let plan_sanitization = PlanSanitization::new();
-    let transformed_plan = plan_sanitization.analyze(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("analyzer_pass", pass = "PlanSanitization")
+        .in_scope(|| plan_sanitization.analyze(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("PlanSanitization", &plan);
 
     Ok(plan)
 }
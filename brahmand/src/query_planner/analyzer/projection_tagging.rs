@@ -7,7 +7,10 @@ use crate::{
             analyzer_pass::{AnalyzerPass, AnalyzerResult},
             errors::{AnalyzerError, Pass},
         },
-        logical_expr::{AggregateFnCall, Column, LogicalExpr, PropertyAccess, TableAlias},
+        logical_expr::{
+            AggregateFnCall, Column, ColumnAlias, Literal, LogicalExpr, PropertyAccess,
+            ScalarFnCall, TableAlias,
+        },
         logical_plan::{LogicalPlan, Projection, ProjectionItem},
         plan_ctx::PlanCtx,
         transformed::Transformed,
@@ -157,7 +160,7 @@ impl ProjectionTagging {
     }
 
     fn get_explicit_aliases(&self, plan_ctx: &mut PlanCtx) -> Vec<String> {
-        plan_ctx
+        let mut explicit_aliases: Vec<String> = plan_ctx
             .get_alias_table_ctx_map()
             .iter()
             .filter_map(|(alias, table_ctx)| {
@@ -167,7 +170,10 @@ impl ProjectionTagging {
                     None
                 }
             })
-            .collect()
+            .collect();
+        // named paths are always explicit
+        explicit_aliases.extend(plan_ctx.get_path_ctx_map().keys().cloned());
+        explicit_aliases
     }
 
     fn tag_projection(
@@ -175,34 +181,18 @@ impl ProjectionTagging {
         plan_ctx: &mut PlanCtx,
         graph_schema: &GraphSchema,
     ) -> AnalyzerResult<()> {
+        if Self::tag_path_projection(item, plan_ctx, graph_schema)? {
+            return Ok(());
+        }
+
         match item.expression.clone() {
             LogicalExpr::TableAlias(table_alias) => {
-                // if just table alias i.e MATCH (p:Post) Return p; then For final overall projection keep p.* and for p's projection keep *.
-
-                let table_ctx = plan_ctx.get_mut_table_ctx(&table_alias.0).map_err(|e| {
-                    AnalyzerError::PlanCtx {
-                        pass: Pass::ProjectionTagging,
-                        source: e,
-                    }
-                })?;
-                let tagged_proj = ProjectionItem {
-                    expression: LogicalExpr::Star,
-                    col_alias: None,
-                    // belongs_to_table: Some(table_alias.clone()),
-                };
-                // table_ctx.projection_items = vec![tagged_proj];
-                table_ctx.set_projections(vec![tagged_proj]);
-
-                // if table_ctx is of relation then mark use_edge_list = true
-                if table_ctx.is_relation() {
-                    table_ctx.set_use_edge_list(true);
+                // if just table alias i.e MATCH (p:Post) Return p; then for p's projection keep * and
+                // in the final overall projection return p as a structured entity.
+                item.expression = Self::build_entity_expr(&table_alias.0, plan_ctx, graph_schema)?;
+                if item.col_alias.is_none() {
+                    item.col_alias = Some(ColumnAlias(table_alias.0.clone()));
                 }
-
-                // update the overall projection
-                item.expression = LogicalExpr::PropertyAccessExp(PropertyAccess {
-                    table_alias: table_alias.clone(),
-                    column: Column("*".to_string()),
-                });
                 Ok(())
             }
             LogicalExpr::PropertyAccessExp(property_access) => {
@@ -277,4 +267,400 @@ impl ProjectionTagging {
             _ => Ok(()),
         }
     }
+
+    // Named path p and its functions nodes(p), relationships(p) and length(p) are converted into ClickHouse tuples of entities.
+    // e.g. MATCH p = (a)-[r1]->(b)-[r2]->(c)
+    //  nodes(p)         -> tuple(a, b, c)
+    //  relationships(p) -> tuple(r1, r2)
+    //  length(p)        -> 2
+    //  p                -> tuple(nodes(p), relationships(p))
+    // Returns true if the item was a path expression.
+    fn tag_path_projection(
+        item: &mut ProjectionItem,
+        plan_ctx: &mut PlanCtx,
+        graph_schema: &GraphSchema,
+    ) -> AnalyzerResult<bool> {
+        let (path_alias, path_fn_name) = match &item.expression {
+            LogicalExpr::TableAlias(TableAlias(alias)) => (alias.clone(), None),
+            LogicalExpr::ScalarFnCall(scalar_fn_call) => match scalar_fn_call.args.as_slice() {
+                [LogicalExpr::TableAlias(TableAlias(alias))] => {
+                    (alias.clone(), Some(scalar_fn_call.name.clone()))
+                }
+                _ => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+
+        let Some(path_ctx) = plan_ctx.get_path_ctx_opt(&path_alias).cloned() else {
+            return Ok(false);
+        };
+
+        let path_expr = match path_fn_name.as_deref().map(str::to_lowercase).as_deref() {
+            None => {
+                if item.col_alias.is_none() {
+                    item.col_alias = Some(ColumnAlias(path_alias.clone()));
+                }
+                LogicalExpr::ScalarFnCall(ScalarFnCall {
+                    name: "tuple".to_string(),
+                    args: vec![
+                        Self::build_entities_tuple(
+                            path_ctx.get_node_aliases(),
+                            plan_ctx,
+                            graph_schema,
+                        )?,
+                        Self::build_entities_tuple(
+                            path_ctx.get_rel_aliases(),
+                            plan_ctx,
+                            graph_schema,
+                        )?,
+                    ],
+                })
+            }
+            Some("nodes") => {
+                Self::build_entities_tuple(path_ctx.get_node_aliases(), plan_ctx, graph_schema)?
+            }
+            Some("relationships") => {
+                Self::build_entities_tuple(path_ctx.get_rel_aliases(), plan_ctx, graph_schema)?
+            }
+            Some("length") => LogicalExpr::Literal(Literal::Integer(path_ctx.get_length() as i64)),
+            Some(_) => return Ok(false),
+        };
+
+        // the column keeps the name of the return item, e.g. `length(p)`
+        if let (None, Some(path_fn_name)) = (&item.col_alias, path_fn_name) {
+            item.col_alias = Some(ColumnAlias(format!("`{path_fn_name}({path_alias})`")));
+        }
+        item.expression = path_expr;
+        Ok(true)
+    }
+
+    fn build_entities_tuple(
+        aliases: &[String],
+        plan_ctx: &mut PlanCtx,
+        graph_schema: &GraphSchema,
+    ) -> AnalyzerResult<LogicalExpr> {
+        let entity_exprs = aliases
+            .iter()
+            .map(|alias| Self::build_entity_expr(alias, plan_ctx, graph_schema))
+            .collect::<AnalyzerResult<Vec<LogicalExpr>>>()?;
+
+        Ok(LogicalExpr::ScalarFnCall(ScalarFnCall {
+            name: "tuple".to_string(),
+            args: entity_exprs,
+        }))
+    }
+
+    // Builds the entity value of a node or a relationship as a ClickHouse named tuple so that it is returned as an object.
+    //  node -> {"id": .., "labels": [..], "properties": {..}}
+    //  relationship -> {"type": .., "start_id": .., "end_id": .., "properties": {..}}
+    // Property types are not kept in the graph schema, so properties are cast to Dynamic.
+    // The table's projection is set to * so that all the properties are available in its cte.
+    fn build_entity_expr(
+        alias: &str,
+        plan_ctx: &mut PlanCtx,
+        graph_schema: &GraphSchema,
+    ) -> AnalyzerResult<LogicalExpr> {
+        let table_ctx = plan_ctx
+            .get_mut_table_ctx(alias)
+            .map_err(|e| AnalyzerError::PlanCtx {
+                pass: Pass::ProjectionTagging,
+                source: e,
+            })?;
+        table_ctx.set_projections(vec![ProjectionItem {
+            expression: LogicalExpr::Star,
+            col_alias: None,
+        }]);
+
+        let label = table_ctx
+            .get_label_str()
+            .map_err(|e| AnalyzerError::PlanCtx {
+                pass: Pass::ProjectionTagging,
+                source: e,
+            })?;
+
+        let property_access = |column: &str| {
+            LogicalExpr::PropertyAccessExp(PropertyAccess {
+                table_alias: TableAlias(alias.to_string()),
+                column: Column(column.to_string()),
+            })
+        };
+
+        let (mut values, mut types, column_names) =
+            if table_ctx.is_relation() {
+                // if table_ctx is of relation then mark use_edge_list = true
+                table_ctx.set_use_edge_list(true);
+
+                let rel_schema = graph_schema.get_rel_schema(&label).map_err(|e| {
+                    AnalyzerError::GraphSchema {
+                        pass: Pass::ProjectionTagging,
+                        source: e,
+                    }
+                })?;
+                // the stored columns, from_id and to_id are swapped in the reversed half of an
+                // either direction union
+                (
+                    vec![
+                        LogicalExpr::Literal(Literal::String(label.clone())),
+                        property_access(&format!("from_{}", rel_schema.from_node)),
+                        property_access(&format!("to_{}", rel_schema.to_node)),
+                    ],
+                    vec![
+                        "type String".to_string(),
+                        format!("start_id {}", rel_schema.from_node_id_dtype),
+                        format!("end_id {}", rel_schema.to_node_id_dtype),
+                    ],
+                    &rel_schema.column_names,
+                )
+            } else {
+                let node_schema = graph_schema.get_node_schema(&label).map_err(|e| {
+                    AnalyzerError::GraphSchema {
+                        pass: Pass::ProjectionTagging,
+                        source: e,
+                    }
+                })?;
+                (
+                    vec![
+                        property_access(&node_schema.node_id.column),
+                        LogicalExpr::ScalarFnCall(ScalarFnCall {
+                            name: "array".to_string(),
+                            args: vec![LogicalExpr::Literal(Literal::String(label.clone()))],
+                        }),
+                    ],
+                    vec![
+                        format!("id {}", node_schema.node_id.dtype),
+                        "labels Array(String)".to_string(),
+                    ],
+                    &node_schema.column_names,
+                )
+            };
+
+        if column_names.is_empty() {
+            values.push(LogicalExpr::ScalarFnCall(ScalarFnCall {
+                name: "map".to_string(),
+                args: vec![],
+            }));
+            types.push("properties Map(String, String)".to_string());
+        } else {
+            values.push(LogicalExpr::ScalarFnCall(ScalarFnCall {
+                name: "tuple".to_string(),
+                args: column_names
+                    .iter()
+                    .map(|column| property_access(column))
+                    .collect(),
+            }));
+            let property_types: Vec<String> = column_names
+                .iter()
+                .map(|column| format!("`{}` Dynamic", column))
+                .collect();
+            types.push(format!("properties Tuple({})", property_types.join(", ")));
+        }
+
+        Ok(LogicalExpr::ScalarFnCall(ScalarFnCall {
+            name: "CAST".to_string(),
+            args: vec![
+                LogicalExpr::ScalarFnCall(ScalarFnCall {
+                    name: "tuple".to_string(),
+                    args: values,
+                }),
+                LogicalExpr::Literal(Literal::String(format!("Tuple({})", types.join(", ")))),
+            ],
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clickhouse_query_generator,
+        graph_catalog::graph_schema::{
+            NodeIdSchema, NodeSchema, RelationshipSchema, TableEngineSchema,
+        },
+        open_cypher_parser, query_planner,
+        query_planner::plan_ctx::{PathCtx, TableCtx},
+        render_plan::plan_builder::RenderPlanBuilder,
+    };
+    use std::collections::HashMap;
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "User".to_string(),
+            NodeSchema {
+                table_name: "User".to_string(),
+                column_names: vec!["user_id".to_string(), "name".to_string()],
+                primary_keys: "user_id".to_string(),
+                node_id: NodeIdSchema {
+                    column: "user_id".to_string(),
+                    dtype: "UInt64".to_string(),
+                },
+                engine: TableEngineSchema::default(),
+            },
+        );
+
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            "FOLLOWS".to_string(),
+            RelationshipSchema {
+                table_name: "FOLLOWS".to_string(),
+                column_names: vec![],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+
+        GraphSchema::build(1, nodes, relationships, HashMap::new())
+    }
+
+    fn setup_plan_ctx() -> PlanCtx {
+        let mut plan_ctx = PlanCtx::default();
+        for (alias, label, is_rel) in [
+            ("a", "User", false),
+            ("f", "FOLLOWS", true),
+            ("b", "User", false),
+        ] {
+            plan_ctx.insert_table_ctx(
+                alias.to_string(),
+                TableCtx::build(
+                    alias.to_string(),
+                    Some(label.to_string()),
+                    vec![],
+                    is_rel,
+                    true,
+                ),
+            );
+        }
+        plan_ctx.insert_path_ctx(
+            "p".to_string(),
+            PathCtx::build(
+                vec!["a".to_string(), "b".to_string()],
+                vec!["f".to_string()],
+            ),
+        );
+        plan_ctx
+    }
+
+    fn tag(expression: LogicalExpr, plan_ctx: &mut PlanCtx) -> ProjectionItem {
+        let mut item = ProjectionItem {
+            expression,
+            col_alias: None,
+        };
+        ProjectionTagging::tag_projection(&mut item, plan_ctx, &create_test_graph_schema())
+            .unwrap();
+        item
+    }
+
+    fn get_cast_type(expr: &LogicalExpr) -> String {
+        match expr {
+            LogicalExpr::ScalarFnCall(ScalarFnCall { name, args }) if name == "CAST" => {
+                match &args[1] {
+                    LogicalExpr::Literal(Literal::String(cast_type)) => cast_type.clone(),
+                    _ => panic!("Expected cast type"),
+                }
+            }
+            _ => panic!("Expected CAST, got {:?}", expr),
+        }
+    }
+
+    #[test]
+    fn test_node_returned_as_entity() {
+        let mut plan_ctx = setup_plan_ctx();
+        let item = tag(
+            LogicalExpr::TableAlias(TableAlias("a".to_string())),
+            &mut plan_ctx,
+        );
+
+        assert_eq!(item.col_alias, Some(ColumnAlias("a".to_string())));
+        assert_eq!(
+            get_cast_type(&item.expression),
+            "Tuple(id UInt64, labels Array(String), properties Tuple(`user_id` Dynamic, `name` Dynamic))"
+        );
+        assert_eq!(
+            plan_ctx.get_table_ctx("a").unwrap().get_projections()[0].expression,
+            LogicalExpr::Star
+        );
+    }
+
+    #[test]
+    fn test_relationship_returned_as_entity() {
+        let mut plan_ctx = setup_plan_ctx();
+        let item = tag(
+            LogicalExpr::TableAlias(TableAlias("f".to_string())),
+            &mut plan_ctx,
+        );
+
+        assert_eq!(
+            get_cast_type(&item.expression),
+            "Tuple(type String, start_id UInt64, end_id UInt64, properties Map(String, String))"
+        );
+        assert!(plan_ctx.get_table_ctx("f").unwrap().should_use_edge_list());
+    }
+
+    #[test]
+    fn test_either_direction_relationship_keeps_stored_orientation() {
+        let query = "MATCH (a:User)-[f:FOLLOWS]-(b:User) RETURN f;";
+        let ast = open_cypher_parser::parse_query(query).unwrap();
+        let logical_plan =
+            query_planner::evaluate_read_query(ast, &create_test_graph_schema(), None).unwrap();
+        let sql = clickhouse_query_generator::generate_sql(logical_plan.to_render_plan().unwrap());
+
+        assert!(sql.contains("tuple('FOLLOWS', f.from_User, f.to_User, "));
+        // both halves of the union carry the stored columns
+        assert_eq!(sql.matches("*, \n      to_User AS from_id").count(), 1);
+        assert_eq!(sql.matches("*, \n      from_User AS from_id").count(), 1);
+    }
+
+    #[test]
+    fn test_path_functions() {
+        let mut plan_ctx = setup_plan_ctx();
+        let path_fn = |name: &str| {
+            LogicalExpr::ScalarFnCall(ScalarFnCall {
+                name: name.to_string(),
+                args: vec![LogicalExpr::TableAlias(TableAlias("p".to_string()))],
+            })
+        };
+
+        let item = tag(path_fn("length"), &mut plan_ctx);
+        assert_eq!(item.expression, LogicalExpr::Literal(Literal::Integer(1)));
+        assert_eq!(item.col_alias, Some(ColumnAlias("`length(p)`".to_string())));
+
+        let item = tag(path_fn("relationships"), &mut plan_ctx);
+        assert_eq!(
+            item.col_alias,
+            Some(ColumnAlias("`relationships(p)`".to_string()))
+        );
+
+        let item = tag(path_fn("nodes"), &mut plan_ctx);
+        match item.expression {
+            LogicalExpr::ScalarFnCall(ScalarFnCall { name, args }) => {
+                assert_eq!(name, "tuple");
+                assert_eq!(args.len(), 2);
+            }
+            _ => panic!("Expected tuple of nodes"),
+        }
+
+        let item = tag(
+            LogicalExpr::TableAlias(TableAlias("p".to_string())),
+            &mut plan_ctx,
+        );
+        assert_eq!(item.col_alias, Some(ColumnAlias("p".to_string())));
+
+        // not a path function
+        let item = tag(
+            LogicalExpr::ScalarFnCall(ScalarFnCall {
+                name: "toString".to_string(),
+                args: vec![LogicalExpr::PropertyAccessExp(PropertyAccess {
+                    table_alias: TableAlias("a".to_string()),
+                    column: Column("name".to_string()),
+                })],
+            }),
+            &mut plan_ctx,
+        );
+        assert!(
+            matches!(item.expression, LogicalExpr::ScalarFnCall(ScalarFnCall { ref name, .. }) if name == "toString")
+        );
+    }
 }
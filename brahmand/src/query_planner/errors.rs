@@ -12,10 +12,23 @@ pub enum QueryPlannerError {
     LogicalPlan(#[from] LogicalPlanError),
     #[error("OptimizerError: {0}")]
     Optimizer(#[from] OptimizerError),
-    #[error("AnalyzerError: {0}")]
-    Analyzer(#[from] AnalyzerError),
+    // `offset` is where the alias or the label the error is about is written in the query
+    #[error("AnalyzerError: {source}")]
+    Analyzer {
+        source: AnalyzerError,
+        offset: Option<usize>,
+    },
     // #[error("RenderBuildError: {0}")]
     // RenderBuild(#[from] RenderBuildError),
     #[error("Logical Plan Extractor")]
     LogicalPlanExtractor,
 }
+
+impl From<AnalyzerError> for QueryPlannerError {
+    fn from(source: AnalyzerError) -> Self {
+        QueryPlannerError::Analyzer {
+            source,
+            offset: None,
+        }
+    }
+}
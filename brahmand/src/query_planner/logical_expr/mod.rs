@@ -46,6 +46,8 @@ pub enum LogicalExpr {
 pub struct InSubquery {
     pub expr: Box<LogicalExpr>,
     pub subplan: Arc<LogicalPlan>,
+    // GLOBAL IN, the subquery runs once on the initiator instead of on every shard
+    pub global: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -416,9 +418,9 @@ mod tests {
         assert_eq!(logical_int, Literal::Integer(42));
 
         // Test float conversion
-        let ast_float = ast::Literal::Float(3.14);
+        let ast_float = ast::Literal::Float(2.5);
         let logical_float = Literal::from(ast_float);
-        assert_eq!(logical_float, Literal::Float(3.14));
+        assert_eq!(logical_float, Literal::Float(2.5));
 
         // Test boolean conversion
         let ast_bool = ast::Literal::Boolean(true);
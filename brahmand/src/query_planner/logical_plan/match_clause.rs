@@ -9,7 +9,7 @@ use crate::{
             plan_builder::LogicalPlanResult,
             {GraphNode, GraphRel, LogicalPlan, Scan},
         },
-        plan_ctx::{PlanCtx, TableCtx},
+        plan_ctx::{PathCtx, PlanCtx, TableCtx},
     },
 };
 
@@ -61,7 +61,12 @@ fn traverse_connected_pattern<'a>(
     mut plan: Arc<LogicalPlan>,
     plan_ctx: &mut PlanCtx,
     path_pattern_idx: usize,
+    path_variable: Option<&str>,
 ) -> LogicalPlanResult<Arc<LogicalPlan>> {
+    // aliases in the path order to build the named path
+    let mut path_node_aliases: Vec<String> = vec![];
+    let mut path_rel_aliases: Vec<String> = vec![];
+
     for connected_pattern in connected_patterns {
         let start_node_ref = connected_pattern.start_node.borrow();
         let start_node_label = start_node_ref.label.map(|val| val.to_string());
@@ -102,6 +107,12 @@ fn traverse_connected_pattern<'a>(
             .map(|props| props.into_iter().map(Property::from).collect())
             .unwrap_or_else(Vec::new);
 
+        if path_node_aliases.is_empty() {
+            path_node_aliases.push(start_node_alias.clone());
+        }
+        path_rel_aliases.push(rel_alias.clone());
+        path_node_aliases.push(end_node_alias.clone());
+
         // if start alias already present in ctx map, it means the current nested connected pattern's start node will be connecting at right side plan and end node will be at the left
         if let Some(table_ctx) = plan_ctx.get_mut_table_ctx_opt(&start_node_alias) {
             if start_node_label.is_some() {
@@ -260,6 +271,13 @@ fn traverse_connected_pattern<'a>(
         }
     }
 
+    if let Some(path_alias) = path_variable {
+        plan_ctx.insert_path_ctx(
+            path_alias.to_string(),
+            PathCtx::build(path_node_aliases, path_rel_aliases),
+        );
+    }
+
     Ok(plan)
 }
 
@@ -315,13 +333,26 @@ pub fn evaluate_match_clause<'a>(
     mut plan: Arc<LogicalPlan>,
     plan_ctx: &mut PlanCtx,
 ) -> LogicalPlanResult<Arc<LogicalPlan>> {
-    for (idx, path_pattern) in match_clause.path_patterns.iter().enumerate() {
+    for (idx, (path_variable, path_pattern)) in match_clause.path_patterns.iter().enumerate() {
         match path_pattern {
             ast::PathPattern::Node(node_pattern) => {
                 plan = traverse_node_pattern(node_pattern, plan, plan_ctx)?;
+                // a path with a single node and no relationships
+                if let (Some(path_alias), Some(node_alias)) = (path_variable, node_pattern.name) {
+                    plan_ctx.insert_path_ctx(
+                        path_alias.to_string(),
+                        PathCtx::build(vec![node_alias.to_string()], vec![]),
+                    );
+                }
             }
             ast::PathPattern::ConnectedPattern(connected_patterns) => {
-                plan = traverse_connected_pattern(connected_patterns, plan, plan_ctx, idx)?;
+                plan = traverse_connected_pattern(
+                    connected_patterns,
+                    plan,
+                    plan_ctx,
+                    idx,
+                    *path_variable,
+                )?;
             }
         }
     }
@@ -556,7 +587,7 @@ mod tests {
         let connected_patterns = vec![connected_pattern];
 
         let result =
-            traverse_connected_pattern(&connected_patterns, initial_plan, &mut plan_ctx, 0)
+            traverse_connected_pattern(&connected_patterns, initial_plan, &mut plan_ctx, 0, None)
                 .unwrap();
 
         // Should return a GraphRel plan
@@ -641,7 +672,7 @@ mod tests {
         let connected_patterns = vec![connected_pattern];
 
         let result =
-            traverse_connected_pattern(&connected_patterns, initial_plan, &mut plan_ctx, 0)
+            traverse_connected_pattern(&connected_patterns, initial_plan, &mut plan_ctx, 0, None)
                 .unwrap();
 
         // Should return a GraphRel plan with different structure
@@ -702,7 +733,7 @@ mod tests {
 
         // Pass path_pattern_idx > 0 to simulate second pattern that's disconnected
         let result =
-            traverse_connected_pattern(&connected_patterns, initial_plan, &mut plan_ctx, 1);
+            traverse_connected_pattern(&connected_patterns, initial_plan, &mut plan_ctx, 1, None);
 
         assert!(result.is_err());
         match result.unwrap_err() {
@@ -753,8 +784,11 @@ mod tests {
 
         let match_clause = ast::MatchClause {
             path_patterns: vec![
-                ast::PathPattern::Node(node_pattern),
-                ast::PathPattern::ConnectedPattern(vec![connected_pattern]),
+                (None, ast::PathPattern::Node(node_pattern)),
+                (
+                    None,
+                    ast::PathPattern::ConnectedPattern(vec![connected_pattern]),
+                ),
             ],
         };
 
@@ -775,6 +809,57 @@ mod tests {
         assert!(admin_ctx.should_use_edge_list()); // Should be true because properties were found
     }
 
+    #[test]
+    fn test_evaluate_match_clause_with_named_path() {
+        let mut plan_ctx = PlanCtx::default();
+        let initial_plan = Arc::new(LogicalPlan::Empty);
+
+        // MATCH p = (a)-[r1:FOLLOWS]->(b)-[r2:FOLLOWS]->(c)
+        let node_b = Rc::new(RefCell::new(ast::NodePattern {
+            name: Some("b"),
+            label: None,
+            properties: None,
+        }));
+        let build_rel = |name| ast::RelationshipPattern {
+            name: Some(name),
+            direction: ast::Direction::Outgoing,
+            label: Some("FOLLOWS"),
+            properties: None,
+        };
+        let build_node = |name| {
+            Rc::new(RefCell::new(ast::NodePattern {
+                name: Some(name),
+                label: None,
+                properties: None,
+            }))
+        };
+
+        let match_clause = ast::MatchClause {
+            path_patterns: vec![(
+                Some("p"),
+                ast::PathPattern::ConnectedPattern(vec![
+                    ast::ConnectedPattern {
+                        start_node: build_node("a"),
+                        relationship: build_rel("r1"),
+                        end_node: node_b.clone(),
+                    },
+                    ast::ConnectedPattern {
+                        start_node: node_b,
+                        relationship: build_rel("r2"),
+                        end_node: build_node("c"),
+                    },
+                ]),
+            )],
+        };
+
+        evaluate_match_clause(&match_clause, initial_plan, &mut plan_ctx).unwrap();
+
+        let path_ctx = plan_ctx.get_path_ctx_opt("p").unwrap();
+        assert_eq!(path_ctx.get_node_aliases(), &vec!["a", "b", "c"]);
+        assert_eq!(path_ctx.get_rel_aliases(), &vec!["r1", "r2"]);
+        assert_eq!(path_ctx.get_length(), 2);
+    }
+
     #[test]
     fn test_convert_properties_to_operator_application() {
         let mut plan_ctx = PlanCtx::default();
@@ -125,6 +125,8 @@ pub struct Join {
     pub table_alias: String,
     pub joining_on: Vec<OperatorApplication>,
     pub join_type: JoinType,
+    // GLOBAL JOIN, the joined table is built once on the initiator instead of on every shard
+    pub global: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
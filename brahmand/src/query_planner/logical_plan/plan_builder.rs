@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
 use crate::{
-    open_cypher_parser::ast::OpenCypherQueryAst,
+    open_cypher_parser::{
+        ast::{Expression, OpenCypherQueryAst, PathPattern},
+        errors::SourcePosition,
+    },
     query_planner::{
         logical_plan::{
             LogicalPlan, errors::LogicalPlanError, match_clause, order_by_clause, return_clause,
@@ -18,6 +21,7 @@ pub fn build_logical_plan(
 ) -> LogicalPlanResult<(Arc<LogicalPlan>, PlanCtx)> {
     let mut logical_plan: Arc<LogicalPlan> = Arc::new(LogicalPlan::Empty);
     let mut plan_ctx = PlanCtx::default();
+    record_source_offsets(query_ast, &mut plan_ctx);
 
     if let Some(match_clause) = &query_ast.match_clause {
         logical_plan =
@@ -46,3 +50,87 @@ pub fn build_logical_plan(
 
     Ok((logical_plan, plan_ctx))
 }
+
+// Analyzer errors only carry the alias or the label they are about. Remember where those are
+// written in the query so that the errors can point back at it.
+fn record_source_offsets(query_ast: &OpenCypherQueryAst, plan_ctx: &mut PlanCtx) {
+    let source = query_ast.source;
+
+    if let Some(match_clause) = &query_ast.match_clause {
+        for (_, path_pattern) in &match_clause.path_patterns {
+            match path_pattern {
+                PathPattern::Node(node_pattern) => {
+                    record_label_offset(source, node_pattern.label, plan_ctx);
+                }
+                PathPattern::ConnectedPattern(connected_patterns) => {
+                    for connected_pattern in connected_patterns {
+                        let start_label = connected_pattern.start_node.borrow().label;
+                        let end_label = connected_pattern.end_node.borrow().label;
+                        record_label_offset(source, start_label, plan_ctx);
+                        record_label_offset(source, connected_pattern.relationship.label, plan_ctx);
+                        record_label_offset(source, end_label, plan_ctx);
+                    }
+                }
+            }
+        }
+    }
+
+    // in the order the clauses are written, the first reference of an alias wins
+    let where_conditions = query_ast
+        .where_clause
+        .iter()
+        .map(|where_clause| &where_clause.conditions);
+    let return_expressions = query_ast
+        .return_clause
+        .iter()
+        .flat_map(|return_clause| &return_clause.return_items)
+        .map(|return_item| &return_item.expression);
+    let order_by_expressions = query_ast
+        .order_by_clause
+        .iter()
+        .flat_map(|order_by_clause| &order_by_clause.order_by_items)
+        .map(|order_by_item| &order_by_item.expression);
+    for expression in where_conditions
+        .chain(return_expressions)
+        .chain(order_by_expressions)
+    {
+        record_alias_offsets(source, expression, plan_ctx);
+    }
+}
+
+fn record_label_offset(source: &str, label: Option<&str>, plan_ctx: &mut PlanCtx) {
+    if let Some(label) = label {
+        if let Some(position) = SourcePosition::from_slice(source, label) {
+            plan_ctx.record_label_offset(label, position.offset);
+        }
+    }
+}
+
+fn record_alias_offsets(source: &str, expression: &Expression, plan_ctx: &mut PlanCtx) {
+    let alias = match expression {
+        Expression::Variable(alias) => *alias,
+        Expression::PropertyAccessExp(property_access) => property_access.base,
+        Expression::List(items) => {
+            for item in items {
+                record_alias_offsets(source, item, plan_ctx);
+            }
+            return;
+        }
+        Expression::FunctionCallExp(function_call) => {
+            for arg in &function_call.args {
+                record_alias_offsets(source, arg, plan_ctx);
+            }
+            return;
+        }
+        Expression::OperatorApplicationExp(operator_application) => {
+            for operand in &operator_application.operands {
+                record_alias_offsets(source, operand, plan_ctx);
+            }
+            return;
+        }
+        _ => return,
+    };
+    if let Some(position) = SourcePosition::from_slice(source, alias) {
+        plan_ctx.record_alias_offset(alias, position.offset);
+    }
+}
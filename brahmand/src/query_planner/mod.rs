@@ -1,16 +1,21 @@
 use std::sync::Arc;
 
 use errors::QueryPlannerError;
+use tracing::debug;
 use types::QueryType;
 
 use crate::{
-    graph_catalog::graph_schema::GraphSchema,
+    graph_catalog::{graph_permissions::GraphPermissions, graph_schema::GraphSchema},
     open_cypher_parser::ast::OpenCypherQueryAst,
-    query_planner::{analyzer::errors::AnalyzerError, logical_plan::LogicalPlan},
+    query_planner::{
+        analyzer::errors::AnalyzerError,
+        logical_plan::LogicalPlan,
+        plan_ctx::{PlanCtx, PlanSnapshot},
+    },
 };
 
 pub mod analyzer;
-mod errors;
+pub mod errors;
 pub mod logical_expr;
 pub mod logical_plan;
 pub mod optimizer;
@@ -19,7 +24,11 @@ pub mod transformed;
 pub mod types;
 
 pub fn get_query_type(query_ast: &OpenCypherQueryAst) -> QueryType {
-    if query_ast.create_node_table_clause.is_some() || query_ast.create_rel_table_clause.is_some() {
+    if query_ast.create_node_table_clause.is_some()
+        || query_ast.create_rel_table_clause.is_some()
+        || query_ast.create_index_clause.is_some()
+        || query_ast.adj_index_clause.is_some()
+    {
         QueryType::Ddl
     } else if query_ast.delete_clause.is_some() {
         QueryType::Delete
@@ -30,15 +39,54 @@ pub fn get_query_type(query_ast: &OpenCypherQueryAst) -> QueryType {
     }
 }
 
+// `permissions` restricts what the query can read, None when access control is not configured.
 pub fn evaluate_read_query(
     query_ast: OpenCypherQueryAst,
     current_graph_schema: &GraphSchema,
+    permissions: Option<&GraphPermissions>,
 ) -> Result<LogicalPlan, QueryPlannerError> {
+    let (logical_plan, _) = plan_read_query(query_ast, current_graph_schema, permissions, false)?;
+    Ok(logical_plan)
+}
+
+// Same as evaluate_read_query but also returns the plan after each analyzer and optimizer pass.
+pub fn explain_read_query(
+    query_ast: OpenCypherQueryAst,
+    current_graph_schema: &GraphSchema,
+    permissions: Option<&GraphPermissions>,
+) -> Result<(LogicalPlan, Vec<PlanSnapshot>), QueryPlannerError> {
+    plan_read_query(query_ast, current_graph_schema, permissions, true)
+}
+
+fn plan_read_query(
+    query_ast: OpenCypherQueryAst,
+    current_graph_schema: &GraphSchema,
+    permissions: Option<&GraphPermissions>,
+    record_plan_snapshots: bool,
+) -> Result<(LogicalPlan, Vec<PlanSnapshot>), QueryPlannerError> {
     let (logical_plan, mut plan_ctx) = logical_plan::evaluate_query(query_ast)?;
 
+    // Hidden properties are removed from the schema so that nodes and relationships returned as
+    // entities do not carry them. The AccessControl pass rejects any other use of them.
+    let restricted_graph_schema;
+    let current_graph_schema = match permissions {
+        Some(permissions) => {
+            plan_ctx.set_permissions(permissions.clone());
+            restricted_graph_schema = permissions.restrict_schema(current_graph_schema);
+            &restricted_graph_schema
+        }
+        None => current_graph_schema,
+    };
+
+    if record_plan_snapshots {
+        plan_ctx.enable_plan_snapshots();
+        plan_ctx.record_plan_snapshot("LogicalPlan", &logical_plan);
+    }
+
     // println!("\n\n PLAN Before  {} \n\n", logical_plan);
     let logical_plan =
-        analyzer::initial_analyzing(logical_plan, &mut plan_ctx, current_graph_schema)?;
+        analyzer::initial_analyzing(logical_plan, &mut plan_ctx, current_graph_schema)
+            .map_err(|e| locate_analyzer_error(&plan_ctx, e))?;
 
     let logical_plan = optimizer::initial_optimization(logical_plan, &mut plan_ctx)?;
 
@@ -51,11 +99,11 @@ pub fn evaluate_read_query(
         Ok(plan) => Ok(plan),
         Err(e) => match e {
             AnalyzerError::InvalidRelationInQuery { rel } => {
-                println!("Invalid relation in query found {rel}");
+                debug!("Invalid relation in query found {rel}");
                 let new_plan = LogicalPlan::get_empty_match_plan();
-                return Ok(new_plan);
+                return Ok((new_plan, plan_ctx.take_plan_snapshots()));
             }
-            _ => Err(e),
+            _ => Err(locate_analyzer_error(&plan_ctx, e)),
         },
     }?;
 
@@ -63,13 +111,20 @@ pub fn evaluate_read_query(
 
     let logical_plan = optimizer::final_optimization(logical_plan, &mut plan_ctx)?;
 
-    let logical_plan =
-        analyzer::final_analyzing(logical_plan, &mut plan_ctx, current_graph_schema)?;
+    let logical_plan = analyzer::final_analyzing(logical_plan, &mut plan_ctx, current_graph_schema)
+        .map_err(|e| locate_analyzer_error(&plan_ctx, e))?;
 
     // println!("\n\n plan_ctx after \n {}",plan_ctx);
     // println!("\n plan after{}", logical_plan);
 
     let logical_plan =
         Arc::into_inner(logical_plan).ok_or(QueryPlannerError::LogicalPlanExtractor)?;
-    Ok(logical_plan)
+    Ok((logical_plan, plan_ctx.take_plan_snapshots()))
+}
+
+fn locate_analyzer_error(plan_ctx: &PlanCtx, source: AnalyzerError) -> QueryPlannerError {
+    QueryPlannerError::Analyzer {
+        offset: plan_ctx.source_offset(&source),
+        source,
+    }
 }
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use tracing::debug_span;
+
 use crate::query_planner::{
     logical_plan::LogicalPlan,
     optimizer::{
@@ -23,8 +25,10 @@ pub fn initial_optimization(
     plan_ctx: &mut PlanCtx,
 ) -> OptimizerResult<Arc<LogicalPlan>> {
     let anchor_node_selection = AnchorNodeSelection::new();
-    let transformed_plan = anchor_node_selection.optimize(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("optimizer_pass", pass = "AnchorNodeSelection")
+        .in_scope(|| anchor_node_selection.optimize(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("AnchorNodeSelection", &plan);
 
     Ok(plan)
 }
@@ -34,12 +38,16 @@ pub fn final_optimization(
     plan_ctx: &mut PlanCtx,
 ) -> OptimizerResult<Arc<LogicalPlan>> {
     let projection_push_down = ProjectionPushDown::new();
-    let transformed_plan = projection_push_down.optimize(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("optimizer_pass", pass = "ProjectionPushDown")
+        .in_scope(|| projection_push_down.optimize(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("ProjectionPushDown", &plan);
 
     let filter_push_down = FilterPushDown::new();
-    let transformed_plan = filter_push_down.optimize(plan.clone(), plan_ctx)?;
+    let transformed_plan = debug_span!("optimizer_pass", pass = "FilterPushDown")
+        .in_scope(|| filter_push_down.optimize(plan.clone(), plan_ctx))?;
     let plan = transformed_plan.get_plan();
+    plan_ctx.record_plan_snapshot("FilterPushDown", &plan);
 
     // println!("\n plan_ctx After {} \n\n", plan_ctx);
     // println!("\n PLAN After {} \n\n", plan);
@@ -2,10 +2,16 @@ pub mod errors;
 
 use std::{collections::HashMap, fmt};
 
-use crate::query_planner::{
-    logical_expr::{LogicalExpr, Property},
-    logical_plan::ProjectionItem,
-    plan_ctx::errors::PlanCtxError,
+use serde::Serialize;
+
+use crate::{
+    graph_catalog::{errors::GraphSchemaError, graph_permissions::GraphPermissions},
+    query_planner::{
+        analyzer::errors::AnalyzerError,
+        logical_expr::{LogicalExpr, Property},
+        logical_plan::{LogicalPlan, ProjectionItem},
+        plan_ctx::errors::PlanCtxError,
+    },
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -122,9 +128,53 @@ impl TableCtx {
     }
 }
 
+// Named path e.g. MATCH p = (a)-[r1]->(b)-[r2]->(c). Keeps the node and relationship aliases in the path order.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PathCtx {
+    node_aliases: Vec<String>,
+    rel_aliases: Vec<String>,
+}
+
+impl PathCtx {
+    pub fn build(node_aliases: Vec<String>, rel_aliases: Vec<String>) -> Self {
+        PathCtx {
+            node_aliases,
+            rel_aliases,
+        }
+    }
+
+    pub fn get_node_aliases(&self) -> &Vec<String> {
+        &self.node_aliases
+    }
+
+    pub fn get_rel_aliases(&self) -> &Vec<String> {
+        &self.rel_aliases
+    }
+
+    // length of a path is the number of relationships in it
+    pub fn get_length(&self) -> usize {
+        self.rel_aliases.len()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct PlanCtx {
     alias_table_ctx_map: HashMap<String, TableCtx>,
+    path_ctx_map: HashMap<String, PathCtx>,
+    // plan after each analyzer and optimizer pass, only recorded for EXPLAIN and PROFILE
+    plan_snapshots: Option<Vec<PlanSnapshot>>,
+    // what the caller may read, None when access control is not configured
+    permissions: Option<GraphPermissions>,
+    // byte offsets in the query of the first reference to each alias outside of the patterns
+    alias_offsets: HashMap<String, usize>,
+    // byte offsets in the query of the first use of each label and relationship type
+    label_offsets: HashMap<String, usize>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct PlanSnapshot {
+    pub pass: String,
+    pub plan: String,
 }
 
 impl PlanCtx {
@@ -200,6 +250,72 @@ impl PlanCtx {
     //         })
     // }
 
+    pub fn insert_path_ctx(&mut self, path_alias: String, path_ctx: PathCtx) {
+        self.path_ctx_map.insert(path_alias, path_ctx);
+    }
+
+    pub fn get_path_ctx_map(&self) -> &HashMap<String, PathCtx> {
+        &self.path_ctx_map
+    }
+
+    pub fn get_path_ctx_opt(&self, path_alias: &str) -> Option<&PathCtx> {
+        self.path_ctx_map.get(path_alias)
+    }
+
+    pub fn enable_plan_snapshots(&mut self) {
+        self.plan_snapshots = Some(vec![]);
+    }
+
+    pub fn record_plan_snapshot(&mut self, pass: &str, plan: &LogicalPlan) {
+        if let Some(plan_snapshots) = self.plan_snapshots.as_mut() {
+            plan_snapshots.push(PlanSnapshot {
+                pass: pass.to_string(),
+                plan: plan.to_string(),
+            });
+        }
+    }
+
+    pub fn take_plan_snapshots(&mut self) -> Vec<PlanSnapshot> {
+        self.plan_snapshots.take().unwrap_or_default()
+    }
+
+    pub fn set_permissions(&mut self, permissions: GraphPermissions) {
+        self.permissions = Some(permissions);
+    }
+
+    pub fn get_permissions(&self) -> Option<&GraphPermissions> {
+        self.permissions.as_ref()
+    }
+
+    pub fn record_alias_offset(&mut self, alias: &str, offset: usize) {
+        self.alias_offsets
+            .entry(alias.to_string())
+            .or_insert(offset);
+    }
+
+    pub fn record_label_offset(&mut self, label: &str, offset: usize) {
+        self.label_offsets
+            .entry(label.to_string())
+            .or_insert(offset);
+    }
+
+    // Where the alias or the label an analyzer error is about is written in the query.
+    pub fn source_offset(&self, error: &AnalyzerError) -> Option<usize> {
+        match error {
+            AnalyzerError::OrphanAlias { alias, .. } => self.alias_offsets.get(alias).copied(),
+            AnalyzerError::GraphSchema { source, .. } => match source {
+                GraphSchemaError::Node { node_label } => {
+                    self.label_offsets.get(node_label).copied()
+                }
+                GraphSchemaError::Relation { rel_label }
+                | GraphSchemaError::RelationIndex { rel_label } => {
+                    self.label_offsets.get(rel_label).copied()
+                }
+            },
+            _ => None,
+        }
+    }
+
     pub fn get_mut_table_ctx_opt(&mut self, alias: &str) -> Option<&mut TableCtx> {
         self.alias_table_ctx_map.get_mut(alias)
     }
@@ -219,6 +335,11 @@ impl PlanCtx {
     pub fn default() -> Self {
         PlanCtx {
             alias_table_ctx_map: HashMap::new(),
+            path_ctx_map: HashMap::new(),
+            plan_snapshots: None,
+            permissions: None,
+            alias_offsets: HashMap::new(),
+            label_offsets: HashMap::new(),
         }
     }
 }
@@ -230,6 +351,11 @@ impl fmt::Display for PlanCtx {
             writeln!(f, "\n [{}]:", alias)?;
             table_ctx.fmt_with_indent(f, 2)?;
         }
+        for (path_alias, path_ctx) in &self.path_ctx_map {
+            writeln!(f, "\n [{}]:", path_alias)?;
+            writeln!(f, "           node_aliases: {:?}", path_ctx.node_aliases)?;
+            writeln!(f, "           rel_aliases: {:?}", path_ctx.rel_aliases)?;
+        }
         writeln!(f, "\n---- PlanCtx Ends Here ----")?;
         Ok(())
     }
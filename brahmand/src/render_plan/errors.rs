@@ -10,4 +10,7 @@ pub enum RenderBuildError {
 
     #[error("Malformed CTE name.")]
     MalformedCTEName,
+
+    #[error("Missing value for parameter `${0}`.")]
+    MissingParameter(String),
 }
@@ -9,6 +9,7 @@ use crate::query_planner::logical_plan::{
 use std::fmt;
 
 pub mod errors;
+pub mod parameters;
 pub mod plan_builder;
 pub mod render_expr;
 
@@ -63,6 +64,7 @@ pub struct Join {
     pub table_alias: String,
     pub joining_on: Vec<OperatorApplication>,
     pub join_type: JoinType,
+    pub global: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -101,6 +103,7 @@ impl TryFrom<LogicalJoin> for Join {
                 .map(OperatorApplication::try_from)
                 .collect::<Result<Vec<OperatorApplication>, RenderBuildError>>()?,
             join_type: value.join_type.clone().try_into()?,
+            global: value.global,
         };
         Ok(join)
     }
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use super::{
+    RenderPlan,
+    errors::RenderBuildError,
+    render_expr::{Literal, RenderExpr},
+};
+
+impl RenderPlan {
+    // Replaces $parameters in every expression of the plan with their values. Values are
+    // expected to be literals or lists of literals.
+    pub fn bind_parameters(
+        &mut self,
+        parameters: &HashMap<String, RenderExpr>,
+    ) -> Result<(), RenderBuildError> {
+        for cte in self.ctes.0.iter_mut() {
+            cte.cte_plan.bind_parameters(parameters)?;
+        }
+        for select_item in self.select.0.iter_mut() {
+            bind_expr(&mut select_item.expression, parameters)?;
+        }
+        for join in self.joins.0.iter_mut() {
+            for operator_application in join.joining_on.iter_mut() {
+                for operand in operator_application.operands.iter_mut() {
+                    bind_expr(operand, parameters)?;
+                }
+            }
+        }
+        if let Some(filter) = self.filters.0.as_mut() {
+            bind_expr(filter, parameters)?;
+        }
+        for group_by_expr in self.group_by.0.iter_mut() {
+            bind_expr(group_by_expr, parameters)?;
+        }
+        for order_by_item in self.order_by.0.iter_mut() {
+            bind_expr(&mut order_by_item.expression, parameters)?;
+        }
+        if let Some(union) = self.union.0.as_mut() {
+            for input in union.input.iter_mut() {
+                input.bind_parameters(parameters)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bind_expr(
+    expr: &mut RenderExpr,
+    parameters: &HashMap<String, RenderExpr>,
+) -> Result<(), RenderBuildError> {
+    match expr {
+        RenderExpr::Parameter(name) => {
+            let value = parameters
+                .get(name)
+                .ok_or_else(|| RenderBuildError::MissingParameter(name.clone()))?;
+            *expr = escape_string_literals(value.clone());
+        }
+        RenderExpr::List(items) => {
+            for item in items {
+                bind_expr(item, parameters)?;
+            }
+        }
+        RenderExpr::AggregateFnCall(fn_call) => {
+            for arg in fn_call.args.iter_mut() {
+                bind_expr(arg, parameters)?;
+            }
+        }
+        RenderExpr::ScalarFnCall(fn_call) => {
+            for arg in fn_call.args.iter_mut() {
+                bind_expr(arg, parameters)?;
+            }
+        }
+        RenderExpr::OperatorApplicationExp(operator_application) => {
+            for operand in operator_application.operands.iter_mut() {
+                bind_expr(operand, parameters)?;
+            }
+        }
+        RenderExpr::InSubquery(in_subquery) => {
+            bind_expr(&mut in_subquery.expr, parameters)?;
+            in_subquery.subplan.bind_parameters(parameters)?;
+        }
+        RenderExpr::Literal(_)
+        | RenderExpr::Star
+        | RenderExpr::TableAlias(_)
+        | RenderExpr::ColumnAlias(_)
+        | RenderExpr::Column(_)
+        | RenderExpr::PropertyAccessExp(_) => {}
+    }
+    Ok(())
+}
+
+// String literals from the query are written to SQL as they are, parameter values come from
+// the client and are escaped so they can not close the quotes.
+fn escape_string_literals(value: RenderExpr) -> RenderExpr {
+    match value {
+        RenderExpr::Literal(Literal::String(s)) => RenderExpr::Literal(Literal::String(
+            s.replace('\\', "\\\\").replace('\'', "\\'"),
+        )),
+        RenderExpr::List(items) => {
+            RenderExpr::List(items.into_iter().map(escape_string_literals).collect())
+        }
+        other => other,
+    }
+}
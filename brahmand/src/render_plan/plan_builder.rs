@@ -340,19 +340,16 @@ impl RenderPlanBuilder for LogicalPlan {
 
             let final_filters_opt = self.extract_final_filters()?;
 
-            let final_combined_filters =
-                if last_node_filters_opt.is_some() && final_filters_opt.is_some() {
+            let final_combined_filters = match (final_filters_opt, last_node_filters_opt) {
+                (Some(final_filters), Some(last_node_filters)) => {
                     Some(RenderExpr::OperatorApplicationExp(OperatorApplication {
                         operator: Operator::And,
-                        operands: vec![final_filters_opt.unwrap(), last_node_filters_opt.unwrap()],
+                        operands: vec![final_filters, last_node_filters],
                     }))
-                } else if final_filters_opt.is_some() {
-                    final_filters_opt
-                } else if last_node_filters_opt.is_some() {
-                    last_node_filters_opt
-                } else {
-                    None
-                };
+                }
+                (Some(final_filters), None) => Some(final_filters),
+                (None, last_node_filters_opt) => last_node_filters_opt,
+            };
 
             final_filters = final_combined_filters;
         } else {
@@ -44,6 +44,7 @@ pub enum RenderExpr {
 pub struct InSubquery {
     pub expr: Box<RenderExpr>,
     pub subplan: Box<RenderPlan>,
+    pub global: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -151,6 +152,7 @@ impl TryFrom<LogicalInSubquery> for InSubquery {
         let in_sub_query = InSubquery {
             expr: Box::new((value.expr.as_ref().clone()).try_into()?),
             subplan: Box::new(sub_plan),
+            global: value.global,
         };
         Ok(in_sub_query)
     }
@@ -0,0 +1,116 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{graph_catalog::graph_permissions::GraphPermissions, server::config::ConfigError};
+
+use super::Identity;
+
+// Graph permissions per role, loaded from BRAHMAND_ACL_FILE e.g.
+// {
+//   "roles": {
+//     "analyst": {
+//       "nodes": {"User": {"hidden_properties": ["email"], "row_filter": "n.country = 'IN'"}},
+//       "relationships": {"FOLLOWS": {}}
+//     },
+//     "modeler": {"ddl": true}
+//   },
+//   "users": {"alice": ["analyst"]}
+// }
+// Roles come from the JWT roles claim and from `users`. A caller without any role can not read
+// anything. DDL is only allowed to the admin role and to roles with `ddl`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AccessControl {
+    roles: HashMap<String, GraphPermissions>,
+    users: HashMap<String, Vec<String>>,
+}
+
+impl AccessControl {
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let acl = fs::read_to_string(path).map_err(|e| ConfigError::file(path, e))?;
+        let access_control: AccessControl =
+            serde_json::from_str(&acl).map_err(|e| ConfigError::file(path, e))?;
+        for (role, permissions) in &access_control.roles {
+            if let Err(e) = permissions.validate() {
+                return Err(ConfigError::file(
+                    path,
+                    format!("invalid permissions of role `{role}`: {e}"),
+                ));
+            }
+        }
+        Ok(access_control)
+    }
+
+    pub fn permissions(&self, identity: &Identity) -> GraphPermissions {
+        GraphPermissions::union(self.roles(identity).filter_map(|role| self.roles.get(role)))
+    }
+
+    pub fn can_run_ddl(&self, identity: &Identity, admin_role: &str) -> bool {
+        self.roles(identity).any(|role| role == admin_role) || self.permissions(identity).ddl
+    }
+
+    // Roles of the JWT and the ones given to the user in the file.
+    pub fn roles<'a>(&'a self, identity: &'a Identity) -> impl Iterator<Item = &'a String> {
+        let user_roles = self.users.get(&identity.user).into_iter().flatten();
+        identity.roles.iter().chain(user_roles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_permissions() {
+        let access_control: AccessControl = serde_json::from_value(json!({
+            "roles": {
+                "analyst": {"nodes": {"User": {"hidden_properties": ["email"]}}},
+                "auditor": {"relationships": {"PAYS": {}}},
+            },
+            "users": {"alice": ["analyst"]},
+        }))
+        .unwrap();
+
+        let alice = Identity {
+            user: "alice".to_string(),
+            roles: vec!["auditor".to_string()],
+            password: None,
+        };
+        let permissions = access_control.permissions(&alice);
+        assert!(permissions.nodes.contains_key("User"));
+        assert!(permissions.relationships.contains_key("PAYS"));
+        assert_eq!(
+            access_control.roles(&alice).collect::<Vec<_>>(),
+            vec!["auditor", "analyst"]
+        );
+
+        assert_eq!(
+            access_control.permissions(&Identity::anonymous()),
+            GraphPermissions::default()
+        );
+    }
+
+    #[test]
+    fn test_can_run_ddl() {
+        let access_control: AccessControl = serde_json::from_value(json!({
+            "roles": {
+                "analyst": {"nodes": {"User": {}}},
+                "modeler": {"ddl": true},
+            },
+            "users": {"alice": ["analyst"], "bob": ["analyst", "modeler"]},
+        }))
+        .unwrap();
+        let identity = |user: &str, roles: &[&str]| Identity {
+            user: user.to_string(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            password: None,
+        };
+
+        // a reader of User can not create tables or indexes
+        assert!(!access_control.can_run_ddl(&identity("alice", &[]), "admin"));
+        assert!(access_control.can_run_ddl(&identity("bob", &[]), "admin"));
+        assert!(access_control.can_run_ddl(&identity("alice", &["admin"]), "admin"));
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use openssl::{
+    bn::BigNum,
+    ec::{EcGroup, EcKey},
+    ecdsa::EcdsaSig,
+    hash::MessageDigest,
+    nid::Nid,
+    pkey::{Id, PKey, Public},
+    rsa::Rsa,
+    sign::Verifier,
+};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::server::config::{ConfigError, JwtConfig, JwtKeyFile};
+
+use super::Identity;
+
+// allowed clock skew between brahmand and the token issuer
+const LEEWAY_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum JwtError {
+    #[error("Malformed token.")]
+    Malformed,
+    #[error("Unsupported signing algorithm `{0}`.")]
+    UnsupportedAlgorithm(String),
+    #[error("No key found to verify the token.")]
+    UnknownKey,
+    #[error("Invalid token signature.")]
+    InvalidSignature,
+    #[error("Token has expired.")]
+    Expired,
+    #[error("Token is not valid yet.")]
+    NotYetValid,
+    #[error("Token issuer is not accepted.")]
+    InvalidIssuer,
+    #[error("Token audience is not accepted.")]
+    InvalidAudience,
+    #[error("Token has no `{0}` claim.")]
+    MissingClaim(String),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+}
+
+impl From<openssl::error::ErrorStack> for JwtError {
+    fn from(value: openssl::error::ErrorStack) -> Self {
+        JwtError::InvalidKey(value.to_string())
+    }
+}
+
+struct JwtKey {
+    // key id from the JWKS, tokens name the key which signed them in their header
+    kid: Option<String>,
+    key: PKey<Public>,
+}
+
+pub struct JwtValidator {
+    keys: Vec<JwtKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    user_claim: String,
+    roles_claim: String,
+}
+
+impl JwtValidator {
+    // JWT is enabled by configuring either a PEM public key or a JWKS file.
+    pub fn from_config(config: &JwtConfig) -> Result<Self, ConfigError> {
+        let keys = match &config.key_file {
+            JwtKeyFile::PublicKey(path) => {
+                let pem = fs::read(path).map_err(|e| ConfigError::file(path, e))?;
+                keys_from_pem(&pem).map_err(|e| ConfigError::file(path, e))?
+            }
+            JwtKeyFile::Jwks(path) => {
+                let jwks = fs::read_to_string(path).map_err(|e| ConfigError::file(path, e))?;
+                keys_from_jwks(&jwks).map_err(|e| ConfigError::file(path, e))?
+            }
+        };
+
+        Ok(JwtValidator {
+            keys,
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            user_claim: config.user_claim.clone(),
+            roles_claim: config.roles_claim.clone(),
+        })
+    }
+
+    pub fn validate(&self, token: &str) -> Result<Identity, JwtError> {
+        let parts: Vec<&str> = token.split('.').collect();
+        let [header, payload, signature] = parts[..] else {
+            return Err(JwtError::Malformed);
+        };
+        let signing_input = format!("{header}.{payload}");
+        let header = decode_json(header)?;
+        let claims = decode_json(payload)?;
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| JwtError::Malformed)?;
+
+        let alg = header["alg"].as_str().unwrap_or_default();
+        let kid = header["kid"].as_str();
+        self.verify_signature(alg, kid, signing_input.as_bytes(), &signature)?;
+        self.validate_claims(&claims)?;
+
+        let user = claims[&self.user_claim]
+            .as_str()
+            .ok_or_else(|| JwtError::MissingClaim(self.user_claim.clone()))?;
+        let roles = match &claims[&self.roles_claim] {
+            Value::String(role) => vec![role.clone()],
+            Value::Array(roles) => roles
+                .iter()
+                .filter_map(|role| role.as_str().map(String::from))
+                .collect(),
+            _ => vec![],
+        };
+        Ok(Identity {
+            user: user.to_string(),
+            roles,
+            password: None,
+        })
+    }
+
+    fn verify_signature(
+        &self,
+        alg: &str,
+        kid: Option<&str>,
+        signing_input: &[u8],
+        signature: &[u8],
+    ) -> Result<(), JwtError> {
+        let (digest, key_type) = match alg {
+            "RS256" => (MessageDigest::sha256(), Id::RSA),
+            "RS384" => (MessageDigest::sha384(), Id::RSA),
+            "RS512" => (MessageDigest::sha512(), Id::RSA),
+            "ES256" => (MessageDigest::sha256(), Id::EC),
+            "ES384" => (MessageDigest::sha384(), Id::EC),
+            "ES512" => (MessageDigest::sha512(), Id::EC),
+            _ => return Err(JwtError::UnsupportedAlgorithm(alg.to_string())),
+        };
+
+        // JWS has the raw r and s of ECDSA signatures, openssl expects them DER encoded
+        let signature = if key_type == Id::EC {
+            let (r, s) = signature.split_at(signature.len() / 2);
+            EcdsaSig::from_private_components(BigNum::from_slice(r)?, BigNum::from_slice(s)?)?
+                .to_der()?
+        } else {
+            signature.to_vec()
+        };
+
+        let mut candidate_keys = self
+            .keys
+            .iter()
+            .filter(|jwt_key| jwt_key.key.id() == key_type)
+            .filter(|jwt_key| {
+                kid.is_none() || jwt_key.kid.is_none() || jwt_key.kid.as_deref() == kid
+            })
+            .peekable();
+        if candidate_keys.peek().is_none() {
+            return Err(JwtError::UnknownKey);
+        }
+
+        for jwt_key in candidate_keys {
+            let mut verifier = Verifier::new(digest, &jwt_key.key)?;
+            verifier.update(signing_input)?;
+            if verifier.verify(&signature).unwrap_or(false) {
+                return Ok(());
+            }
+        }
+        Err(JwtError::InvalidSignature)
+    }
+
+    fn validate_claims(&self, claims: &Value) -> Result<(), JwtError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        if let Some(exp) = claims["exp"].as_u64() {
+            if now > exp + LEEWAY_SECS {
+                return Err(JwtError::Expired);
+            }
+        }
+        if let Some(nbf) = claims["nbf"].as_u64() {
+            if now + LEEWAY_SECS < nbf {
+                return Err(JwtError::NotYetValid);
+            }
+        }
+
+        if let Some(issuer) = &self.issuer {
+            if claims["iss"].as_str() != Some(issuer) {
+                return Err(JwtError::InvalidIssuer);
+            }
+        }
+        if let Some(audience) = &self.audience {
+            let accepted = match &claims["aud"] {
+                Value::String(aud) => aud == audience,
+                Value::Array(auds) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+                _ => false,
+            };
+            if !accepted {
+                return Err(JwtError::InvalidAudience);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn decode_json(part: &str) -> Result<Value, JwtError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(part)
+        .map_err(|_| JwtError::Malformed)?;
+    serde_json::from_slice(&bytes).map_err(|_| JwtError::Malformed)
+}
+
+fn keys_from_pem(pem: &[u8]) -> Result<Vec<JwtKey>, JwtError> {
+    Ok(vec![JwtKey {
+        kid: None,
+        key: PKey::public_key_from_pem(pem)?,
+    }])
+}
+
+fn keys_from_jwks(jwks: &str) -> Result<Vec<JwtKey>, JwtError> {
+    let jwks: Value =
+        serde_json::from_str(jwks).map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+    let Some(jwks_keys) = jwks["keys"].as_array() else {
+        return Err(JwtError::InvalidKey("JWKS has no keys.".to_string()));
+    };
+
+    let big_num = |jwk: &Value, name: &str| -> Result<BigNum, JwtError> {
+        let value = jwk[name]
+            .as_str()
+            .ok_or_else(|| JwtError::InvalidKey(format!("JWK has no `{name}`.")))?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .map_err(|e| JwtError::InvalidKey(e.to_string()))?;
+        Ok(BigNum::from_slice(&bytes)?)
+    };
+
+    let mut keys = vec![];
+    for jwk in jwks_keys {
+        let key = match jwk["kty"].as_str() {
+            Some("RSA") => PKey::from_rsa(Rsa::from_public_components(
+                big_num(jwk, "n")?,
+                big_num(jwk, "e")?,
+            )?)?,
+            Some("EC") => {
+                let nid = match jwk["crv"].as_str() {
+                    Some("P-256") => Nid::X9_62_PRIME256V1,
+                    Some("P-384") => Nid::SECP384R1,
+                    Some("P-521") => Nid::SECP521R1,
+                    crv => {
+                        return Err(JwtError::InvalidKey(format!(
+                            "Unsupported curve {:?}.",
+                            crv
+                        )));
+                    }
+                };
+                let group = EcGroup::from_curve_name(nid)?;
+                let (x, y) = (big_num(jwk, "x")?, big_num(jwk, "y")?);
+                PKey::from_ec_key(EcKey::from_public_key_affine_coordinates(&group, &x, &y)?)?
+            }
+            // other key types, e.g. symmetric keys, are not used for verification
+            _ => continue,
+        };
+        keys.push(JwtKey {
+            kid: jwk["kid"].as_str().map(String::from),
+            key,
+        });
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::{bn::BigNumContext, ec::PointConversionForm, pkey::Private, sign::Signer};
+    use serde_json::json;
+
+    fn sign(alg: &str, kid: &str, claims: &Value, key: &PKey<Private>) -> String {
+        let header = json!({"alg": alg, "typ": "JWT", "kid": kid});
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(signing_input.as_bytes()).unwrap();
+        let mut signature = signer.sign_to_vec().unwrap();
+        if alg.starts_with("ES") {
+            let ecdsa_sig = EcdsaSig::from_der(&signature).unwrap();
+            signature = [
+                ecdsa_sig.r().to_vec_padded(32).unwrap(),
+                ecdsa_sig.s().to_vec_padded(32).unwrap(),
+            ]
+            .concat();
+        }
+        format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    fn validator(keys: Vec<JwtKey>) -> JwtValidator {
+        JwtValidator {
+            keys,
+            issuer: Some("https://issuer.example".to_string()),
+            audience: Some("brahmand".to_string()),
+            user_claim: "sub".to_string(),
+            roles_claim: "roles".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rs256_with_pem_key() {
+        let private_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let pem = private_key.public_key_to_pem().unwrap();
+        let validator = validator(keys_from_pem(&pem).unwrap());
+
+        let claims = json!({
+            "sub": "alice",
+            "roles": ["analyst"],
+            "iss": "https://issuer.example",
+            "aud": ["brahmand", "other"],
+            "exp": 4_000_000_000u64,
+        });
+        let token = sign("RS256", "k1", &claims, &private_key);
+        assert_eq!(
+            validator.validate(&token),
+            Ok(Identity {
+                user: "alice".to_string(),
+                roles: vec!["analyst".to_string()],
+                password: None,
+            })
+        );
+
+        let tampered = token.replacen('.', ".e30", 1);
+        assert!(validator.validate(&tampered).is_err());
+        assert_eq!(validator.validate("abc"), Err(JwtError::Malformed));
+
+        let expired = sign(
+            "RS256",
+            "k1",
+            &json!({"sub": "alice", "exp": 1}),
+            &private_key,
+        );
+        assert_eq!(validator.validate(&expired), Err(JwtError::Expired));
+
+        let other_issuer = json!({"sub": "alice", "iss": "x", "aud": "brahmand"});
+        let token = sign("RS256", "k1", &other_issuer, &private_key);
+        assert_eq!(validator.validate(&token), Err(JwtError::InvalidIssuer));
+
+        // signed by a key which is not configured
+        let other_key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let token = sign("RS256", "k1", &claims, &other_key);
+        assert_eq!(validator.validate(&token), Err(JwtError::InvalidSignature));
+    }
+
+    #[test]
+    fn test_es256_with_jwks() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let mut ctx = BigNumContext::new().unwrap();
+        let point = ec_key
+            .public_key()
+            .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+            .unwrap();
+        // uncompressed points are 0x04 || x || y
+        let jwks = json!({"keys": [
+            {"kty": "oct", "k": "c2VjcmV0"},
+            {
+                "kty": "EC",
+                "kid": "ec-1",
+                "crv": "P-256",
+                "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+            },
+        ]});
+        let validator = validator(keys_from_jwks(&jwks.to_string()).unwrap());
+        let private_key = PKey::from_ec_key(ec_key).unwrap();
+
+        let claims = json!({"sub": "bob", "iss": "https://issuer.example", "aud": "brahmand"});
+        let token = sign("ES256", "ec-1", &claims, &private_key);
+        assert_eq!(validator.validate(&token).unwrap().user, "bob");
+
+        let token = sign("ES256", "ec-2", &claims, &private_key);
+        assert_eq!(validator.validate(&token), Err(JwtError::UnknownKey));
+    }
+}
@@ -0,0 +1,421 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, header},
+    middleware::Next,
+    response::Response,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use clickhouse::Client;
+use jwt::JwtValidator;
+
+use super::{
+    AppState,
+    config::{AuthConfig, ConfigError},
+    errors::BrahmandError,
+};
+
+pub mod access_control;
+pub mod jwt;
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// Forwarded Basic credentials are checked against ClickHouse at most once per this interval.
+const VERIFIED_CREDENTIALS_TTL: Duration = Duration::from_secs(60);
+
+// Caller of a request, inserted into the request extensions by `authenticate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    pub user: String,
+    pub roles: Vec<String>,
+    // only set for HTTP Basic, kept to forward the credentials to ClickHouse
+    pub password: Option<String>,
+}
+
+impl Identity {
+    pub fn anonymous() -> Self {
+        Identity {
+            user: String::new(),
+            roles: vec![],
+            password: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credentials {
+    ApiKey(String),
+    Basic { user: String, password: String },
+    // a JWT or an API key
+    Bearer(String),
+}
+
+// How queries of an authenticated caller are run in ClickHouse.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum IdentityForwarding {
+    // with the service user of CLICKHOUSE_USER
+    #[default]
+    None,
+    // with the Basic credentials of the caller, ClickHouse checks them on every query
+    Credentials,
+    // with the service user and `SET ROLE <user>` (ClickHouse 24.4+)
+    Role,
+}
+
+#[derive(Default)]
+pub struct Authenticator {
+    // api key -> user
+    api_keys: HashMap<String, String>,
+    // user -> password
+    basic_users: HashMap<String, String>,
+    jwt: Option<JwtValidator>,
+    forwarding: IdentityForwarding,
+    // checks the forwarded Basic credentials with `SELECT 1`
+    clickhouse_client: Client,
+    // user -> sha256 of the password and when it was accepted by ClickHouse
+    verified_credentials: Mutex<HashMap<String, ([u8; 32], Instant)>>,
+}
+
+impl Authenticator {
+    // Authentication is disabled unless one of the methods is configured e.g.
+    // BRAHMAND_API_KEYS="key1=alice,key2=bob", BRAHMAND_BASIC_USERS="alice:secret",
+    // BRAHMAND_JWT_PUBLIC_KEY_FILE=/etc/brahmand/jwt.pem
+    pub fn from_config(
+        config: &AuthConfig,
+        clickhouse_client: &Client,
+    ) -> Result<Self, ConfigError> {
+        Ok(Authenticator {
+            api_keys: config.api_keys.clone(),
+            basic_users: config.basic_users.clone(),
+            jwt: config
+                .jwt
+                .as_ref()
+                .map(JwtValidator::from_config)
+                .transpose()?,
+            forwarding: config.forwarding,
+            clickhouse_client: clickhouse_client.clone(),
+            verified_credentials: Mutex::default(),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.api_keys.is_empty() || self.basic_enabled() || self.jwt.is_some()
+    }
+
+    // Basic credentials are either checked against BRAHMAND_BASIC_USERS or passed through
+    // to ClickHouse.
+    fn basic_enabled(&self) -> bool {
+        !self.basic_users.is_empty() || self.forwarding == IdentityForwarding::Credentials
+    }
+
+    pub async fn authenticate(
+        &self,
+        credentials: Option<Credentials>,
+    ) -> Result<Identity, BrahmandError> {
+        if !self.is_enabled() {
+            return Ok(Identity::anonymous());
+        }
+        let unauthorized = |message: &str| BrahmandError::Unauthorized(message.to_string());
+
+        match credentials {
+            None => Err(unauthorized("Authentication is required.")),
+            Some(Credentials::ApiKey(key)) => self.authenticate_api_key(&key),
+            Some(Credentials::Bearer(token)) => match &self.jwt {
+                // JWTs have three dot separated parts, anything else is taken as an API key
+                Some(jwt) if token.split('.').count() == 3 => jwt
+                    .validate(&token)
+                    .map_err(|e| BrahmandError::Unauthorized(e.to_string())),
+                _ => self.authenticate_api_key(&token),
+            },
+            Some(Credentials::Basic { user, password }) => {
+                if !self.basic_enabled() {
+                    return Err(unauthorized("HTTP Basic authentication is not enabled."));
+                }
+                if self.basic_users.is_empty() {
+                    // the identity decides who can see and cancel queries and jobs, so the
+                    // forwarded credentials are checked before it is trusted
+                    self.verify_with_clickhouse(&user, &password).await?;
+                } else {
+                    let valid = self
+                        .basic_users
+                        .get(&user)
+                        .is_some_and(|expected| constant_time_eq(expected, &password));
+                    if !valid {
+                        return Err(unauthorized("Invalid user or password."));
+                    }
+                }
+                Ok(Identity {
+                    user,
+                    roles: vec![],
+                    password: Some(password),
+                })
+            }
+        }
+    }
+
+    async fn verify_with_clickhouse(
+        &self,
+        user: &str,
+        password: &str,
+    ) -> Result<(), BrahmandError> {
+        let digest = openssl::sha::sha256(password.as_bytes());
+        let cached = self
+            .verified_credentials
+            .lock()
+            .unwrap()
+            .get(user)
+            .is_some_and(|(expected, verified_at)| {
+                verified_at.elapsed() < VERIFIED_CREDENTIALS_TTL
+                    && openssl::memcmp::eq(expected, &digest)
+            });
+        if cached {
+            return Ok(());
+        }
+
+        let result = self
+            .clickhouse_client
+            .clone()
+            .with_user(user)
+            .with_password(password)
+            .query("SELECT 1")
+            .fetch_one::<u8>()
+            .await;
+        match result {
+            Ok(_) => {
+                self.verified_credentials
+                    .lock()
+                    .unwrap()
+                    .insert(user.to_string(), (digest, Instant::now()));
+                Ok(())
+            }
+            // ClickHouse answered and refused the credentials
+            Err(clickhouse::error::Error::BadResponse(_)) => Err(BrahmandError::Unauthorized(
+                "Invalid user or password.".to_string(),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn authenticate_api_key(&self, key: &str) -> Result<Identity, BrahmandError> {
+        self.api_keys
+            .iter()
+            .find(|(expected, _)| constant_time_eq(expected, key))
+            .map(|(_, user)| Identity {
+                user: user.clone(),
+                roles: vec![],
+                password: None,
+            })
+            .ok_or_else(|| BrahmandError::Unauthorized("Invalid API key.".to_string()))
+    }
+
+    // Client to run the queries of `identity` with, so that ClickHouse row policies and quotas
+    // of the caller apply.
+    pub fn clickhouse_client(
+        &self,
+        client: &Client,
+        identity: &Identity,
+    ) -> Result<Client, BrahmandError> {
+        if identity.user.is_empty() {
+            return Ok(client.clone());
+        }
+        match self.forwarding {
+            IdentityForwarding::None => Ok(client.clone()),
+            IdentityForwarding::Role => Ok(client.clone().with_option("role", &identity.user)),
+            IdentityForwarding::Credentials => match &identity.password {
+                Some(password) => Ok(client
+                    .clone()
+                    .with_user(&identity.user)
+                    .with_password(password)),
+                None => Err(BrahmandError::Forbidden(
+                    "ClickHouse credentials are forwarded, authenticate with HTTP Basic."
+                        .to_string(),
+                )),
+            },
+        }
+    }
+}
+
+// Reads `Authorization: Basic|Bearer ...` or the X-API-Key header.
+pub fn credentials_from_headers(headers: &HeaderMap) -> Result<Option<Credentials>, BrahmandError> {
+    let invalid = || BrahmandError::Unauthorized("Invalid Authorization header.".to_string());
+
+    if let Some(authorization) = headers.get(header::AUTHORIZATION) {
+        let authorization = authorization.to_str().map_err(|_| invalid())?;
+        let (scheme, value) = authorization.split_once(' ').ok_or_else(invalid)?;
+        let value = value.trim();
+        return match scheme.to_ascii_lowercase().as_str() {
+            "basic" => {
+                let decoded = STANDARD.decode(value).map_err(|_| invalid())?;
+                let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+                let (user, password) = decoded.split_once(':').ok_or_else(invalid)?;
+                Ok(Some(Credentials::Basic {
+                    user: user.to_string(),
+                    password: password.to_string(),
+                }))
+            }
+            "bearer" => Ok(Some(Credentials::Bearer(value.to_string()))),
+            _ => Err(invalid()),
+        };
+    }
+
+    match headers.get(API_KEY_HEADER) {
+        Some(key) => Ok(Some(Credentials::ApiKey(
+            key.to_str().map_err(|_| invalid())?.to_string(),
+        ))),
+        None => Ok(None),
+    }
+}
+
+// Middleware of the HTTP routes, handlers take the caller as `Extension<Identity>`.
+pub async fn authenticate(
+    State(app_state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, BrahmandError> {
+    let credentials = credentials_from_headers(request.headers())?;
+    let identity = app_state.authenticator.authenticate(credentials).await?;
+    request.extensions_mut().insert(identity);
+    Ok(next.run(request).await)
+}
+
+fn constant_time_eq(expected: &str, actual: &str) -> bool {
+    expected.len() == actual.len() && openssl::memcmp::eq(expected.as_bytes(), actual.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use clickhouse::test::{Mock, handlers, status};
+
+    fn authenticator() -> Authenticator {
+        Authenticator {
+            api_keys: HashMap::from([("key-1".to_string(), "alice".to_string())]),
+            basic_users: HashMap::from([("bob".to_string(), "secret".to_string())]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_credentials_from_headers() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(credentials_from_headers(&headers).unwrap(), None);
+
+        headers.insert(API_KEY_HEADER, HeaderValue::from_static("key-1"));
+        assert_eq!(
+            credentials_from_headers(&headers).unwrap(),
+            Some(Credentials::ApiKey("key-1".to_string()))
+        );
+
+        // bob:secret
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic Ym9iOnNlY3JldA=="),
+        );
+        assert_eq!(
+            credentials_from_headers(&headers).unwrap(),
+            Some(Credentials::Basic {
+                user: "bob".to_string(),
+                password: "secret".to_string(),
+            })
+        );
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Digest x"));
+        assert!(matches!(
+            credentials_from_headers(&headers),
+            Err(BrahmandError::Unauthorized(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let authenticator = authenticator();
+        assert_eq!(
+            authenticator
+                .authenticate(Some(Credentials::Bearer("key-1".to_string())))
+                .await
+                .unwrap()
+                .user,
+            "alice"
+        );
+        assert!(
+            authenticator
+                .authenticate(Some(Credentials::ApiKey("key-2".to_string())))
+                .await
+                .is_err()
+        );
+        assert!(authenticator.authenticate(None).await.is_err());
+
+        let bob = authenticator
+            .authenticate(Some(Credentials::Basic {
+                user: "bob".to_string(),
+                password: "secret".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(bob.password.as_deref(), Some("secret"));
+        assert!(
+            authenticator
+                .authenticate(Some(Credentials::Basic {
+                    user: "bob".to_string(),
+                    password: "secrets".to_string(),
+                }))
+                .await
+                .is_err()
+        );
+
+        // nothing configured, every request is allowed
+        assert_eq!(
+            Authenticator::default().authenticate(None).await.unwrap(),
+            Identity::anonymous()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_credentials() {
+        let authenticator = Authenticator {
+            forwarding: IdentityForwarding::Credentials,
+            ..authenticator()
+        };
+        let client = Client::default();
+        let alice = authenticator
+            .authenticate(Some(Credentials::ApiKey("key-1".to_string())))
+            .await
+            .unwrap();
+        assert!(matches!(
+            authenticator.clickhouse_client(&client, &alice),
+            Err(BrahmandError::Forbidden(_))
+        ));
+
+        // Basic users are not configured, ClickHouse checks the credentials once per TTL
+        let mock = Mock::new();
+        mock.add(handlers::failure(status::UNAUTHORIZED));
+        mock.add(handlers::provide(vec![1u8]));
+        mock.add(handlers::failure(status::UNAUTHORIZED));
+        let forwarding_only = Authenticator {
+            forwarding: IdentityForwarding::Credentials,
+            clickhouse_client: Client::default().with_url(mock.url()),
+            ..Default::default()
+        };
+        let basic = |password: &str| {
+            Some(Credentials::Basic {
+                user: "carol".to_string(),
+                password: password.to_string(),
+            })
+        };
+        assert!(matches!(
+            forwarding_only.authenticate(basic("wrong")).await,
+            Err(BrahmandError::Unauthorized(_))
+        ));
+        let carol = forwarding_only.authenticate(basic("pw")).await.unwrap();
+        assert!(forwarding_only.clickhouse_client(&client, &carol).is_ok());
+        // cached, only a different password is checked again
+        assert!(forwarding_only.authenticate(basic("pw")).await.is_ok());
+        assert!(forwarding_only.authenticate(basic("other")).await.is_err());
+    }
+}
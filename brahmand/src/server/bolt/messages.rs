@@ -0,0 +1,494 @@
+use serde_json::{Map, Value};
+
+use crate::graph_catalog::graph_schema::GraphSchema;
+
+use super::{
+    super::{
+        auth::Credentials,
+        errors::BrahmandError,
+        graph_output::{edge_keys, is_edge, is_node, legacy_id, node_key},
+        handlers,
+        metrics::METRICS,
+    },
+    BoltError, BoltVersion,
+    packstream::PackStreamValue,
+};
+
+const NODE_TAG: u8 = 0x4E;
+const RELATIONSHIP_TAG: u8 = 0x52;
+const UNBOUND_RELATIONSHIP_TAG: u8 = 0x72;
+const PATH_TAG: u8 = 0x50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    // credentials are in HELLO up to Bolt 5.0 and in LOGON after
+    Hello {
+        credentials: Option<Credentials>,
+    },
+    Logon {
+        credentials: Option<Credentials>,
+    },
+    Logoff,
+    Goodbye,
+    Reset,
+    Run {
+        query: String,
+        parameters: PackStreamValue,
+    },
+    Begin,
+    Commit,
+    Rollback,
+    Discard,
+    Pull {
+        // -1 pulls every remaining record
+        n: i64,
+    },
+    Route,
+}
+
+impl TryFrom<PackStreamValue> for Request {
+    type Error = BoltError;
+
+    fn try_from(value: PackStreamValue) -> Result<Self, Self::Error> {
+        let PackStreamValue::Struct { tag, mut fields } = value else {
+            return Err(BoltError::Protocol(
+                "Bolt message should be a structure.".to_string(),
+            ));
+        };
+
+        let request = match tag {
+            0x01 => Request::Hello {
+                credentials: fields.first().and_then(credentials_from_auth),
+            },
+            0x6A => Request::Logon {
+                credentials: fields.first().and_then(credentials_from_auth),
+            },
+            0x6B => Request::Logoff,
+            0x02 => Request::Goodbye,
+            0x0F => Request::Reset,
+            0x10 => {
+                if fields.is_empty() {
+                    return Err(BoltError::Protocol(
+                        "RUN message without a query.".to_string(),
+                    ));
+                }
+                let query = fields.remove(0);
+                let Some(query) = query.as_str() else {
+                    return Err(BoltError::Protocol(
+                        "Query of the RUN message should be a string.".to_string(),
+                    ));
+                };
+                Request::Run {
+                    query: query.to_string(),
+                    parameters: fields
+                        .into_iter()
+                        .next()
+                        .unwrap_or(PackStreamValue::Map(vec![])),
+                }
+            }
+            0x11 => Request::Begin,
+            0x12 => Request::Commit,
+            0x13 => Request::Rollback,
+            0x2F => Request::Discard,
+            0x3F => Request::Pull {
+                n: fields
+                    .first()
+                    .and_then(|extra| extra.get("n"))
+                    .and_then(PackStreamValue::as_i64)
+                    .unwrap_or(-1),
+            },
+            0x66 => Request::Route,
+            _ => {
+                return Err(BoltError::Protocol(format!(
+                    "Unsupported Bolt message 0x{:02X}.",
+                    tag
+                )));
+            }
+        };
+        Ok(request)
+    }
+}
+
+// Auth token of HELLO and LOGON, e.g. {scheme: "basic", principal: "alice", credentials: "secret"}.
+// Bearer tokens are JWTs or API keys.
+fn credentials_from_auth(auth: &PackStreamValue) -> Option<Credentials> {
+    let credentials = auth.get("credentials").and_then(PackStreamValue::as_str);
+    match auth.get("scheme").and_then(PackStreamValue::as_str)? {
+        "basic" => Some(Credentials::Basic {
+            user: auth
+                .get("principal")
+                .and_then(PackStreamValue::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            password: credentials.unwrap_or_default().to_string(),
+        }),
+        "bearer" => Some(Credentials::Bearer(credentials?.to_string())),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    Success(PackStreamValue),
+    Record(Vec<PackStreamValue>),
+    Ignored,
+    Failure { code: String, message: String },
+}
+
+impl Response {
+    pub fn success(metadata: Vec<(&str, PackStreamValue)>) -> Self {
+        Response::Success(PackStreamValue::map(metadata))
+    }
+
+    pub fn failure(error: &BrahmandError) -> Self {
+        METRICS.record_error(error);
+        Response::Failure {
+            code: error.neo4j_code().to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    pub fn to_value(&self) -> PackStreamValue {
+        let (tag, fields) = match self {
+            Response::Success(metadata) => (0x70, vec![metadata.clone()]),
+            Response::Record(values) => (0x71, vec![PackStreamValue::List(values.clone())]),
+            Response::Ignored => (0x7E, vec![]),
+            Response::Failure { code, message } => (
+                0x7F,
+                vec![PackStreamValue::map(vec![
+                    ("code", PackStreamValue::String(code.clone())),
+                    ("message", PackStreamValue::String(message.clone())),
+                ])],
+            ),
+        };
+        PackStreamValue::Struct { tag, fields }
+    }
+}
+
+// RUN parameters are bound like the ones of the Neo4j HTTP API, which come as JSON.
+pub fn parameters_to_json(
+    parameters: &PackStreamValue,
+) -> Result<Map<String, Value>, BrahmandError> {
+    let PackStreamValue::Map(entries) = parameters else {
+        return Err(BrahmandError::InvalidRequest(
+            "Parameters should be a map.".to_string(),
+        ));
+    };
+    entries
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), parameter_to_json(name, value)?)))
+        .collect()
+}
+
+fn parameter_to_json(name: &str, value: &PackStreamValue) -> Result<Value, BrahmandError> {
+    let value = match value {
+        PackStreamValue::Null => Value::Null,
+        PackStreamValue::Boolean(b) => Value::Bool(*b),
+        PackStreamValue::Integer(i) => Value::from(*i),
+        PackStreamValue::Float(f) => serde_json::Number::from_f64(*f)
+            .map(Value::Number)
+            .ok_or_else(|| handlers::unsupported_parameter(name))?,
+        PackStreamValue::String(s) => Value::String(s.clone()),
+        PackStreamValue::List(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| parameter_to_json(name, item))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        PackStreamValue::Bytes(_) | PackStreamValue::Map(_) | PackStreamValue::Struct { .. } => {
+            return Err(handlers::unsupported_parameter(name));
+        }
+    };
+    Ok(value)
+}
+
+// Converts values of a JSON result row into PackStream. Node and relationship entities become
+// Bolt structures so drivers return them as graph types.
+pub struct ValueConverter<'a> {
+    pub graph_schema: &'a GraphSchema,
+    pub version: BoltVersion,
+}
+
+impl ValueConverter<'_> {
+    pub fn convert(&self, value: &Value) -> PackStreamValue {
+        match value {
+            Value::Null => PackStreamValue::Null,
+            Value::Bool(b) => PackStreamValue::Boolean(*b),
+            Value::Number(number) => match number.as_i64() {
+                Some(i) => PackStreamValue::Integer(i),
+                None => PackStreamValue::Float(number.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => PackStreamValue::String(s.clone()),
+            Value::Array(items) => self.convert_path(items).unwrap_or_else(|| {
+                PackStreamValue::List(items.iter().map(|item| self.convert(item)).collect())
+            }),
+            Value::Object(object) => {
+                if is_node(object) {
+                    self.convert_node(object)
+                } else if is_edge(object) {
+                    self.convert_relationship(object)
+                } else {
+                    self.convert_map(object)
+                }
+            }
+        }
+    }
+
+    fn convert_map(&self, object: &Map<String, Value>) -> PackStreamValue {
+        PackStreamValue::Map(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), self.convert(value)))
+                .collect(),
+        )
+    }
+
+    fn convert_properties(&self, properties: &Value) -> PackStreamValue {
+        match properties {
+            Value::Object(object) => self.convert_map(object),
+            _ => PackStreamValue::Map(vec![]),
+        }
+    }
+
+    fn convert_node(&self, object: &Map<String, Value>) -> PackStreamValue {
+        let labels = object["labels"].as_array().cloned().unwrap_or_default();
+        let label = labels.first().and_then(Value::as_str).unwrap_or_default();
+        let element_id = node_key(label, &object["id"]);
+
+        let mut fields = vec![
+            PackStreamValue::Integer(legacy_id(&element_id)),
+            PackStreamValue::List(labels.iter().map(|label| self.convert(label)).collect()),
+            self.convert_properties(&object["properties"]),
+        ];
+        if self.version.has_element_ids() {
+            fields.push(PackStreamValue::String(element_id));
+        }
+        PackStreamValue::Struct {
+            tag: NODE_TAG,
+            fields,
+        }
+    }
+
+    fn convert_relationship(&self, object: &Map<String, Value>) -> PackStreamValue {
+        let (element_id, start_element_id, end_element_id) = edge_keys(object, self.graph_schema);
+
+        let mut fields = vec![
+            PackStreamValue::Integer(legacy_id(&element_id)),
+            PackStreamValue::Integer(legacy_id(&start_element_id)),
+            PackStreamValue::Integer(legacy_id(&end_element_id)),
+            self.convert(&object["type"]),
+            self.convert_properties(&object["properties"]),
+        ];
+        if self.version.has_element_ids() {
+            fields.push(PackStreamValue::String(element_id));
+            fields.push(PackStreamValue::String(start_element_id));
+            fields.push(PackStreamValue::String(end_element_id));
+        }
+        PackStreamValue::Struct {
+            tag: RELATIONSHIP_TAG,
+            fields,
+        }
+    }
+
+    // Paths are projected as [[nodes...], [relationships...]].
+    fn convert_path(&self, items: &[Value]) -> Option<PackStreamValue> {
+        let [Value::Array(nodes), Value::Array(rels)] = items else {
+            return None;
+        };
+        let nodes = nodes
+            .iter()
+            .map(|node| node.as_object().filter(|node| is_node(node)))
+            .collect::<Option<Vec<_>>>()?;
+        let rels = rels
+            .iter()
+            .map(|rel| rel.as_object().filter(|rel| is_edge(rel)))
+            .collect::<Option<Vec<_>>>()?;
+        if nodes.is_empty() || nodes.len() != rels.len() + 1 {
+            return None;
+        }
+
+        let node_element_ids: Vec<String> = nodes
+            .iter()
+            .map(|node| {
+                let label = node["labels"][0].as_str().unwrap_or_default();
+                node_key(label, &node["id"])
+            })
+            .collect();
+
+        // Every hop is the relationship index, negative when it is traversed against its
+        // direction, followed by the index of the next node.
+        let mut indices = vec![];
+        let mut unbound_rels = vec![];
+        for (i, rel) in rels.iter().enumerate() {
+            let (element_id, start_element_id, _) = edge_keys(rel, self.graph_schema);
+            let rel_index = i as i64 + 1;
+            if start_element_id == node_element_ids[i] {
+                indices.push(PackStreamValue::Integer(rel_index));
+            } else {
+                indices.push(PackStreamValue::Integer(-rel_index));
+            }
+            indices.push(PackStreamValue::Integer(rel_index));
+
+            let mut fields = vec![
+                PackStreamValue::Integer(legacy_id(&element_id)),
+                self.convert(&rel["type"]),
+                self.convert_properties(&rel["properties"]),
+            ];
+            if self.version.has_element_ids() {
+                fields.push(PackStreamValue::String(element_id));
+            }
+            unbound_rels.push(PackStreamValue::Struct {
+                tag: UNBOUND_RELATIONSHIP_TAG,
+                fields,
+            });
+        }
+
+        Some(PackStreamValue::Struct {
+            tag: PATH_TAG,
+            fields: vec![
+                PackStreamValue::List(nodes.iter().map(|node| self.convert_node(node)).collect()),
+                PackStreamValue::List(unbound_rels),
+                PackStreamValue::List(indices),
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::graph_catalog::graph_schema::{RelationshipSchema, TableEngineSchema};
+    use serde_json::json;
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            "FOLLOWS".to_string(),
+            RelationshipSchema {
+                table_name: "FOLLOWS".to_string(),
+                column_names: vec![],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+        GraphSchema::build(1, HashMap::new(), relationships, HashMap::new())
+    }
+
+    #[test]
+    fn test_request_from_value() {
+        let run = PackStreamValue::Struct {
+            tag: 0x10,
+            fields: vec![
+                PackStreamValue::String("MATCH (u:User) RETURN u;".to_string()),
+                PackStreamValue::map(vec![("age", PackStreamValue::Integer(30))]),
+                PackStreamValue::Map(vec![]),
+            ],
+        };
+        let Ok(Request::Run { query, parameters }) = Request::try_from(run) else {
+            panic!("expected RUN");
+        };
+        assert_eq!(query, "MATCH (u:User) RETURN u;");
+        assert_eq!(parameters_to_json(&parameters).unwrap()["age"], json!(30));
+        let bytes = PackStreamValue::map(vec![("b", PackStreamValue::Bytes(vec![1]))]);
+        assert!(matches!(
+            parameters_to_json(&bytes),
+            Err(BrahmandError::InvalidRequest(_))
+        ));
+
+        let pull = PackStreamValue::Struct {
+            tag: 0x3F,
+            fields: vec![PackStreamValue::map(vec![(
+                "n",
+                PackStreamValue::Integer(100),
+            )])],
+        };
+        assert_eq!(Request::try_from(pull).unwrap(), Request::Pull { n: 100 });
+
+        let hello = PackStreamValue::Struct {
+            tag: 0x01,
+            fields: vec![PackStreamValue::map(vec![
+                (
+                    "user_agent",
+                    PackStreamValue::String("neo4j-python".to_string()),
+                ),
+                ("scheme", PackStreamValue::String("basic".to_string())),
+                ("principal", PackStreamValue::String("alice".to_string())),
+                ("credentials", PackStreamValue::String("secret".to_string())),
+            ])],
+        };
+        assert_eq!(
+            Request::try_from(hello).unwrap(),
+            Request::Hello {
+                credentials: Some(Credentials::Basic {
+                    user: "alice".to_string(),
+                    password: "secret".to_string(),
+                })
+            }
+        );
+        assert!(Request::try_from(PackStreamValue::Null).is_err());
+    }
+
+    #[test]
+    fn test_convert_entities() {
+        let graph_schema = create_test_graph_schema();
+        let alice = json!({"id": 1, "labels": ["User"], "properties": {"name": "alice"}});
+        let bob = json!({"id": 2, "labels": ["User"], "properties": {"name": "bob"}});
+        let follows = json!({"type": "FOLLOWS", "start_id": 2, "end_id": 1, "properties": {}});
+
+        let converter = ValueConverter {
+            graph_schema: &graph_schema,
+            version: BoltVersion { major: 5, minor: 0 },
+        };
+        let PackStreamValue::Struct { tag, fields } = converter.convert(&alice) else {
+            panic!("expected a node");
+        };
+        assert_eq!(tag, NODE_TAG);
+        assert_eq!(fields[3], PackStreamValue::String("User:1".to_string()));
+        assert_eq!(fields[0], PackStreamValue::Integer(legacy_id("User:1")));
+
+        let PackStreamValue::Struct { tag, fields } = converter.convert(&follows) else {
+            panic!("expected a relationship");
+        };
+        assert_eq!(tag, RELATIONSHIP_TAG);
+        assert!(matches!(
+            &fields[5],
+            PackStreamValue::String(element_id) if element_id.starts_with("FOLLOWS:User:2:User:1:")
+        ));
+
+        // alice <-[:FOLLOWS]- bob
+        let path = json!([[alice, bob], [follows]]);
+        let PackStreamValue::Struct { tag, fields } = converter.convert(&path) else {
+            panic!("expected a path");
+        };
+        assert_eq!(tag, PATH_TAG);
+        assert_eq!(
+            fields[2],
+            PackStreamValue::List(vec![
+                PackStreamValue::Integer(-1),
+                PackStreamValue::Integer(1)
+            ])
+        );
+
+        // Bolt 4 has no element ids
+        let converter = ValueConverter {
+            graph_schema: &graph_schema,
+            version: BoltVersion { major: 4, minor: 4 },
+        };
+        let PackStreamValue::Struct { fields, .. } = converter.convert(&alice) else {
+            panic!("expected a node");
+        };
+        assert_eq!(fields.len(), 3);
+        assert_eq!(
+            converter.convert(&json!([1, "a"])),
+            PackStreamValue::List(vec![
+                PackStreamValue::Integer(1),
+                PackStreamValue::String("a".to_string())
+            ])
+        );
+    }
+}
@@ -0,0 +1,519 @@
+use std::{future::Future, io, sync::Arc, time::Instant};
+
+use thiserror::Error;
+use tokio::{
+    io::{
+        AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter,
+    },
+    net::{TcpListener, TcpStream},
+};
+use tracing::{Instrument, error, info, info_span};
+use uuid::Uuid;
+
+use messages::{Request, Response, ValueConverter};
+use packstream::{PackStreamError, PackStreamValue};
+
+use super::{
+    AppState,
+    auth::{Credentials, Identity},
+    errors::BrahmandError,
+    handlers::{self, ReadResult},
+    query_log::QueryLogRecord,
+};
+
+mod messages;
+mod packstream;
+
+const BOLT_MAGIC: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+// the connection is closed when a message is larger, queries and their parameters are far smaller
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+// Drivers check that the server agent starts with "Neo4j/".
+const SERVER_AGENT: &str = "Neo4j/5.0.0";
+// preferred first
+const SUPPORTED_VERSIONS: [BoltVersion; 2] = [
+    BoltVersion { major: 5, minor: 0 },
+    BoltVersion { major: 4, minor: 4 },
+];
+
+#[derive(Debug, Error)]
+pub enum BoltError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    PackStream(#[from] PackStreamError),
+    #[error("{0}")]
+    Protocol(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoltVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl BoltVersion {
+    // A proposal is [_, range, minor, major] and covers minor versions from minor - range to minor.
+    fn negotiate(proposals: &[u8]) -> Option<BoltVersion> {
+        proposals.chunks_exact(4).find_map(|proposal| {
+            let (range, minor, major) = (proposal[1], proposal[2], proposal[3]);
+            SUPPORTED_VERSIONS.into_iter().find(|version| {
+                version.major == major
+                    && version.minor <= minor
+                    && version.minor >= minor.saturating_sub(range)
+            })
+        })
+    }
+
+    pub fn has_element_ids(&self) -> bool {
+        self.major >= 5
+    }
+}
+
+// Accepts connections until `shutdown` resolves. The HTTP server keeps running when the Bolt
+// port can not be bound.
+pub async fn run_listener(
+    app_state: Arc<AppState>,
+    bind_address: String,
+    shutdown: impl Future<Output = ()>,
+) {
+    let listener = match TcpListener::bind(&bind_address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Unable to start the Bolt server on {}: {}", bind_address, e);
+            return;
+        }
+    };
+    info!("Bolt server running on - {}", bind_address);
+
+    tokio::pin!(shutdown);
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => return,
+        };
+        let (stream, peer_address) = match accepted {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("Unable to accept Bolt connection: {}", e);
+                continue;
+            }
+        };
+        let app_state = app_state.clone();
+        tokio::spawn(
+            async move {
+                if let Err(e) = handle_connection(stream, app_state).await {
+                    error!("Bolt connection error: {}", e);
+                }
+            }
+            .instrument(info_span!("bolt_connection", peer = %peer_address)),
+        );
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    app_state: Arc<AppState>,
+) -> Result<(), BoltError> {
+    let local_address = stream.local_addr()?.to_string();
+
+    let mut preamble = [0u8; 20];
+    stream.read_exact(&mut preamble).await?;
+    if preamble[..4] != BOLT_MAGIC {
+        return Err(BoltError::Protocol("Invalid Bolt preamble.".to_string()));
+    }
+    let Some(version) = BoltVersion::negotiate(&preamble[4..]) else {
+        // all zeros tells the driver that none of the versions is supported
+        stream.write_all(&[0, 0, 0, 0]).await?;
+        return Ok(());
+    };
+    stream
+        .write_all(&[0, 0, version.minor, version.major])
+        .await?;
+
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut writer = BufWriter::new(writer);
+    let mut session = BoltSession {
+        app_state,
+        version,
+        local_address,
+        failed: false,
+        in_transaction: false,
+        identity: None,
+        result: None,
+        result_timed_out: false,
+    };
+
+    loop {
+        // Only waiting for the next message can be interrupted, reading it can not. A result
+        // which is not pulled times out meanwhile so that it does not hold up the shutdown.
+        tokio::select! {
+            buffered = reader.fill_buf() => {
+                buffered?;
+            }
+            _ = session.result_timeout() => {
+                session.result = None;
+                session.result_timed_out = true;
+                continue;
+            }
+        }
+        let Some(message) = read_message(&mut reader).await? else {
+            break;
+        };
+        let request = Request::try_from(PackStreamValue::decode(&message)?)?;
+        if request == Request::Goodbye {
+            break;
+        }
+        session.handle(request, &mut writer).await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+// Messages are split into chunks prefixed with their size and end with an empty chunk.
+// Returns None when the client closed the connection.
+async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Vec<u8>>, BoltError> {
+    let mut message = vec![];
+    loop {
+        let chunk_size = match reader.read_u16().await {
+            Ok(chunk_size) => chunk_size as usize,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && message.is_empty() => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if message.len() + chunk_size > MAX_MESSAGE_SIZE {
+            return Err(BoltError::Protocol(format!(
+                "Bolt messages should be at most {} bytes.",
+                MAX_MESSAGE_SIZE
+            )));
+        }
+        if chunk_size == 0 {
+            // an empty chunk outside of a message is a keep alive
+            if message.is_empty() {
+                continue;
+            }
+            return Ok(Some(message));
+        }
+        let start = message.len();
+        message.resize(start + chunk_size, 0);
+        reader.read_exact(&mut message[start..]).await?;
+    }
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    response: &Response,
+) -> Result<(), BoltError> {
+    let mut buf = vec![];
+    response.to_value().encode(&mut buf);
+    for chunk in buf.chunks(u16::MAX as usize) {
+        writer.write_u16(chunk.len() as u16).await?;
+        writer.write_all(chunk).await?;
+    }
+    writer.write_u16(0).await?;
+    Ok(())
+}
+
+// Result of the last RUN which is streamed to the client by PULL messages.
+struct BoltResult {
+    read: ReadResult,
+    instant: Instant,
+}
+
+struct BoltSession {
+    app_state: Arc<AppState>,
+    version: BoltVersion,
+    local_address: String,
+    // after a failure every message is ignored until RESET
+    failed: bool,
+    in_transaction: bool,
+    // set by a successful HELLO or LOGON
+    identity: Option<Identity>,
+    result: Option<BoltResult>,
+    // the last result was dropped because the query ran out of time before it was pulled
+    result_timed_out: bool,
+}
+
+impl BoltSession {
+    async fn handle<W: AsyncWrite + Unpin>(
+        &mut self,
+        request: Request,
+        writer: &mut W,
+    ) -> Result<(), BoltError> {
+        if self.failed && request != Request::Reset {
+            return write_message(writer, &Response::Ignored).await;
+        }
+
+        let response = match request {
+            Request::Hello { credentials } => match self.logon(credentials).await {
+                Ok(()) => Response::success(vec![
+                    ("server", PackStreamValue::String(SERVER_AGENT.to_string())),
+                    (
+                        "connection_id",
+                        PackStreamValue::String(format!("bolt-{}", Uuid::new_v4().simple())),
+                    ),
+                    ("hints", PackStreamValue::Map(vec![])),
+                ]),
+                Err(e) => Response::failure(&e),
+            },
+            Request::Logon { credentials } => match self.logon(credentials).await {
+                Ok(()) => Response::success(vec![]),
+                Err(e) => Response::failure(&e),
+            },
+            Request::Logoff => {
+                self.identity = None;
+                Response::success(vec![])
+            }
+            Request::Reset => {
+                self.failed = false;
+                self.in_transaction = false;
+                self.result = None;
+                self.result_timed_out = false;
+                Response::success(vec![])
+            }
+            // Queries are read only, so transactions only have to be acknowledged.
+            Request::Begin => {
+                self.in_transaction = true;
+                Response::success(vec![])
+            }
+            Request::Commit | Request::Rollback => {
+                self.in_transaction = false;
+                Response::success(vec![])
+            }
+            Request::Run { query, parameters } => match self
+                .run(query, &parameters)
+                .instrument(info_span!("request", request_id = %Uuid::new_v4()))
+                .await
+            {
+                Ok(metadata) => metadata,
+                Err(e) => Response::failure(&e),
+            },
+            Request::Pull { n } => self.pull(n, writer).await?,
+            Request::Discard => {
+                self.result = None;
+                Response::success(vec![("has_more", PackStreamValue::Boolean(false))])
+            }
+            // Single server, it serves every role of the routing table.
+            Request::Route => {
+                let server = |role: &str| {
+                    PackStreamValue::map(vec![
+                        (
+                            "addresses",
+                            PackStreamValue::List(vec![PackStreamValue::String(
+                                self.local_address.clone(),
+                            )]),
+                        ),
+                        ("role", PackStreamValue::String(role.to_string())),
+                    ])
+                };
+                Response::success(vec![(
+                    "rt",
+                    PackStreamValue::map(vec![
+                        ("ttl", PackStreamValue::Integer(300)),
+                        ("db", PackStreamValue::String("neo4j".to_string())),
+                        (
+                            "servers",
+                            PackStreamValue::List(vec![
+                                server("ROUTE"),
+                                server("READ"),
+                                server("WRITE"),
+                            ]),
+                        ),
+                    ]),
+                )])
+            }
+            Request::Goodbye => return Ok(()),
+        };
+
+        if matches!(response, Response::Failure { .. }) {
+            self.failed = true;
+        }
+        write_message(writer, &response).await
+    }
+
+    async fn logon(&mut self, credentials: Option<Credentials>) -> Result<(), BrahmandError> {
+        self.identity = None;
+        self.identity = Some(
+            self.app_state
+                .authenticator
+                .authenticate(credentials)
+                .await?,
+        );
+        Ok(())
+    }
+
+    async fn run(
+        &mut self,
+        query: String,
+        parameters: &PackStreamValue,
+    ) -> Result<Response, BrahmandError> {
+        // a new query replaces the result which was not pulled yet
+        self.result = None;
+        self.result_timed_out = false;
+
+        let Some(identity) = self.identity.clone() else {
+            return Err(BrahmandError::Unauthorized(
+                "Authenticate with HELLO or LOGON first.".to_string(),
+            ));
+        };
+        self.app_state.lifecycle.check_ready()?;
+        let query_log = self.app_state.start_query_log(&identity, &query);
+        let result = self
+            .start_result(&identity, query, parameters, &query_log)
+            .await;
+        if let Err(e) = &result {
+            query_log.set_error(e);
+        }
+        result
+    }
+
+    async fn start_result(
+        &mut self,
+        identity: &Identity,
+        query: String,
+        parameters: &PackStreamValue,
+        query_log: &QueryLogRecord,
+    ) -> Result<Response, BrahmandError> {
+        let instant = Instant::now();
+        let parameters = messages::parameters_to_json(parameters)?;
+        let read =
+            handlers::start_read(&self.app_state, identity, query, &parameters, query_log).await?;
+        let fields = read.columns.clone();
+
+        self.result = Some(BoltResult { read, instant });
+
+        let mut metadata = vec![
+            (
+                "fields",
+                PackStreamValue::List(fields.into_iter().map(PackStreamValue::String).collect()),
+            ),
+            (
+                "t_first",
+                PackStreamValue::Integer(instant.elapsed().as_millis() as i64),
+            ),
+        ];
+        if self.in_transaction {
+            metadata.push(("qid", PackStreamValue::Integer(0)));
+        }
+        Ok(Response::success(metadata))
+    }
+
+    // Records are written as they are read from ClickHouse, at most `n` of them per PULL.
+    async fn pull<W: AsyncWrite + Unpin>(
+        &mut self,
+        n: i64,
+        writer: &mut W,
+    ) -> Result<Response, BoltError> {
+        let mut pulled = 0;
+        while n < 0 || pulled < n {
+            match self.next_record().await {
+                Ok(Some(record)) => write_message(writer, &Response::Record(record)).await?,
+                Ok(None) => {
+                    let result = self.result.take().unwrap();
+                    let t_last = result.instant.elapsed().as_millis() as i64;
+                    result.read.finish();
+                    return Ok(Response::success(vec![
+                        ("type", PackStreamValue::String("r".to_string())),
+                        ("t_last", PackStreamValue::Integer(t_last)),
+                    ]));
+                }
+                Err(e) => {
+                    if let Some(result) = &self.result {
+                        result.read.query_log().set_error(&e);
+                    }
+                    // dropping the result kills the query
+                    self.result = None;
+                    return Ok(Response::failure(&e));
+                }
+            }
+            pulled += 1;
+        }
+        Ok(Response::success(vec![(
+            "has_more",
+            PackStreamValue::Boolean(true),
+        )]))
+    }
+
+    // Resolves once the query of the pending result ran out of time.
+    fn result_timeout(&self) -> impl Future<Output = ()> + 'static {
+        let timed_out = self.result.as_ref().map(|result| result.read.timed_out());
+        async move {
+            match timed_out {
+                Some(timed_out) => timed_out.await,
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    async fn next_record(&mut self) -> Result<Option<Vec<PackStreamValue>>, BrahmandError> {
+        let Some(result) = self.result.as_mut() else {
+            if self.result_timed_out {
+                return Err(BrahmandError::Clickhouse(
+                    clickhouse::error::Error::TimedOut,
+                ));
+            }
+            return Err(BrahmandError::InvalidRequest(
+                "There is no result to pull.".to_string(),
+            ));
+        };
+        let Some(row) = result.read.next_row().await? else {
+            return Ok(None);
+        };
+        let converter = ValueConverter {
+            graph_schema: &result.read.graph_schema,
+            version: self.version,
+        };
+        Ok(Some(
+            row.iter().map(|value| converter.convert(value)).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_version() {
+        // 5.4 down to 5.0, then 4.4
+        let proposals = [0, 4, 4, 5, 0, 0, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            BoltVersion::negotiate(&proposals),
+            Some(BoltVersion { major: 5, minor: 0 })
+        );
+
+        let proposals = [0, 2, 4, 4, 0, 0, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(
+            BoltVersion::negotiate(&proposals),
+            Some(BoltVersion { major: 4, minor: 4 })
+        );
+
+        let proposals = [0, 0, 4, 5, 0, 0, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(BoltVersion::negotiate(&proposals), None);
+    }
+
+    #[tokio::test]
+    async fn test_message_chunking() {
+        let response = Response::success(vec![(
+            "server",
+            PackStreamValue::String("a".repeat(70_000)),
+        )]);
+        let mut buf = vec![];
+        write_message(&mut buf, &response).await.unwrap();
+
+        // one full chunk, the rest and the end marker
+        assert_eq!(&buf[..2], &[0xFF, 0xFF]);
+        let mut reader = &buf[..];
+        let message = read_message(&mut reader).await.unwrap().unwrap();
+        assert_eq!(PackStreamValue::decode(&message), Ok(response.to_value()));
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+
+        let chunk = [&[0xFF, 0xFF][..], &[0; u16::MAX as usize]].concat();
+        let buf = chunk.repeat(MAX_MESSAGE_SIZE / u16::MAX as usize + 1);
+        assert!(matches!(
+            read_message(&mut &buf[..]).await,
+            Err(BoltError::Protocol(_))
+        ));
+    }
+}
@@ -0,0 +1,365 @@
+use thiserror::Error;
+
+// lists, maps and structures nested deeper are rejected rather than overflowing the stack
+const MAX_DEPTH: usize = 64;
+
+// PackStream is the binary format of Bolt messages, see
+// https://neo4j.com/docs/bolt/current/packstream/
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackStreamValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    List(Vec<PackStreamValue>),
+    // entries keep their order so encoded messages are deterministic
+    Map(Vec<(String, PackStreamValue)>),
+    Struct {
+        tag: u8,
+        fields: Vec<PackStreamValue>,
+    },
+}
+
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum PackStreamError {
+    #[error("Unexpected end of message.")]
+    UnexpectedEnd,
+    #[error("Unknown PackStream marker 0x{0:02X}.")]
+    UnknownMarker(u8),
+    #[error("Map keys should be strings.")]
+    InvalidMapKey,
+    #[error("Invalid utf-8 string.")]
+    InvalidString,
+    #[error("Values are nested deeper than {MAX_DEPTH} levels.")]
+    TooDeep,
+}
+
+impl PackStreamValue {
+    pub fn map(entries: Vec<(&str, PackStreamValue)>) -> Self {
+        PackStreamValue::Map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&PackStreamValue> {
+        match self {
+            PackStreamValue::Map(entries) => entries
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PackStreamValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            PackStreamValue::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            PackStreamValue::Null => buf.push(0xC0),
+            PackStreamValue::Boolean(false) => buf.push(0xC2),
+            PackStreamValue::Boolean(true) => buf.push(0xC3),
+            PackStreamValue::Integer(i) => encode_integer(*i, buf),
+            PackStreamValue::Float(f) => {
+                buf.push(0xC1);
+                buf.extend_from_slice(&f.to_be_bytes());
+            }
+            PackStreamValue::Bytes(bytes) => {
+                encode_size(bytes.len(), None, [0xCC, 0xCD, 0xCE], buf);
+                buf.extend_from_slice(bytes);
+            }
+            PackStreamValue::String(s) => {
+                encode_size(s.len(), Some(0x80), [0xD0, 0xD1, 0xD2], buf);
+                buf.extend_from_slice(s.as_bytes());
+            }
+            PackStreamValue::List(items) => {
+                encode_size(items.len(), Some(0x90), [0xD4, 0xD5, 0xD6], buf);
+                for item in items {
+                    item.encode(buf);
+                }
+            }
+            PackStreamValue::Map(entries) => {
+                encode_size(entries.len(), Some(0xA0), [0xD8, 0xD9, 0xDA], buf);
+                for (key, value) in entries {
+                    PackStreamValue::String(key.clone()).encode(buf);
+                    value.encode(buf);
+                }
+            }
+            PackStreamValue::Struct { tag, fields } => {
+                // structures have at most 15 fields
+                buf.push(0xB0 | fields.len() as u8);
+                buf.push(*tag);
+                for field in fields {
+                    field.encode(buf);
+                }
+            }
+        }
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Self, PackStreamError> {
+        let mut decoder = Decoder {
+            buf,
+            pos: 0,
+            depth: 0,
+        };
+        decoder.decode_value()
+    }
+}
+
+fn encode_integer(i: i64, buf: &mut Vec<u8>) {
+    if (-16..=127).contains(&i) {
+        buf.push(i as u8);
+    } else if (i8::MIN as i64..=i8::MAX as i64).contains(&i) {
+        buf.push(0xC8);
+        buf.push(i as i8 as u8);
+    } else if (i16::MIN as i64..=i16::MAX as i64).contains(&i) {
+        buf.push(0xC9);
+        buf.extend_from_slice(&(i as i16).to_be_bytes());
+    } else if (i32::MIN as i64..=i32::MAX as i64).contains(&i) {
+        buf.push(0xCA);
+        buf.extend_from_slice(&(i as i32).to_be_bytes());
+    } else {
+        buf.push(0xCB);
+        buf.extend_from_slice(&i.to_be_bytes());
+    }
+}
+
+// Small sizes are packed into the marker when the type has a tiny form.
+fn encode_size(size: usize, tiny_marker: Option<u8>, markers: [u8; 3], buf: &mut Vec<u8>) {
+    match tiny_marker {
+        Some(tiny_marker) if size < 16 => buf.push(tiny_marker | size as u8),
+        _ if size <= u8::MAX as usize => {
+            buf.push(markers[0]);
+            buf.push(size as u8);
+        }
+        _ if size <= u16::MAX as usize => {
+            buf.push(markers[1]);
+            buf.extend_from_slice(&(size as u16).to_be_bytes());
+        }
+        _ => {
+            buf.push(markers[2]);
+            buf.extend_from_slice(&(size as u32).to_be_bytes());
+        }
+    }
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], PackStreamError> {
+        let bytes = self
+            .buf
+            .get(self.pos..self.pos + len)
+            .ok_or(PackStreamError::UnexpectedEnd)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], PackStreamError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    // Sizes come from the message, every item takes at least one more byte of it.
+    fn capacity(&self, size: usize) -> usize {
+        size.min(self.buf.len() - self.pos)
+    }
+
+    fn decode_value(&mut self) -> Result<PackStreamValue, PackStreamError> {
+        if self.depth == MAX_DEPTH {
+            return Err(PackStreamError::TooDeep);
+        }
+        self.depth += 1;
+        let value = self.decode_marker();
+        self.depth -= 1;
+        value
+    }
+
+    fn decode_marker(&mut self) -> Result<PackStreamValue, PackStreamError> {
+        let marker = self.take_array::<1>()?[0];
+        let value = match marker {
+            0x00..=0x7F => PackStreamValue::Integer(marker as i64),
+            0xF0..=0xFF => PackStreamValue::Integer(marker as i8 as i64),
+            0x80..=0x8F => self.decode_string((marker & 0x0F) as usize)?,
+            0x90..=0x9F => self.decode_list((marker & 0x0F) as usize)?,
+            0xA0..=0xAF => self.decode_map((marker & 0x0F) as usize)?,
+            0xB0..=0xBF => {
+                let tag = self.take_array::<1>()?[0];
+                let fields = (0..(marker & 0x0F))
+                    .map(|_| self.decode_value())
+                    .collect::<Result<Vec<_>, _>>()?;
+                PackStreamValue::Struct { tag, fields }
+            }
+            0xC0 => PackStreamValue::Null,
+            0xC1 => PackStreamValue::Float(f64::from_be_bytes(self.take_array()?)),
+            0xC2 => PackStreamValue::Boolean(false),
+            0xC3 => PackStreamValue::Boolean(true),
+            0xC8 => PackStreamValue::Integer(i8::from_be_bytes(self.take_array()?) as i64),
+            0xC9 => PackStreamValue::Integer(i16::from_be_bytes(self.take_array()?) as i64),
+            0xCA => PackStreamValue::Integer(i32::from_be_bytes(self.take_array()?) as i64),
+            0xCB => PackStreamValue::Integer(i64::from_be_bytes(self.take_array()?)),
+            0xCC..=0xCE => {
+                let size = self.decode_size(marker - 0xCC)?;
+                PackStreamValue::Bytes(self.take(size)?.to_vec())
+            }
+            0xD0..=0xD2 => {
+                let size = self.decode_size(marker - 0xD0)?;
+                self.decode_string(size)?
+            }
+            0xD4..=0xD6 => {
+                let size = self.decode_size(marker - 0xD4)?;
+                self.decode_list(size)?
+            }
+            0xD8..=0xDA => {
+                let size = self.decode_size(marker - 0xD8)?;
+                self.decode_map(size)?
+            }
+            _ => return Err(PackStreamError::UnknownMarker(marker)),
+        };
+        Ok(value)
+    }
+
+    // 0, 1 and 2 are 8, 16 and 32 bit sizes
+    fn decode_size(&mut self, width: u8) -> Result<usize, PackStreamError> {
+        let size = match width {
+            0 => u8::from_be_bytes(self.take_array()?) as usize,
+            1 => u16::from_be_bytes(self.take_array()?) as usize,
+            _ => u32::from_be_bytes(self.take_array()?) as usize,
+        };
+        Ok(size)
+    }
+
+    fn decode_string(&mut self, size: usize) -> Result<PackStreamValue, PackStreamError> {
+        let bytes = self.take(size)?;
+        let s = std::str::from_utf8(bytes).map_err(|_| PackStreamError::InvalidString)?;
+        Ok(PackStreamValue::String(s.to_string()))
+    }
+
+    fn decode_list(&mut self, size: usize) -> Result<PackStreamValue, PackStreamError> {
+        let mut items = Vec::with_capacity(self.capacity(size));
+        for _ in 0..size {
+            items.push(self.decode_value()?);
+        }
+        Ok(PackStreamValue::List(items))
+    }
+
+    fn decode_map(&mut self, size: usize) -> Result<PackStreamValue, PackStreamError> {
+        let mut entries = Vec::with_capacity(self.capacity(size));
+        for _ in 0..size {
+            let PackStreamValue::String(key) = self.decode_value()? else {
+                return Err(PackStreamError::InvalidMapKey);
+            };
+            entries.push((key, self.decode_value()?));
+        }
+        Ok(PackStreamValue::Map(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: &PackStreamValue) -> Vec<u8> {
+        let mut buf = vec![];
+        value.encode(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_encode_scalars() {
+        assert_eq!(encode(&PackStreamValue::Null), vec![0xC0]);
+        assert_eq!(encode(&PackStreamValue::Boolean(true)), vec![0xC3]);
+        assert_eq!(encode(&PackStreamValue::Integer(1)), vec![0x01]);
+        assert_eq!(encode(&PackStreamValue::Integer(-16)), vec![0xF0]);
+        assert_eq!(encode(&PackStreamValue::Integer(-17)), vec![0xC8, 0xEF]);
+        assert_eq!(
+            encode(&PackStreamValue::Integer(128)),
+            vec![0xC9, 0x00, 0x80]
+        );
+        assert_eq!(
+            encode(&PackStreamValue::Integer(1 << 40)),
+            vec![0xCB, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            encode(&PackStreamValue::String("A".to_string())),
+            vec![0x81, 0x41]
+        );
+        assert_eq!(
+            encode(&PackStreamValue::String("a".repeat(16)))[..2],
+            [0xD0, 0x10]
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = PackStreamValue::Struct {
+            tag: 0x10,
+            fields: vec![
+                PackStreamValue::String("MATCH (u:User) WHERE u.age > $age RETURN u;".to_string()),
+                PackStreamValue::map(vec![
+                    ("age", PackStreamValue::Integer(30)),
+                    ("score", PackStreamValue::Float(1.5)),
+                    (
+                        "names",
+                        PackStreamValue::List(vec![
+                            PackStreamValue::String("alice".to_string()),
+                            PackStreamValue::Null,
+                        ]),
+                    ),
+                ]),
+                PackStreamValue::Map(vec![]),
+            ],
+        };
+        assert_eq!(PackStreamValue::decode(&encode(&value)), Ok(value));
+
+        let list = PackStreamValue::List((0..300).map(PackStreamValue::Integer).collect());
+        assert_eq!(PackStreamValue::decode(&encode(&list)), Ok(list));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            PackStreamValue::decode(&[0x82, 0x41]),
+            Err(PackStreamError::UnexpectedEnd)
+        );
+        assert_eq!(
+            PackStreamValue::decode(&[0xE0]),
+            Err(PackStreamError::UnknownMarker(0xE0))
+        );
+        assert_eq!(
+            PackStreamValue::decode(&[0xA1, 0x01, 0x01]),
+            Err(PackStreamError::InvalidMapKey)
+        );
+        // the size is not trusted for the allocation
+        assert_eq!(
+            PackStreamValue::decode(&[0xDA, 0xFF, 0xFF, 0xFF, 0xFF]),
+            Err(PackStreamError::UnexpectedEnd)
+        );
+
+        let nested = |depth: usize| [vec![0x91; depth], vec![0x01]].concat();
+        assert!(PackStreamValue::decode(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(
+            PackStreamValue::decode(&nested(MAX_DEPTH)),
+            Err(PackStreamError::TooDeep)
+        );
+    }
+}
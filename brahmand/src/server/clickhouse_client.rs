@@ -1,19 +1,260 @@
-use std::env;
+use std::{collections::HashMap, fs, time::Duration};
 
 use clickhouse::Client;
+use hyper_tls::HttpsConnector;
+use hyper_util::{
+    client::legacy::{Client as HyperClient, connect::HttpConnector},
+    rt::TokioExecutor,
+};
+use native_tls::{Certificate, Identity as TlsIdentity};
+use serde_json::Value;
+use tracing::info;
 
-fn read_env_var(key: &str) -> String {
-    env::var(key).unwrap_or_else(|_| panic!("{key} env variable should be set"))
-}
+use super::{
+    config::{ClickHouseConfig, ConfigError},
+    errors::BrahmandError,
+};
+
+// same as the default client of the clickhouse crate
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
 
-pub fn get_client() -> Client {
-    println!("\n CLICKHOUSE_URL {}\n", read_env_var("CLICKHOUSE_URL"));
-    Client::default()
-        .with_url(read_env_var("CLICKHOUSE_URL"))
-        .with_user(read_env_var("CLICKHOUSE_USER"))
-        .with_password(read_env_var("CLICKHOUSE_PASSWORD"))
-        .with_database(read_env_var("CLICKHOUSE_DATABASE"))
+pub fn get_client(config: &ClickHouseConfig) -> Result<Client, ConfigError> {
+    info!("CLICKHOUSE_URL {}", config.url);
+    // the body type of the pool is private to the clickhouse crate, so it is built here
+    let http_client = HyperClient::builder(TokioExecutor::new())
+        .pool_idle_timeout(config.pool_idle_timeout)
+        .pool_max_idle_per_host(config.pool_max_idle)
+        .build(https_connector(config)?);
+    let client = Client::with_http_client(http_client)
+        .with_url(&config.url)
+        .with_user(&config.user)
+        .with_password(&config.password)
+        .with_database(&config.database)
         .with_option("allow_experimental_json_type", "1")
+        // node and relationship entities are returned with Dynamic typed properties
+        .with_option("allow_experimental_dynamic_type", "1")
         .with_option("input_format_binary_read_json_as_string", "1")
-        .with_option("output_format_binary_write_json_as_string", "1")
+        .with_option("output_format_binary_write_json_as_string", "1");
+
+    // Server wide ClickHouse settings
+    Ok(config
+        .settings
+        .iter()
+        .fold(client, |client, (name, value)| {
+            client.with_option(name, value)
+        }))
+}
+
+// Plain http urls are served by the same connector.
+fn https_connector(
+    config: &ClickHouseConfig,
+) -> Result<HttpsConnector<HttpConnector>, ConfigError> {
+    let mut tls = native_tls::TlsConnector::builder();
+    if let Some(ca_file) = &config.tls_ca_file {
+        let pem = fs::read(ca_file).map_err(|e| ConfigError::file(ca_file, e))?;
+        let certificate = Certificate::from_pem(&pem).map_err(|e| ConfigError::file(ca_file, e))?;
+        tls.add_root_certificate(certificate);
+    }
+    if let Some((cert_file, key_file)) = &config.tls_identity {
+        let cert = fs::read(cert_file).map_err(|e| ConfigError::file(cert_file, e))?;
+        let key = fs::read(key_file).map_err(|e| ConfigError::file(key_file, e))?;
+        let identity =
+            TlsIdentity::from_pkcs8(&cert, &key).map_err(|e| ConfigError::file(cert_file, e))?;
+        tls.identity(identity);
+    }
+    let tls = tls
+        .build()
+        .map_err(|e| ConfigError::Invalid(vec![format!("clickhouse TLS: {e}")]))?;
+
+    let mut http = HttpConnector::new();
+    http.enforce_http(false);
+    http.set_keepalive(Some(TCP_KEEPALIVE));
+    http.set_connect_timeout(Some(config.connect_timeout));
+
+    Ok(HttpsConnector::from((http, tls.into())))
+}
+
+// Limits a request can set, only lower than the server wide settings. 0 lifts a limit in
+// ClickHouse, so it is not accepted either.
+const REQUEST_LIMIT_SETTINGS: &[&str] = &[
+    "max_execution_time",
+    "max_memory_usage",
+    "max_rows_to_read",
+    "max_bytes_to_read",
+    "max_result_rows",
+    "max_result_bytes",
+    "max_threads",
+];
+
+// Other settings a request can set, they only change how the result is formatted.
+const REQUEST_OUTPUT_SETTINGS: &[&str] = &[
+    "date_time_output_format",
+    "output_format_json_quote_64bit_integers",
+    "output_format_json_quote_denormals",
+];
+
+// Settings a request can set to one of the given values, or a comma separated list of them.
+const REQUEST_CHOICE_SETTINGS: &[(&str, &[&str])] = &[(
+    "join_algorithm",
+    &[
+        "default",
+        "auto",
+        "hash",
+        "parallel_hash",
+        "partial_merge",
+        "prefer_partial_merge",
+        "direct",
+        "full_sorting_merge",
+        "grace_hash",
+    ],
+)];
+
+// Settings sent with a single request, they override the server wide settings. Anything which
+// is not allowed above, e.g. readonly, query_id or session_id, is rejected.
+pub fn with_request_settings(
+    client: Client,
+    settings: &Option<HashMap<String, Value>>,
+    server_settings: &[(String, String)],
+) -> Result<Client, BrahmandError> {
+    let Some(settings) = settings else {
+        return Ok(client);
+    };
+
+    settings.iter().try_fold(client, |client, (name, value)| {
+        let value = match value {
+            Value::String(value) => value.clone(),
+            Value::Number(value) => value.to_string(),
+            Value::Bool(value) => (*value as u8).to_string(),
+            _ => {
+                return Err(BrahmandError::InvalidRequest(format!(
+                    "Value of ClickHouse setting `{}` should be a string, number or boolean.",
+                    name
+                )));
+            }
+        };
+        let choices = REQUEST_CHOICE_SETTINGS
+            .iter()
+            .find(|(setting, _)| setting == name)
+            .map(|(_, choices)| choices);
+        if is_limit_setting(name) {
+            check_request_limit(name, &value, server_settings)?;
+        } else if let Some(choices) = choices {
+            if !value
+                .split(',')
+                .all(|choice| choices.contains(&choice.trim()))
+            {
+                return Err(BrahmandError::InvalidRequest(format!(
+                    "Value of ClickHouse setting `{}` should be one of {}.",
+                    name,
+                    choices.join(", ")
+                )));
+            }
+        } else if !REQUEST_OUTPUT_SETTINGS.contains(&name.as_str()) {
+            return Err(BrahmandError::InvalidRequest(format!(
+                "ClickHouse setting `{}` can not be set by a request.",
+                name
+            )));
+        }
+        Ok(client.with_option(name, value))
+    })
+}
+
+fn check_request_limit(
+    name: &str,
+    value: &str,
+    server_settings: &[(String, String)],
+) -> Result<(), BrahmandError> {
+    let limit = parse_limit(value)
+        .filter(|limit| *limit > 0)
+        .ok_or_else(|| {
+            BrahmandError::InvalidRequest(format!(
+                "Value of ClickHouse setting `{}` should be a positive integer.",
+                name
+            ))
+        })?;
+    // server wide limits are normalised to integers when the configuration is loaded
+    let server_limit = server_settings
+        .iter()
+        .find(|(server_name, _)| server_name == name)
+        .and_then(|(_, server_value)| server_value.parse::<u64>().ok())
+        .filter(|server_limit| *server_limit > 0);
+    match server_limit {
+        Some(server_limit) if limit > server_limit => Err(BrahmandError::InvalidRequest(format!(
+            "ClickHouse setting `{}` can be at most {}.",
+            name, server_limit
+        ))),
+        _ => Ok(()),
+    }
+}
+
+pub fn is_limit_setting(name: &str) -> bool {
+    REQUEST_LIMIT_SETTINGS.contains(&name)
+}
+
+// An integer with an optional size suffix as ClickHouse reads them, e.g. 10G or 512Mi.
+pub fn parse_limit(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, suffix) = value.split_at(digits);
+    let multiplier: u64 = match suffix {
+        "" => 1,
+        "K" | "k" => 1_000,
+        "M" => 1_000_000,
+        "G" => 1_000_000_000,
+        "T" => 1_000_000_000_000,
+        "Ki" => 1 << 10,
+        "Mi" => 1 << 20,
+        "Gi" => 1 << 30,
+        "Ti" => 1 << 40,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+pub fn is_valid_setting_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_with_request_settings() {
+        let server_settings = vec![("max_execution_time".to_string(), "60".to_string())];
+        let settings = |settings: Value| {
+            let settings = serde_json::from_value(settings).unwrap();
+            with_request_settings(Client::default(), &Some(settings), &server_settings)
+        };
+
+        assert!(settings(json!({"max_execution_time": 30, "max_threads": 4})).is_ok());
+        assert!(settings(json!({"date_time_output_format": "iso"})).is_ok());
+        assert!(settings(json!({"join_algorithm": "grace_hash"})).is_ok());
+        assert!(settings(json!({"join_algorithm": "direct,hash"})).is_ok());
+        assert!(settings(json!({"join_algorithm": "nested_loop"})).is_err());
+        // limits can only be lowered
+        assert!(settings(json!({"max_execution_time": 120})).is_err());
+        assert!(settings(json!({"max_execution_time": 0})).is_err());
+        assert!(settings(json!({"max_memory_usage": 0})).is_err());
+        assert!(settings(json!({"max_memory_usage": "-1"})).is_err());
+        assert!(settings(json!({"max_memory_usage": "1G"})).is_ok());
+        // not allowed at all
+        assert!(settings(json!({"readonly": 0})).is_err());
+        assert!(settings(json!({"query_id": "other"})).is_err());
+        assert!(settings(json!({"session_id": "s1"})).is_err());
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("60"), Some(60));
+        assert_eq!(parse_limit("10G"), Some(10_000_000_000));
+        assert_eq!(parse_limit("512Mi"), Some(512 << 20));
+        assert_eq!(parse_limit("1.5"), None);
+        assert_eq!(parse_limit("10GB"), None);
+        assert_eq!(parse_limit(""), None);
+    }
 }
@@ -0,0 +1,275 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use clickhouse::Client;
+use futures_util::future::join_all;
+use tokio::time::{interval, timeout};
+use tracing::{info, warn};
+
+use super::{
+    config::LoadBalancing, errors::BrahmandError, metrics::METRICS,
+    query_registry::RunningQueryGuard,
+};
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Replicas serving the read queries. DDL, writes, jobs and the graph catalog always use the
+// client of CLICKHOUSE_URL. The replicas share its settings and pool, only the url differs, so
+// per caller options like the role or the credentials apply on every replica.
+pub struct ClickHousePool {
+    replicas: Vec<Arc<Replica>>,
+    load_balancing: LoadBalancing,
+    next: AtomicUsize,
+}
+
+#[derive(Debug)]
+pub struct Replica {
+    url: String,
+    // replicas start healthy, failed connections and health checks take them out of rotation
+    healthy: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+// Counts a read query against its replica while it runs.
+#[derive(Debug)]
+pub struct ReplicaLease(Arc<Replica>);
+
+impl ClickHousePool {
+    // Without replicas the reads go to `url` as well.
+    pub fn new(url: &str, replica_urls: &[String], load_balancing: LoadBalancing) -> Self {
+        let replica_urls = if replica_urls.is_empty() {
+            vec![url.to_string()]
+        } else {
+            replica_urls.to_vec()
+        };
+        let replicas = replica_urls
+            .into_iter()
+            .map(|url| {
+                METRICS.set_replica_healthy(&url, true);
+                Arc::new(Replica {
+                    url,
+                    healthy: AtomicBool::new(true),
+                    in_flight: AtomicUsize::new(0),
+                })
+            })
+            .collect();
+        ClickHousePool {
+            replicas,
+            load_balancing,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn healthy_replicas(&self) -> usize {
+        self.replicas
+            .iter()
+            .filter(|replica| replica.is_healthy())
+            .count()
+    }
+
+    // Replicas in the order a read should try them. Unhealthy ones come last, they are only
+    // used when every other replica failed.
+    fn candidates(&self) -> Vec<Arc<Replica>> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        let mut candidates: Vec<Arc<Replica>> = self.replicas[start..]
+            .iter()
+            .chain(&self.replicas[..start])
+            .cloned()
+            .collect();
+        // stable, so replicas with the same load keep the round robin order
+        match self.load_balancing {
+            LoadBalancing::RoundRobin => candidates.sort_by_key(|replica| !replica.is_healthy()),
+            LoadBalancing::LeastLoaded => candidates.sort_by_key(|replica| {
+                (
+                    !replica.is_healthy(),
+                    replica.in_flight.load(Ordering::Relaxed),
+                )
+            }),
+        }
+        candidates
+    }
+
+    // Runs `read` with `clickhouse_client` pointed at a replica and carrying the query_id of
+    // `running_query`. A connection error takes the replica out of rotation and the read is
+    // tried on the next one, so `read` must fail before returning any row to the caller.
+    pub async fn read<T, F, Fut>(
+        &self,
+        clickhouse_client: &Client,
+        running_query: &mut RunningQueryGuard,
+        mut read: F,
+    ) -> Result<T, BrahmandError>
+    where
+        F: FnMut(Client) -> Fut,
+        Fut: Future<Output = Result<T, BrahmandError>>,
+    {
+        let mut last_error = None;
+        for replica in self.candidates() {
+            let replica_client = clickhouse_client
+                .clone()
+                .with_url(&replica.url)
+                .with_option("query_id", running_query.query_id());
+            running_query.set_replica(replica.lease());
+            match read(replica_client).await {
+                Err(e) if is_connection_error(&e) => {
+                    replica.set_healthy(false);
+                    warn!(
+                        "Read failed on replica {}, trying the next one",
+                        replica.url
+                    );
+                    last_error = Some(e);
+                }
+                // an exception from ClickHouse ends the query there, it is not killed
+                Err(BrahmandError::Clickhouse(clickhouse::error::Error::BadResponse(message))) => {
+                    running_query.set_failed();
+                    return Err(BrahmandError::Clickhouse(
+                        clickhouse::error::Error::BadResponse(message),
+                    ));
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            BrahmandError::Unavailable("No ClickHouse replica is available.".to_string())
+        }))
+    }
+
+    // Pings every replica on each tick so that replicas come back into rotation.
+    pub async fn run_health_checks(&self, clickhouse_client: Client, check_interval: Duration) {
+        let mut ticker = interval(check_interval);
+        loop {
+            ticker.tick().await;
+            join_all(self.replicas.iter().map(|replica| {
+                let replica_client = clickhouse_client.clone().with_url(&replica.url);
+                async move {
+                    let ping = replica_client.query("SELECT 1").fetch_one::<u8>();
+                    let is_healthy = matches!(timeout(HEALTH_CHECK_TIMEOUT, ping).await, Ok(Ok(_)));
+                    replica.set_healthy(is_healthy);
+                }
+            }))
+            .await;
+        }
+    }
+}
+
+impl Replica {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.healthy.load(Ordering::Relaxed)
+    }
+
+    fn set_healthy(&self, is_healthy: bool) {
+        if self.healthy.swap(is_healthy, Ordering::Relaxed) != is_healthy {
+            if is_healthy {
+                info!("ClickHouse replica {} is back", self.url);
+            } else {
+                warn!("ClickHouse replica {} is down", self.url);
+            }
+        }
+        METRICS.set_replica_healthy(&self.url, is_healthy);
+    }
+
+    fn lease(self: &Arc<Self>) -> ReplicaLease {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        ReplicaLease(self.clone())
+    }
+}
+
+impl ReplicaLease {
+    pub fn url(&self) -> &str {
+        self.0.url()
+    }
+}
+
+impl Drop for ReplicaLease {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn is_connection_error(error: &BrahmandError) -> bool {
+    matches!(
+        error,
+        BrahmandError::Clickhouse(clickhouse::error::Error::Network(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::query_registry::QueryRegistry;
+
+    fn urls(candidates: &[Arc<Replica>]) -> Vec<&str> {
+        candidates.iter().map(|replica| replica.url()).collect()
+    }
+
+    #[test]
+    fn test_candidates() {
+        let replica_urls = vec!["http://r1".to_string(), "http://r2".to_string()];
+        let pool = ClickHousePool::new("http://writer", &replica_urls, LoadBalancing::RoundRobin);
+        assert_eq!(urls(&pool.candidates()), vec!["http://r1", "http://r2"]);
+        assert_eq!(urls(&pool.candidates()), vec!["http://r2", "http://r1"]);
+
+        pool.replicas[1].set_healthy(false);
+        assert_eq!(urls(&pool.candidates()), vec!["http://r1", "http://r2"]);
+        assert_eq!(urls(&pool.candidates()), vec!["http://r1", "http://r2"]);
+        assert_eq!(pool.healthy_replicas(), 1);
+
+        let pool = ClickHousePool::new("http://writer", &replica_urls, LoadBalancing::LeastLoaded);
+        let _lease = pool.replicas[0].lease();
+        assert_eq!(urls(&pool.candidates()), vec!["http://r2", "http://r1"]);
+        assert_eq!(urls(&pool.candidates()), vec!["http://r2", "http://r1"]);
+
+        let pool = ClickHousePool::new("http://writer", &[], LoadBalancing::RoundRobin);
+        assert_eq!(urls(&pool.candidates()), vec!["http://writer"]);
+    }
+
+    #[tokio::test]
+    async fn test_read_failover() {
+        let replica_urls = vec!["http://r1".to_string(), "http://r2".to_string()];
+        let pool = ClickHousePool::new("http://writer", &replica_urls, LoadBalancing::RoundRobin);
+        let registry = Arc::new(QueryRegistry::default());
+        let mut running_query = registry
+            .register(
+                "q1".to_string(),
+                None,
+                "".to_string(),
+                "".to_string(),
+                Client::default(),
+                None,
+            )
+            .unwrap();
+
+        let mut attempts = 0;
+        let result = pool
+            .read(&Client::default(), &mut running_query, |_| {
+                attempts += 1;
+                let attempt = attempts;
+                async move {
+                    if attempt == 1 {
+                        Err(BrahmandError::Clickhouse(
+                            clickhouse::error::Error::Network("connection refused".into()),
+                        ))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(pool.healthy_replicas(), 1);
+        assert_eq!(
+            registry.get_running_queries()[0].replica.as_deref(),
+            Some("http://r2")
+        );
+        running_query.finish();
+    }
+}
@@ -0,0 +1,768 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{Args, Parser, builder::BoolishValueParser};
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::{
+    auth::IdentityForwarding,
+    clickhouse_client::{is_limit_setting, is_valid_setting_name, parse_limit},
+    models::OutputFormat,
+    query_limits::QueryLimits,
+};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    // config file, TLS certificates, JWT keys or ACL file
+    #[error("Unable to load {path}: {message}")]
+    File { path: String, message: String },
+    #[error("Invalid configuration:\n{}", .0.iter().map(|problem| format!("  - {problem}")).collect::<Vec<_>>().join("\n"))]
+    Invalid(Vec<String>),
+}
+
+impl ConfigError {
+    pub fn file(path: &Path, message: impl Display) -> Self {
+        ConfigError::File {
+            path: path.display().to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+// Every option can be given as a command line flag, an env variable or in the TOML file of
+// --config, in this order of precedence, e.g.
+//
+// [server]
+// port = 8080
+// schema_refresh_interval_secs = 30
+//
+// [clickhouse]
+// url = "https://clickhouse:8443"
+// user = "brahmand"
+// tls_ca_file = "/etc/brahmand/ca.pem"
+//
+// [limits]
+// max_hops = 5
+//
+// Run `brahmand --help` for the flags and env variables.
+#[derive(Debug, Default, Parser, Deserialize)]
+#[command(name = "brahmand", version, about = "openCypher over ClickHouse")]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    /// TOML config file
+    #[arg(long, env = "BRAHMAND_CONFIG")]
+    #[serde(skip)]
+    config: Option<PathBuf>,
+    #[command(flatten)]
+    server: ServerOptions,
+    #[command(flatten)]
+    clickhouse: ClickHouseOptions,
+    #[command(flatten)]
+    limits: LimitOptions,
+    #[command(flatten)]
+    auth: AuthOptions,
+    #[command(flatten)]
+    logging: LoggingOptions,
+}
+
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ServerOptions {
+    /// Address the HTTP and Bolt servers listen on [default: 0.0.0.0]
+    #[arg(long, env = "BRAHMAND_HOST")]
+    host: Option<String>,
+    /// HTTP port [default: 8080]
+    #[arg(long, env = "BRAHMAND_PORT")]
+    port: Option<u16>,
+    /// Bolt port [default: 7687]
+    #[arg(long, env = "BRAHMAND_BOLT_PORT")]
+    bolt_port: Option<u16>,
+    /// Serve the Bolt protocol for Neo4j drivers [default: true]
+    #[arg(long, env = "BRAHMAND_BOLT_ENABLED", value_parser = BoolishValueParser::new())]
+    bolt_enabled: Option<bool>,
+    /// Seconds between checks of the graph schema in ClickHouse [default: 60]
+    #[arg(long, env = "BRAHMAND_SCHEMA_REFRESH_INTERVAL_SECS")]
+    schema_refresh_interval_secs: Option<u64>,
+    /// Format of /query results when the request has none [default: JSONEachRow]
+    #[arg(long, env = "BRAHMAND_DEFAULT_FORMAT")]
+    default_format: Option<String>,
+    /// Hours the results of a job are kept [default: 24]
+    #[arg(long, env = "BRAHMAND_JOB_RESULTS_TTL_HOURS")]
+    job_results_ttl_hours: Option<u64>,
+    /// Seconds running queries are given to finish on SIGTERM [default: 30]
+    #[arg(long, env = "BRAHMAND_SHUTDOWN_TIMEOUT_SECS")]
+    shutdown_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ClickHouseOptions {
+    /// ClickHouse HTTP(S) url e.g. http://localhost:8123
+    #[arg(long = "clickhouse-url", env = "CLICKHOUSE_URL")]
+    url: Option<String>,
+    /// Replicas serving the read queries e.g. "http://replica1:8123,http://replica2:8123",
+    /// DDL and writes go to the url [default: the url]
+    #[arg(long = "clickhouse-replicas", env = "CLICKHOUSE_REPLICAS")]
+    replicas: Option<String>,
+    /// Routing of reads to the replicas: round_robin or least_loaded [default: round_robin]
+    #[arg(long = "clickhouse-load-balancing", env = "CLICKHOUSE_LOAD_BALANCING")]
+    load_balancing: Option<String>,
+    /// Seconds between health checks of the replicas [default: 10]
+    #[arg(
+        long = "clickhouse-health-check-interval-secs",
+        env = "CLICKHOUSE_HEALTH_CHECK_INTERVAL_SECS"
+    )]
+    health_check_interval_secs: Option<u64>,
+    /// [default: default]
+    #[arg(long = "clickhouse-user", env = "CLICKHOUSE_USER")]
+    user: Option<String>,
+    #[arg(
+        long = "clickhouse-password",
+        env = "CLICKHOUSE_PASSWORD",
+        hide_env_values = true
+    )]
+    password: Option<String>,
+    /// [default: default]
+    #[arg(long = "clickhouse-database", env = "CLICKHOUSE_DATABASE")]
+    database: Option<String>,
+    /// Server wide settings e.g. "max_execution_time=60,max_memory_usage=10000000000"
+    #[arg(long = "clickhouse-settings", env = "CLICKHOUSE_SETTINGS")]
+    settings: Option<String>,
+    /// PEM CA certificate trusted for https urls, in addition to the system ones
+    #[arg(long = "clickhouse-tls-ca-file", env = "CLICKHOUSE_TLS_CA_FILE")]
+    tls_ca_file: Option<PathBuf>,
+    /// PEM client certificate for https urls
+    #[arg(long = "clickhouse-tls-cert-file", env = "CLICKHOUSE_TLS_CERT_FILE")]
+    tls_cert_file: Option<PathBuf>,
+    /// PEM (PKCS #8) key of the client certificate
+    #[arg(long = "clickhouse-tls-key-file", env = "CLICKHOUSE_TLS_KEY_FILE")]
+    tls_key_file: Option<PathBuf>,
+    /// Idle connections kept per ClickHouse host [default: unlimited]
+    #[arg(long = "clickhouse-pool-max-idle", env = "CLICKHOUSE_POOL_MAX_IDLE")]
+    pool_max_idle: Option<usize>,
+    /// Seconds before an idle connection is closed [default: 2]
+    #[arg(
+        long = "clickhouse-pool-idle-timeout-secs",
+        env = "CLICKHOUSE_POOL_IDLE_TIMEOUT_SECS"
+    )]
+    pool_idle_timeout_secs: Option<u64>,
+    /// [default: 10]
+    #[arg(
+        long = "clickhouse-connect-timeout-secs",
+        env = "CLICKHOUSE_CONNECT_TIMEOUT_SECS"
+    )]
+    connect_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LimitOptions {
+    /// Relationships allowed in a match pattern
+    #[arg(long, env = "BRAHMAND_MAX_HOPS")]
+    max_hops: Option<usize>,
+    /// Selects combined with UNION allowed in a query
+    #[arg(long, env = "BRAHMAND_MAX_UNIONS")]
+    max_unions: Option<usize>,
+    /// LIMIT added to every read query
+    #[arg(long, env = "BRAHMAND_MAX_RESULT_ROWS")]
+    max_result_rows: Option<i64>,
+    /// Seconds after which running queries are killed
+    #[arg(long, env = "BRAHMAND_QUERY_TIMEOUT_SECS")]
+    query_timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AuthOptions {
+    /// API keys and their users e.g. "key1=alice,key2=bob"
+    #[arg(long, env = "BRAHMAND_API_KEYS", hide_env_values = true)]
+    api_keys: Option<String>,
+    /// Users of HTTP Basic e.g. "alice:secret,bob:secret2"
+    #[arg(long, env = "BRAHMAND_BASIC_USERS", hide_env_values = true)]
+    basic_users: Option<String>,
+    /// How queries run in ClickHouse: none, credentials or role [default: none]
+    #[arg(long, env = "BRAHMAND_CLICKHOUSE_IDENTITY")]
+    clickhouse_identity: Option<String>,
+    /// PEM public key verifying JWTs
+    #[arg(long, env = "BRAHMAND_JWT_PUBLIC_KEY_FILE")]
+    jwt_public_key_file: Option<PathBuf>,
+    /// JWKS file verifying JWTs
+    #[arg(long, env = "BRAHMAND_JWT_JWKS_FILE")]
+    jwt_jwks_file: Option<PathBuf>,
+    #[arg(long, env = "BRAHMAND_JWT_ISSUER")]
+    jwt_issuer: Option<String>,
+    #[arg(long, env = "BRAHMAND_JWT_AUDIENCE")]
+    jwt_audience: Option<String>,
+    /// [default: sub]
+    #[arg(long, env = "BRAHMAND_JWT_USER_CLAIM")]
+    jwt_user_claim: Option<String>,
+    /// [default: roles]
+    #[arg(long, env = "BRAHMAND_JWT_ROLES_CLAIM")]
+    jwt_roles_claim: Option<String>,
+    /// JSON file of graph permissions per role
+    #[arg(long, env = "BRAHMAND_ACL_FILE")]
+    acl_file: Option<PathBuf>,
+    /// Role which can see and cancel the queries and jobs of every user [default: admin]
+    #[arg(long, env = "BRAHMAND_ADMIN_ROLE")]
+    admin_role: Option<String>,
+}
+
+#[derive(Debug, Default, Args, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LoggingOptions {
+    /// Level or filter directives e.g. "info,brahmand::query_planner=debug" [default: info]
+    #[arg(long = "log", env = "BRAHMAND_LOG")]
+    filter: Option<String>,
+    /// text or json [default: text]
+    #[arg(long = "log-format", env = "BRAHMAND_LOG_FORMAT")]
+    format: Option<String>,
+    /// Replace the literals of the logged SQL with `?`
+    #[arg(long = "log-redact-sql", env = "BRAHMAND_LOG_REDACT_SQL", value_parser = BoolishValueParser::new())]
+    redact_sql: Option<bool>,
+    /// ClickHouse table every query is recorded to
+    #[arg(long, env = "BRAHMAND_QUERY_LOG_TABLE")]
+    query_log_table: Option<String>,
+}
+
+// Values of `self` take precedence over the ones of `lower`.
+macro_rules! impl_merge {
+    ($options:ident { $($field:ident),* $(,)? }) => {
+        impl $options {
+            fn merge(self, lower: Self) -> Self {
+                $options {
+                    $($field: self.$field.or(lower.$field)),*
+                }
+            }
+        }
+    };
+}
+
+impl_merge!(ServerOptions {
+    host,
+    port,
+    bolt_port,
+    bolt_enabled,
+    schema_refresh_interval_secs,
+    default_format,
+    job_results_ttl_hours,
+    shutdown_timeout_secs,
+});
+impl_merge!(ClickHouseOptions {
+    url,
+    replicas,
+    load_balancing,
+    health_check_interval_secs,
+    user,
+    password,
+    database,
+    settings,
+    tls_ca_file,
+    tls_cert_file,
+    tls_key_file,
+    pool_max_idle,
+    pool_idle_timeout_secs,
+    connect_timeout_secs,
+});
+impl_merge!(LimitOptions {
+    max_hops,
+    max_unions,
+    max_result_rows,
+    query_timeout_secs,
+});
+impl_merge!(AuthOptions {
+    api_keys,
+    basic_users,
+    clickhouse_identity,
+    jwt_public_key_file,
+    jwt_jwks_file,
+    jwt_issuer,
+    jwt_audience,
+    jwt_user_claim,
+    jwt_roles_claim,
+    acl_file,
+    admin_role,
+});
+impl_merge!(LoggingOptions {
+    filter,
+    format,
+    redact_sql,
+    query_log_table,
+});
+
+impl Options {
+    fn merge(self, lower: Self) -> Self {
+        Options {
+            config: self.config.or(lower.config),
+            server: self.server.merge(lower.server),
+            clickhouse: self.clickhouse.merge(lower.clickhouse),
+            limits: self.limits.merge(lower.limits),
+            auth: self.auth.merge(lower.auth),
+            logging: self.logging.merge(lower.logging),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub clickhouse: ClickHouseConfig,
+    pub limits: QueryLimits,
+    pub auth: AuthConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    // None when Bolt is disabled
+    pub bolt_port: Option<u16>,
+    pub schema_refresh_interval: Duration,
+    pub default_format: OutputFormat,
+    pub job_results_ttl_hours: u64,
+    pub shutdown_timeout: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickHouseConfig {
+    pub url: String,
+    // empty when the reads go to `url`
+    pub replicas: Vec<String>,
+    pub load_balancing: LoadBalancing,
+    pub health_check_interval: Duration,
+    pub user: String,
+    pub password: String,
+    pub database: String,
+    pub settings: Vec<(String, String)>,
+    pub tls_ca_file: Option<PathBuf>,
+    // certificate and key
+    pub tls_identity: Option<(PathBuf, PathBuf)>,
+    pub pool_max_idle: usize,
+    pub pool_idle_timeout: Duration,
+    pub connect_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadBalancing {
+    RoundRobin,
+    // the replica with the fewest running reads
+    LeastLoaded,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthConfig {
+    pub api_keys: HashMap<String, String>,
+    pub basic_users: HashMap<String, String>,
+    pub forwarding: IdentityForwarding,
+    pub jwt: Option<JwtConfig>,
+    pub acl_file: Option<PathBuf>,
+    pub admin_role: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JwtKeyFile {
+    PublicKey(PathBuf),
+    Jwks(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtConfig {
+    pub key_file: JwtKeyFile,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    pub user_claim: String,
+    pub roles_claim: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggingConfig {
+    pub filter: String,
+    pub format: LogFormat,
+    pub redact_sql: bool,
+    pub query_log_table: Option<String>,
+}
+
+impl Config {
+    // Flags and env variables of the process, over the config file when one is given.
+    pub fn load() -> Result<Self, ConfigError> {
+        let options = Options::parse();
+        let options = match &options.config {
+            Some(path) => {
+                let file_options = read_file(path)?;
+                options.merge(file_options)
+            }
+            None => options,
+        };
+        Config::validate(options)
+    }
+
+    // Every problem is reported at once instead of failing on the first one.
+    fn validate(options: Options) -> Result<Self, ConfigError> {
+        let mut problems = vec![];
+        let Options {
+            config: _,
+            server,
+            clickhouse,
+            limits,
+            auth,
+            logging,
+        } = options;
+
+        let default_format = match server.default_format {
+            Some(format) => format.parse().unwrap_or_else(|e| {
+                problems.push(format!("server.default_format: {e}"));
+                OutputFormat::JSONEachRow
+            }),
+            None => OutputFormat::JSONEachRow,
+        };
+        let schema_refresh_interval_secs = server.schema_refresh_interval_secs.unwrap_or(60);
+        if schema_refresh_interval_secs == 0 {
+            problems.push("server.schema_refresh_interval_secs should be at least 1".to_string());
+        }
+        let server = ServerConfig {
+            host: server.host.unwrap_or("0.0.0.0".to_string()),
+            port: server.port.unwrap_or(8080),
+            bolt_port: server
+                .bolt_enabled
+                .unwrap_or(true)
+                .then(|| server.bolt_port.unwrap_or(7687)),
+            schema_refresh_interval: Duration::from_secs(schema_refresh_interval_secs),
+            default_format,
+            job_results_ttl_hours: server.job_results_ttl_hours.unwrap_or(24),
+            shutdown_timeout: Duration::from_secs(server.shutdown_timeout_secs.unwrap_or(30)),
+        };
+        if Some(server.port) == server.bolt_port {
+            problems.push(format!(
+                "server.port and server.bolt_port are both {}",
+                server.port
+            ));
+        }
+
+        let url = clickhouse.url.unwrap_or_default();
+        if url.is_empty() {
+            problems.push(
+                "clickhouse.url is required, set it with --clickhouse-url or CLICKHOUSE_URL"
+                    .to_string(),
+            );
+        }
+        let replicas: Vec<String> = clickhouse
+            .replicas
+            .unwrap_or_default()
+            .split(',')
+            .map(|replica| replica.trim().to_string())
+            .filter(|replica| !replica.is_empty())
+            .collect();
+        for url in std::iter::once(&url).chain(&replicas) {
+            if !url.is_empty() && !url.starts_with("http://") && !url.starts_with("https://") {
+                problems.push(format!(
+                    "clickhouse url `{url}` should start with http:// or https://"
+                ));
+            }
+        }
+        let is_https = std::iter::once(&url)
+            .chain(&replicas)
+            .any(|url| url.starts_with("https://"));
+        let load_balancing = match clickhouse
+            .load_balancing
+            .as_deref()
+            .unwrap_or("round_robin")
+        {
+            "round_robin" => LoadBalancing::RoundRobin,
+            "least_loaded" => LoadBalancing::LeastLoaded,
+            other => {
+                problems.push(format!(
+                    "clickhouse.load_balancing `{other}` should be round_robin or least_loaded"
+                ));
+                LoadBalancing::RoundRobin
+            }
+        };
+        let health_check_interval_secs = clickhouse.health_check_interval_secs.unwrap_or(10);
+        if health_check_interval_secs == 0 {
+            problems.push("clickhouse.health_check_interval_secs should be at least 1".to_string());
+        }
+        let mut settings = parse_pairs(
+            "clickhouse.settings",
+            clickhouse.settings.as_deref(),
+            '=',
+            &mut problems,
+        );
+        for (name, value) in settings.iter_mut() {
+            if !is_valid_setting_name(name) {
+                problems.push(format!(
+                    "clickhouse.settings: invalid setting name `{name}`"
+                ));
+            }
+            // requests can only set stricter limits, which are compared as integers
+            if is_limit_setting(name) {
+                match parse_limit(value) {
+                    Some(limit) => *value = limit.to_string(),
+                    None => problems.push(format!(
+                        "clickhouse.settings: `{name}` should be an integer, e.g. 10000000000 or 10G"
+                    )),
+                }
+            }
+        }
+        let tls_identity = match (clickhouse.tls_cert_file, clickhouse.tls_key_file) {
+            (Some(cert_file), Some(key_file)) => Some((cert_file, key_file)),
+            (None, None) => None,
+            _ => {
+                problems.push(
+                    "clickhouse.tls_cert_file and clickhouse.tls_key_file should be set together"
+                        .to_string(),
+                );
+                None
+            }
+        };
+        if !is_https && (clickhouse.tls_ca_file.is_some() || tls_identity.is_some()) {
+            problems.push("clickhouse TLS files are only used with an https:// url".to_string());
+        }
+        let clickhouse = ClickHouseConfig {
+            url,
+            replicas,
+            load_balancing,
+            health_check_interval: Duration::from_secs(health_check_interval_secs),
+            user: clickhouse.user.unwrap_or("default".to_string()),
+            password: clickhouse.password.unwrap_or_default(),
+            database: clickhouse.database.unwrap_or("default".to_string()),
+            settings,
+            tls_ca_file: clickhouse.tls_ca_file,
+            tls_identity,
+            pool_max_idle: clickhouse.pool_max_idle.unwrap_or(usize::MAX),
+            pool_idle_timeout: Duration::from_secs(clickhouse.pool_idle_timeout_secs.unwrap_or(2)),
+            connect_timeout: Duration::from_secs(clickhouse.connect_timeout_secs.unwrap_or(10)),
+        };
+
+        if limits.max_result_rows.is_some_and(|rows| rows < 1) {
+            problems.push("limits.max_result_rows should be at least 1".to_string());
+        }
+        let limits = QueryLimits {
+            max_hops: limits.max_hops,
+            max_unions: limits.max_unions,
+            max_result_rows: limits.max_result_rows,
+            query_timeout: limits.query_timeout_secs.map(Duration::from_secs),
+        };
+
+        let forwarding = match auth.clickhouse_identity.as_deref().unwrap_or("none") {
+            "none" => IdentityForwarding::None,
+            "credentials" => IdentityForwarding::Credentials,
+            "role" => IdentityForwarding::Role,
+            other => {
+                problems.push(format!(
+                    "auth.clickhouse_identity `{other}` should be none, credentials or role"
+                ));
+                IdentityForwarding::None
+            }
+        };
+        let key_file = match (auth.jwt_public_key_file, auth.jwt_jwks_file) {
+            (Some(path), None) => Some(JwtKeyFile::PublicKey(path)),
+            (None, Some(path)) => Some(JwtKeyFile::Jwks(path)),
+            (None, None) => None,
+            (Some(_), Some(_)) => {
+                problems.push(
+                    "only one of auth.jwt_public_key_file and auth.jwt_jwks_file can be set"
+                        .to_string(),
+                );
+                None
+            }
+        };
+        let jwt = key_file.map(|key_file| JwtConfig {
+            key_file,
+            issuer: auth.jwt_issuer,
+            audience: auth.jwt_audience,
+            user_claim: auth.jwt_user_claim.unwrap_or("sub".to_string()),
+            roles_claim: auth.jwt_roles_claim.unwrap_or("roles".to_string()),
+        });
+        let auth = AuthConfig {
+            api_keys: parse_pairs(
+                "auth.api_keys",
+                auth.api_keys.as_deref(),
+                '=',
+                &mut problems,
+            )
+            .into_iter()
+            .collect(),
+            basic_users: parse_pairs(
+                "auth.basic_users",
+                auth.basic_users.as_deref(),
+                ':',
+                &mut problems,
+            )
+            .into_iter()
+            .collect(),
+            forwarding,
+            jwt,
+            acl_file: auth.acl_file,
+            admin_role: auth.admin_role.unwrap_or("admin".to_string()),
+        };
+
+        let format = match logging.format.as_deref().unwrap_or("text") {
+            "text" => LogFormat::Text,
+            "json" => LogFormat::Json,
+            other => {
+                problems.push(format!("logging.format `{other}` should be text or json"));
+                LogFormat::Text
+            }
+        };
+        if let Some(table) = &logging.query_log_table {
+            let is_valid = !table.is_empty()
+                && table
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+            if !is_valid {
+                problems.push(format!(
+                    "logging.query_log_table `{table}` is not a valid table name"
+                ));
+            }
+        }
+        let logging = LoggingConfig {
+            filter: logging.filter.unwrap_or("info".to_string()),
+            format,
+            redact_sql: logging.redact_sql.unwrap_or(false),
+            query_log_table: logging.query_log_table,
+        };
+
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid(problems));
+        }
+        Ok(Config {
+            server,
+            clickhouse,
+            limits,
+            auth,
+            logging,
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<Options, ConfigError> {
+    let content = fs::read_to_string(path).map_err(|e| ConfigError::file(path, e))?;
+    toml::from_str(&content).map_err(|e| ConfigError::file(path, e))
+}
+
+// Lists of `name<separator>value` separated by commas.
+fn parse_pairs(
+    option: &str,
+    value: Option<&str>,
+    separator: char,
+    problems: &mut Vec<String>,
+) -> Vec<(String, String)> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .filter_map(|entry| match entry.split_once(separator) {
+            Some((name, value)) => Some((name.trim().to_string(), value.trim().to_string())),
+            None => {
+                problems.push(format!(
+                    "{option}: entry `{}` should be name{separator}value",
+                    entry.trim()
+                ));
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layers() {
+        let file_options: Options = toml::from_str(
+            r#"
+            [server]
+            port = 9000
+            schema_refresh_interval_secs = 5
+
+            [clickhouse]
+            url = "http://clickhouse:8123"
+            settings = "max_execution_time=60,max_memory_usage=10G"
+
+            [limits]
+            max_hops = 3
+            "#,
+        )
+        .unwrap();
+        let cli_options =
+            Options::try_parse_from(["brahmand", "--port", "9100", "--log-redact-sql", "yes"])
+                .unwrap();
+
+        let config = Config::validate(cli_options.merge(file_options)).unwrap();
+        assert_eq!(config.server.port, 9100);
+        assert_eq!(config.server.bolt_port, Some(7687));
+        assert_eq!(
+            config.server.schema_refresh_interval,
+            Duration::from_secs(5)
+        );
+        assert_eq!(config.clickhouse.url, "http://clickhouse:8123");
+        assert_eq!(
+            config.clickhouse.settings,
+            vec![
+                ("max_execution_time".to_string(), "60".to_string()),
+                ("max_memory_usage".to_string(), "10000000000".to_string()),
+            ]
+        );
+        assert_eq!(config.limits.max_hops, Some(3));
+        assert!(config.logging.redact_sql);
+
+        assert!(toml::from_str::<Options>("[server]\nprot = 1").is_err());
+
+        let options: Options = toml::from_str(
+            "[server]\nbolt_enabled = false\n[clickhouse]\nurl = \"http://clickhouse:8123\"",
+        )
+        .unwrap();
+        assert_eq!(Config::validate(options).unwrap().server.bolt_port, None);
+    }
+
+    #[test]
+    fn test_validation() {
+        let options: Options = toml::from_str(
+            r#"
+            [server]
+            default_format = "Yaml"
+
+            [clickhouse]
+            url = "http://clickhouse:8123"
+            tls_ca_file = "/etc/ca.pem"
+            settings = "max_threads"
+
+            [auth]
+            clickhouse_identity = "roles"
+            "#,
+        )
+        .unwrap();
+        let ConfigError::Invalid(problems) = Config::validate(options).unwrap_err() else {
+            panic!("expected invalid options");
+        };
+        assert_eq!(problems.len(), 4);
+        assert!(problems[0].starts_with("server.default_format"));
+
+        let ConfigError::Invalid(problems) = Config::validate(Options::default()).unwrap_err()
+        else {
+            panic!("expected invalid options");
+        };
+        assert!(problems[0].starts_with("clickhouse.url is required"));
+
+        let options: Options = toml::from_str(
+            "[clickhouse]\nurl = \"http://clickhouse:8123\"\nsettings = \"max_memory_usage=lots\"",
+        )
+        .unwrap();
+        let ConfigError::Invalid(problems) = Config::validate(options).unwrap_err() else {
+            panic!("expected invalid options");
+        };
+        assert!(problems[0].starts_with("clickhouse.settings: `max_memory_usage`"));
+    }
+}
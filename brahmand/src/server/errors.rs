@@ -0,0 +1,481 @@
+use std::io;
+
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    clickhouse_query_generator::errors::ClickhouseQueryGeneratorError,
+    graph_catalog::errors::GraphSchemaError,
+    open_cypher_parser::errors::{OpenCypherParsingError, ParseErrorReport, SourcePosition},
+    query_planner::{
+        analyzer::errors::AnalyzerError, errors::QueryPlannerError,
+        logical_plan::errors::LogicalPlanError,
+    },
+    render_plan::errors::RenderBuildError,
+};
+
+use super::metrics::METRICS;
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorStage {
+    Parse,
+    Plan,
+    Render,
+    Execute,
+}
+
+impl ErrorStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorStage::Parse => "parse",
+            ErrorStage::Plan => "plan",
+            ErrorStage::Render => "render",
+            ErrorStage::Execute => "execute",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorLocation {
+    pub position: SourcePosition,
+    pub snippet: String,
+}
+
+// Every error that can be returned by the server. Errors from the parser, planner and sql
+// generator are wrapped as they are so that the code and the status are decided in one place.
+#[derive(Debug, Error)]
+pub enum BrahmandError {
+    #[error("{}", report.message)]
+    Parse { report: ParseErrorReport },
+    #[error("{source}")]
+    Planner {
+        source: QueryPlannerError,
+        location: Option<ErrorLocation>,
+    },
+    #[error("{0}")]
+    GraphSchema(#[from] GraphSchemaError),
+    #[error("{0}")]
+    RenderBuild(#[from] RenderBuildError),
+    #[error("{0}")]
+    QueryGenerator(#[from] ClickhouseQueryGeneratorError),
+    #[error("Clickhouse Error: {0}")]
+    Clickhouse(#[from] clickhouse::error::Error),
+    #[error("Invalid row received from Clickhouse: {0}")]
+    InvalidResultRow(#[from] serde_json::Error),
+    #[error("Graph catalog error: {0}")]
+    Catalog(String),
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    LimitExceeded(String),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("No running query with id `{0}`")]
+    QueryNotFound(String),
+    #[error("No job with id `{0}`")]
+    JobNotFound(String),
+    #[error("Job `{0}` has not completed")]
+    JobNotCompleted(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Forbidden(String),
+    // not ready to serve queries, e.g. while starting or shutting down
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    stage: ErrorStage,
+    message: String,
+    position: Option<SourcePosition>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    expected: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: ErrorBody,
+}
+
+impl BrahmandError {
+    pub fn from_parsing_error(query: &str, error: OpenCypherParsingError<'_>) -> Self {
+        BrahmandError::Parse {
+            report: error.report(query),
+        }
+    }
+
+    // The planner only knows the byte offset of what an error is about, turn it into a line and a
+    // column of the query.
+    pub fn locate(self, query: &str) -> Self {
+        match self {
+            BrahmandError::Planner {
+                source,
+                location: None,
+            } => {
+                let location = match &source {
+                    QueryPlannerError::Analyzer {
+                        offset: Some(offset),
+                        ..
+                    } => {
+                        let position = SourcePosition::from_offset(query, *offset);
+                        Some(ErrorLocation {
+                            snippet: position.snippet(query),
+                            position,
+                        })
+                    }
+                    _ => None,
+                };
+                BrahmandError::Planner { source, location }
+            }
+            other => other,
+        }
+    }
+
+    pub fn stage(&self) -> ErrorStage {
+        match self {
+            BrahmandError::Parse { .. }
+            | BrahmandError::InvalidRequest(_)
+            | BrahmandError::Unauthorized(_)
+            | BrahmandError::Forbidden(_) => ErrorStage::Parse,
+            BrahmandError::Planner { .. }
+            | BrahmandError::GraphSchema(_)
+            | BrahmandError::Unsupported(_)
+            | BrahmandError::LimitExceeded(_) => ErrorStage::Plan,
+            BrahmandError::RenderBuild(_) | BrahmandError::QueryGenerator(_) => ErrorStage::Render,
+            BrahmandError::Clickhouse(_)
+            | BrahmandError::InvalidResultRow(_)
+            | BrahmandError::Catalog(_)
+            | BrahmandError::QueryNotFound(_)
+            | BrahmandError::JobNotFound(_)
+            | BrahmandError::JobNotCompleted(_)
+            | BrahmandError::Unavailable(_) => ErrorStage::Execute,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code_and_status().0
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        self.code_and_status().1
+    }
+
+    pub fn position(&self) -> Option<&SourcePosition> {
+        match self {
+            BrahmandError::Parse { report } => Some(&report.position),
+            BrahmandError::Planner {
+                location: Some(location),
+                ..
+            } => Some(&location.position),
+            _ => None,
+        }
+    }
+
+    fn snippet(&self) -> Option<&str> {
+        match self {
+            BrahmandError::Parse { report } => Some(&report.snippet),
+            BrahmandError::Planner {
+                location: Some(location),
+                ..
+            } => Some(&location.snippet),
+            _ => None,
+        }
+    }
+
+    fn code_and_status(&self) -> (&'static str, StatusCode) {
+        match self {
+            BrahmandError::Parse { .. } => ("SYNTAX_ERROR", StatusCode::BAD_REQUEST),
+            BrahmandError::Planner { source, .. } => match source {
+                QueryPlannerError::LogicalPlan(
+                    LogicalPlanError::EmptyNode
+                    | LogicalPlanError::FoundParamInProperties
+                    | LogicalPlanError::DisconnectedPatternFound,
+                ) => ("UNSUPPORTED_QUERY", StatusCode::UNPROCESSABLE_ENTITY),
+                QueryPlannerError::Analyzer { source, .. } => match source {
+                    AnalyzerError::GraphSchema { .. } => ("UNKNOWN_LABEL", StatusCode::NOT_FOUND),
+                    AnalyzerError::OrphanAlias { .. } => {
+                        ("UNDEFINED_VARIABLE", StatusCode::UNPROCESSABLE_ENTITY)
+                    }
+                    AnalyzerError::MissingRelationLabel { .. }
+                    | AnalyzerError::NotEnoughLabels { .. } => {
+                        ("MISSING_LABEL", StatusCode::UNPROCESSABLE_ENTITY)
+                    }
+                    AnalyzerError::InvalidRelationInQuery { .. } => {
+                        ("INVALID_RELATIONSHIP", StatusCode::UNPROCESSABLE_ENTITY)
+                    }
+                    AnalyzerError::AccessDenied { .. } => ("FORBIDDEN", StatusCode::FORBIDDEN),
+                    AnalyzerError::PlanCtx { .. } | AnalyzerError::InvalidPermissions { .. } => {
+                        ("PLANNING_ERROR", StatusCode::INTERNAL_SERVER_ERROR)
+                    }
+                },
+                QueryPlannerError::Optimizer(_) | QueryPlannerError::LogicalPlanExtractor => {
+                    ("PLANNING_ERROR", StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            },
+            BrahmandError::GraphSchema(_) => ("UNKNOWN_LABEL", StatusCode::NOT_FOUND),
+            BrahmandError::RenderBuild(RenderBuildError::MissingParameter(_)) => {
+                ("MISSING_PARAMETER", StatusCode::UNPROCESSABLE_ENTITY)
+            }
+            BrahmandError::RenderBuild(_) => ("RENDER_ERROR", StatusCode::INTERNAL_SERVER_ERROR),
+            BrahmandError::QueryGenerator(generator_error) => match generator_error {
+                ClickhouseQueryGeneratorError::UnknownFromTableInRel
+                | ClickhouseQueryGeneratorError::UnknownToTableInRel => {
+                    ("UNKNOWN_LABEL", StatusCode::NOT_FOUND)
+                }
+                ClickhouseQueryGeneratorError::UnsupportedDDLQuery
+                | ClickhouseQueryGeneratorError::UnsupportedDefaultValue
+                | ClickhouseQueryGeneratorError::MissingPrimaryKey
+                | ClickhouseQueryGeneratorError::MissingNodeId
+                | ClickhouseQueryGeneratorError::MultipleNodeIds
+                | ClickhouseQueryGeneratorError::InvalidNodeIdDType
+                | ClickhouseQueryGeneratorError::InvalidNodeId => {
+                    ("INVALID_DDL", StatusCode::UNPROCESSABLE_ENTITY)
+                }
+                _ => ("RENDER_ERROR", StatusCode::INTERNAL_SERVER_ERROR),
+            },
+            BrahmandError::Clickhouse(clickhouse_error) => match clickhouse_error {
+                clickhouse::error::Error::TimedOut => {
+                    ("QUERY_TIMEOUT", StatusCode::GATEWAY_TIMEOUT)
+                }
+                clickhouse::error::Error::Network(_) => {
+                    ("CLICKHOUSE_UNAVAILABLE", StatusCode::BAD_GATEWAY)
+                }
+                // Server side exceptions only carry the ClickHouse error name in the message.
+                clickhouse::error::Error::BadResponse(message) => {
+                    if message.contains("TIMEOUT_EXCEEDED") {
+                        ("QUERY_TIMEOUT", StatusCode::GATEWAY_TIMEOUT)
+                    } else if message.contains("TABLE_ALREADY_EXISTS") {
+                        ("ALREADY_EXISTS", StatusCode::CONFLICT)
+                    } else {
+                        ("CLICKHOUSE_ERROR", StatusCode::INTERNAL_SERVER_ERROR)
+                    }
+                }
+                _ => ("CLICKHOUSE_ERROR", StatusCode::INTERNAL_SERVER_ERROR),
+            },
+            BrahmandError::InvalidResultRow(_) => {
+                ("INVALID_RESULT", StatusCode::INTERNAL_SERVER_ERROR)
+            }
+            BrahmandError::Catalog(_) => ("CATALOG_ERROR", StatusCode::INTERNAL_SERVER_ERROR),
+            BrahmandError::Unsupported(_) => {
+                ("UNSUPPORTED_QUERY", StatusCode::UNPROCESSABLE_ENTITY)
+            }
+            BrahmandError::LimitExceeded(_) => {
+                ("QUERY_LIMIT_EXCEEDED", StatusCode::UNPROCESSABLE_ENTITY)
+            }
+            BrahmandError::InvalidRequest(_) => ("INVALID_REQUEST", StatusCode::BAD_REQUEST),
+            BrahmandError::QueryNotFound(_) => ("QUERY_NOT_FOUND", StatusCode::NOT_FOUND),
+            BrahmandError::JobNotFound(_) => ("JOB_NOT_FOUND", StatusCode::NOT_FOUND),
+            BrahmandError::JobNotCompleted(_) => ("JOB_NOT_COMPLETED", StatusCode::CONFLICT),
+            BrahmandError::Unauthorized(_) => ("UNAUTHORIZED", StatusCode::UNAUTHORIZED),
+            BrahmandError::Forbidden(_) => ("FORBIDDEN", StatusCode::FORBIDDEN),
+            BrahmandError::Unavailable(_) => ("UNAVAILABLE", StatusCode::SERVICE_UNAVAILABLE),
+        }
+    }
+
+    // Status codes of the Neo4j protocols, they let drivers tell client errors, which should not
+    // be retried, from transient ones.
+    pub fn neo4j_code(&self) -> &'static str {
+        match self.code() {
+            "SYNTAX_ERROR" => "Neo.ClientError.Statement.SyntaxError",
+            "MISSING_PARAMETER" => "Neo.ClientError.Statement.ParameterMissing",
+            "UNKNOWN_LABEL"
+            | "UNDEFINED_VARIABLE"
+            | "MISSING_LABEL"
+            | "INVALID_RELATIONSHIP"
+            | "UNSUPPORTED_QUERY"
+            | "QUERY_LIMIT_EXCEEDED" => "Neo.ClientError.Statement.SemanticError",
+            "INVALID_REQUEST" => "Neo.ClientError.Request.Invalid",
+            "UNAUTHORIZED" => "Neo.ClientError.Security.Unauthorized",
+            "FORBIDDEN" => "Neo.ClientError.Security.Forbidden",
+            "QUERY_TIMEOUT" => "Neo.ClientError.Transaction.TransactionTimedOut",
+            "CLICKHOUSE_UNAVAILABLE" | "UNAVAILABLE" => {
+                "Neo.TransientError.General.DatabaseUnavailable"
+            }
+            _ => "Neo.DatabaseError.General.UnknownError",
+        }
+    }
+}
+
+impl From<QueryPlannerError> for BrahmandError {
+    fn from(source: QueryPlannerError) -> Self {
+        BrahmandError::Planner {
+            source,
+            location: None,
+        }
+    }
+}
+
+impl From<io::Error> for BrahmandError {
+    // Reading the result lines wraps the clickhouse error into an io error.
+    fn from(error: io::Error) -> Self {
+        match error.into_inner() {
+            Some(inner) => match inner.downcast::<clickhouse::error::Error>() {
+                Ok(clickhouse_error) => BrahmandError::Clickhouse(*clickhouse_error),
+                Err(other) => BrahmandError::Clickhouse(clickhouse::error::Error::Other(other)),
+            },
+            None => BrahmandError::Clickhouse(clickhouse::error::Error::Network(
+                "result stream closed unexpectedly".into(),
+            )),
+        }
+    }
+}
+
+impl IntoResponse for BrahmandError {
+    fn into_response(self) -> Response {
+        METRICS.record_error(&self);
+        let body = ErrorResponse {
+            error: ErrorBody {
+                code: self.code(),
+                stage: self.stage(),
+                message: self.to_string(),
+                position: self.position().cloned(),
+                snippet: self.snippet().map(str::to_string),
+                expected: match &self {
+                    BrahmandError::Parse { report } => report.expected.clone(),
+                    _ => vec![],
+                },
+            },
+        };
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        graph_catalog::graph_schema::{
+            GraphSchema, NodeIdSchema, NodeSchema, RelationshipSchema, TableEngineSchema,
+        },
+        open_cypher_parser, query_planner,
+        query_planner::analyzer::errors::Pass,
+    };
+
+    #[test]
+    fn test_parse_error_position() {
+        let query = "MATCH (a) RETURN a. ;";
+        let error = BrahmandError::from_parsing_error(
+            query,
+            open_cypher_parser::parse_query(query).unwrap_err(),
+        );
+
+        assert_eq!(error.stage(), ErrorStage::Parse);
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(error.position().unwrap().column, 19);
+        assert_eq!(error.code(), "SYNTAX_ERROR");
+    }
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "User".to_string(),
+            NodeSchema {
+                table_name: "User".to_string(),
+                column_names: vec!["user_id".to_string(), "name".to_string()],
+                primary_keys: "user_id".to_string(),
+                node_id: NodeIdSchema {
+                    column: "user_id".to_string(),
+                    dtype: "UInt64".to_string(),
+                },
+                engine: TableEngineSchema::default(),
+            },
+        );
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            "FOLLOWS".to_string(),
+            RelationshipSchema {
+                table_name: "FOLLOWS".to_string(),
+                column_names: vec![],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+        GraphSchema::build(1, nodes, relationships, HashMap::new())
+    }
+
+    fn planner_error(query: &str) -> BrahmandError {
+        let ast = open_cypher_parser::parse_query(query).unwrap();
+        let error =
+            query_planner::evaluate_read_query(ast, &create_test_graph_schema(), None).unwrap_err();
+        BrahmandError::from(error).locate(query)
+    }
+
+    #[test]
+    fn test_planner_error_location() {
+        // `name` is also written as a property before it is used as an alias
+        let query = "MATCH (a:User)\nWHERE a.name = 'x' AND name.id = 1 RETURN a.name;";
+        let error = planner_error(query);
+
+        assert_eq!(error.code(), "UNDEFINED_VARIABLE");
+        let position = error.position().unwrap();
+        assert_eq!((position.line, position.column), (2, 24));
+        assert_eq!(
+            error.snippet().unwrap(),
+            "WHERE a.name = 'x' AND name.id = 1 RETURN a.name;\n                       ^"
+        );
+    }
+
+    #[test]
+    fn test_unknown_label_location() {
+        let query = "MATCH (a:User)-[:LIKES]->(b:User) RETURN b.name;";
+        let error = planner_error(query);
+
+        assert_eq!(error.code(), "UNKNOWN_LABEL");
+        assert_eq!(error.position().unwrap().column, 18);
+    }
+
+    #[test]
+    fn test_status_code_mapping() {
+        let unknown_label =
+            BrahmandError::from(QueryPlannerError::from(AnalyzerError::GraphSchema {
+                pass: Pass::SchemaInference,
+                source: GraphSchemaError::Node {
+                    node_label: "Person".to_string(),
+                },
+            }));
+        assert_eq!(unknown_label.code(), "UNKNOWN_LABEL");
+        assert_eq!(unknown_label.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(unknown_label.stage(), ErrorStage::Plan);
+
+        let orphan_alias =
+            BrahmandError::from(QueryPlannerError::from(AnalyzerError::OrphanAlias {
+                pass: Pass::FilterTagging,
+                alias: "x".to_string(),
+            }));
+        assert_eq!(orphan_alias.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let timeout = BrahmandError::from(clickhouse::error::Error::BadResponse(
+            "Code: 159. DB::Exception: Timeout exceeded: elapsed 10 seconds. (TIMEOUT_EXCEEDED)"
+                .to_string(),
+        ));
+        assert_eq!(timeout.code(), "QUERY_TIMEOUT");
+        assert_eq!(timeout.status_code(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(timeout.stage(), ErrorStage::Execute);
+
+        let already_exists = BrahmandError::from(clickhouse::error::Error::BadResponse(
+            "Code: 57. DB::Exception: Table default.User already exists. (TABLE_ALREADY_EXISTS)"
+                .to_string(),
+        ));
+        assert_eq!(already_exists.status_code(), StatusCode::CONFLICT);
+
+        let invalid_ddl = BrahmandError::from(ClickhouseQueryGeneratorError::MissingPrimaryKey);
+        assert_eq!(invalid_ddl.stage(), ErrorStage::Render);
+        assert_eq!(invalid_ddl.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,264 @@
+use std::time::Instant;
+
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use clickhouse::Client;
+use serde_json::Value;
+use tokio::io::AsyncBufReadExt;
+
+use crate::{
+    clickhouse_query_generator,
+    graph_catalog::{graph_permissions::GraphPermissions, graph_schema::GraphSchema},
+    open_cypher_parser::ast::OpenCypherQueryAst,
+    query_planner::{self, plan_ctx::PlanSnapshot, types::QueryType},
+    render_plan::plan_builder::RenderPlanBuilder,
+};
+
+use super::{
+    clickhouse_pool::ClickHousePool,
+    errors::BrahmandError,
+    models::{ExplainResponse, ProfileResponse, QueryStats},
+    query_limits::QueryLimits,
+    query_registry::RunningQueryGuard,
+};
+
+pub struct ExplainedQuery {
+    passes: Vec<PlanSnapshot>,
+    render_plan: String,
+    sql: String,
+}
+
+impl ExplainedQuery {
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+}
+
+pub fn plan_query(
+    cypher_ast: OpenCypherQueryAst,
+    graph_schema: &GraphSchema,
+    query_limits: &QueryLimits,
+    query: &str,
+    permissions: Option<&GraphPermissions>,
+) -> Result<ExplainedQuery, BrahmandError> {
+    if query_planner::get_query_type(&cypher_ast) != QueryType::Read {
+        return Err(BrahmandError::Unsupported(
+            "EXPLAIN and PROFILE are only supported for read queries.".to_string(),
+        ));
+    }
+
+    let (logical_plan, passes) =
+        query_planner::explain_read_query(cypher_ast, graph_schema, permissions)
+            .map_err(|e| BrahmandError::from(e).locate(query))?;
+    query_limits.check_logical_plan(&logical_plan)?;
+
+    let mut render_plan = logical_plan.to_render_plan()?;
+    query_limits.apply_to_render_plan(&mut render_plan)?;
+    let render_plan_str = render_plan.to_string();
+    let sql = clickhouse_query_generator::generate_sql(render_plan);
+
+    Ok(ExplainedQuery {
+        passes,
+        render_plan: render_plan_str,
+        sql,
+    })
+}
+
+pub async fn explain_handler(
+    clickhouse_client: Client,
+    explained_query: ExplainedQuery,
+) -> Result<Response, BrahmandError> {
+    let clickhouse_plan = fetch_lines(
+        &clickhouse_client,
+        &format!("EXPLAIN PLAN {}", explained_query.sql),
+    )
+    .await?;
+    let clickhouse_pipeline = fetch_lines(
+        &clickhouse_client,
+        &format!("EXPLAIN PIPELINE {}", explained_query.sql),
+    )
+    .await?;
+
+    Ok(Json(ExplainResponse {
+        passes: explained_query.passes,
+        render_plan: explained_query.render_plan,
+        sql: explained_query.sql,
+        clickhouse_plan,
+        clickhouse_pipeline,
+    })
+    .into_response())
+}
+
+// Runs the query on a replica like any read, but the rows are only counted. The stats come
+// from the progress ClickHouse sends along with the rows, the same data as its
+// X-ClickHouse-Summary header.
+pub async fn profile_handler(
+    clickhouse_pool: &ClickHousePool,
+    clickhouse_client: Client,
+    mut running_query: RunningQueryGuard,
+    explained_query: ExplainedQuery,
+) -> Result<Response, BrahmandError> {
+    let instant = Instant::now();
+    let (result_rows, query_stats) = clickhouse_pool
+        .read(
+            &clickhouse_client,
+            &mut running_query,
+            |clickhouse_client| {
+                let query = clickhouse_client.query(&explained_query.sql);
+                async move {
+                    let mut lines = query.fetch_bytes("JSONEachRowWithProgress")?.lines();
+                    let mut result_rows = 0;
+                    let mut query_stats = None;
+                    while let Some(line) = lines.next_line().await? {
+                        let event: Value = serde_json::from_str(&line)?;
+                        if event.get("row").is_some() {
+                            result_rows += 1;
+                        } else if let Some(progress) = event.get("progress") {
+                            // progress is cumulative, the last one covers the whole query
+                            query_stats = Some(query_stats_from_progress(progress));
+                        } else if let Some(exception) = event.get("exception") {
+                            return Err(BrahmandError::Clickhouse(
+                                clickhouse::error::Error::BadResponse(
+                                    exception.as_str().unwrap_or_default().to_string(),
+                                ),
+                            ));
+                        }
+                    }
+                    Ok((result_rows, query_stats))
+                }
+            },
+        )
+        .await?;
+    let elapsed_ms = instant.elapsed().as_millis() as u64;
+    running_query.query_log().add_rows(result_rows);
+    running_query.finish();
+
+    Ok(Json(ProfileResponse {
+        passes: explained_query.passes,
+        render_plan: explained_query.render_plan,
+        sql: explained_query.sql,
+        result_rows,
+        elapsed_ms,
+        query_stats,
+    })
+    .into_response())
+}
+
+// ClickHouse writes the counters of the progress as strings.
+fn query_stats_from_progress(progress: &Value) -> QueryStats {
+    let counter = |name: &str| {
+        let value = &progress[name];
+        value
+            .as_str()
+            .and_then(|value| value.parse().ok())
+            .or_else(|| value.as_u64())
+            .unwrap_or_default()
+    };
+    QueryStats {
+        read_rows: counter("read_rows"),
+        read_bytes: counter("read_bytes"),
+        total_rows_to_read: counter("total_rows_to_read"),
+        elapsed_ns: counter("elapsed_ns"),
+        peak_memory_usage: ["peak_memory_usage", "memory_usage"]
+            .into_iter()
+            .find(|name| progress.get(name).is_some())
+            .map(counter),
+    }
+}
+
+async fn fetch_lines(
+    clickhouse_client: &Client,
+    query: &str,
+) -> Result<Vec<String>, BrahmandError> {
+    let mut lines = clickhouse_client
+        .query(query)
+        .fetch_bytes("TabSeparatedRaw")?
+        .lines();
+    let mut result = vec![];
+    while let Some(line) = lines.next_line().await? {
+        result.push(line);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use clickhouse::test::{Mock, handlers};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::{config::LoadBalancing, query_registry::QueryRegistry};
+
+    #[tokio::test]
+    async fn test_profile_counts_rows_and_reads_progress() {
+        let mock = Mock::new();
+        mock.add(handlers::provide(
+            concat!(
+                "{\"row\":{\"name\":\"a\"}}\n",
+                "{\"progress\":{\"read_rows\":\"1\",\"read_bytes\":\"8\"}}\n",
+                "{\"row\":{\"name\":\"b\"}}\n",
+                "{\"progress\":{\"read_rows\":\"2\",\"read_bytes\":\"16\",",
+                "\"total_rows_to_read\":\"2\",\"elapsed_ns\":\"1000\",",
+                "\"peak_memory_usage\":\"4096\"}}\n",
+            )
+            .as_bytes()
+            .to_vec(),
+        ));
+        let pool = ClickHousePool::new(mock.url(), &[], LoadBalancing::RoundRobin);
+        let registry = Arc::new(QueryRegistry::default());
+        let running_query = registry
+            .register(
+                "q1".to_string(),
+                None,
+                "".to_string(),
+                "".to_string(),
+                Client::default(),
+                None,
+            )
+            .unwrap();
+        let explained_query = ExplainedQuery {
+            passes: vec![],
+            render_plan: String::new(),
+            sql: "SELECT name FROM User".to_string(),
+        };
+
+        let response = profile_handler(&pool, Client::default(), running_query, explained_query)
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let profile: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(profile["result_rows"], 2);
+        assert!(profile.get("rows").is_none());
+        assert_eq!(
+            profile["query_stats"],
+            json!({
+                "read_rows": 2,
+                "read_bytes": 16,
+                "total_rows_to_read": 2,
+                "elapsed_ns": 1000,
+                "peak_memory_usage": 4096,
+            })
+        );
+        assert!(registry.find("q1").is_none());
+    }
+
+    #[test]
+    fn test_memory_usage_from_progress() {
+        let stats = |progress: Value| query_stats_from_progress(&progress).peak_memory_usage;
+        assert_eq!(stats(json!({"memory_usage": "2048"})), Some(2048));
+        assert_eq!(
+            stats(json!({"memory_usage": "2048", "peak_memory_usage": "4096"})),
+            Some(4096)
+        );
+        // older servers do not report memory
+        assert_eq!(stats(json!({"read_rows": "1"})), None);
+    }
+}
@@ -2,17 +2,29 @@ use std::time::Duration;
 
 use clickhouse::Client;
 use tokio::{sync::RwLock, time::interval};
+use tracing::{error, info};
 
-use crate::graph_catalog::graph_schema::{GraphSchema, GraphSchemaElement};
+use crate::graph_catalog::{
+    errors::GraphSchemaError,
+    graph_schema::{GraphSchema, GraphSchemaElement},
+};
 
-use super::{GLOBAL_GRAPH_SCHEMA, models::GraphCatalog};
+use super::{GLOBAL_GRAPH_SCHEMA, lifecycle, metrics::METRICS, models::GraphCatalog};
 
 pub async fn initialize_global_schema(clickhouse_client: Client) {
-    let schema = get_graph_catalog(clickhouse_client).await.unwrap();
+    let schema = lifecycle::retry_with_backoff("load the graph schema", || {
+        get_graph_catalog(clickhouse_client.clone())
+    })
+    .await;
+    METRICS.set_schema_version(schema.get_version());
     // Set the global schema wrapped in an RwLock.
     GLOBAL_GRAPH_SCHEMA.set(RwLock::new(schema)).ok();
 }
 
+pub fn is_schema_loaded() -> bool {
+    GLOBAL_GRAPH_SCHEMA.initialized()
+}
+
 pub async fn refresh_global_schema(clickhouse_client: Client) -> Result<(), String> {
     let new_schema = get_graph_catalog(clickhouse_client).await?;
     // Acquire a write lock asynchronously.
@@ -20,8 +32,9 @@ pub async fn refresh_global_schema(clickhouse_client: Client) -> Result<(), Stri
         .get()
         .ok_or("Global schema not initialized")?;
     let mut schema_guard = global_schema_lock.write().await;
+    METRICS.set_schema_version(new_schema.get_version());
     *schema_guard = new_schema;
-    println!("Global schema refreshed");
+    info!("Global schema refreshed");
     Ok(())
 }
 
@@ -55,7 +68,7 @@ pub async fn get_graph_catalog(clickhouse_client: Client) -> Result<GraphSchema,
             // println!("err_msg -> {:?}", err_msg);
 
             if err_msg.contains("UNKNOWN_TABLE") {
-                println!("Creating the graph_catalog table");
+                info!("Creating the graph_catalog table");
                 let create_graph_catalog_query = "
                 CREATE TABLE graph_catalog (
                     id UInt64,
@@ -98,7 +111,9 @@ pub async fn get_graph_catalog(clickhouse_client: Client) -> Result<GraphSchema,
     }
 }
 
-pub async fn validate_schema(graph_schema_element: &Vec<GraphSchemaElement>) -> Result<(), String> {
+pub async fn validate_schema(
+    graph_schema_element: &Vec<GraphSchemaElement>,
+) -> Result<(), GraphSchemaError> {
     for element in graph_schema_element {
         if let GraphSchemaElement::Rel(relationship_schema) = element {
             // here check if both from_node and to_node tables are present or not in the schema
@@ -109,14 +124,9 @@ pub async fn validate_schema(graph_schema_element: &Vec<GraphSchemaElement>) ->
                 .read()
                 .await;
 
-            if !graph_schema_lock
-                .get_nodes_schemas()
-                .contains_key(&relationship_schema.from_node)
-                || !graph_schema_lock
-                    .get_nodes_schemas()
-                    .contains_key(&relationship_schema.to_node)
-            {
-                return Err("From and To node tables must be present before creating a relationship between them".to_string());
+            // From and To node tables must be present before creating a relationship between them
+            for node_label in [&relationship_schema.from_node, &relationship_schema.to_node] {
+                graph_schema_lock.get_node_schema(node_label)?;
             }
         }
     }
@@ -148,10 +158,21 @@ pub async fn add_to_schema(
                     relationship_index_schema.table_name.to_string(),
                     relationship_index_schema,
                 );
+                graph_schema.increment_version();
+            }
+            GraphSchemaElement::DropRelIndex(table_name) => {
+                graph_schema.remove_rel_index_schema(&table_name);
+                graph_schema.increment_version();
+            }
+            GraphSchemaElement::PropertyIndex(property_index_schema) => {
+                graph_schema.insert_property_index_schema(property_index_schema);
+                graph_schema.increment_version();
             }
         }
     }
 
+    METRICS.set_schema_version(graph_schema.get_version());
+
     let schema_json = serde_json::to_string(&*graph_schema)
         .map_err(|e| format!("Schema serialization error: {}", e))?;
 
@@ -175,27 +196,30 @@ pub async fn add_to_schema(
 // This function periodically checks for schema updates.
 // This will be helpful in distributed environment where schema has changed.
 // In distributed environment, I think Keeper Map engine makes sense.
-pub async fn monitor_schema_updates(ch_client: Client) -> Result<(), String> {
-    // TODO Currently checking after every min. Make it an option to set by user.
-    let mut ticker = interval(Duration::from_secs(60));
+pub async fn monitor_schema_updates(
+    ch_client: Client,
+    refresh_interval: Duration,
+) -> Result<(), String> {
+    let mut ticker = interval(refresh_interval);
 
     loop {
         ticker.tick().await;
 
-        // get in memory data for the graph schema
-        let in_mem_schema_guard = GLOBAL_GRAPH_SCHEMA
+        // get in memory data for the graph schema, the read lock is released right away as the
+        // write lock below would wait for it forever
+        let mem_version = GLOBAL_GRAPH_SCHEMA
             .get()
             .expect("Global schema not initialized")
             .read()
-            .await;
-
-        let mem_version = in_mem_schema_guard.get_version();
+            .await
+            .get_version();
 
         // Fetch the schema from ClickHouse.
         let remote_schema = match get_graph_catalog(ch_client.clone()).await {
             Ok(schema) => schema,
             Err(err) => {
-                eprintln!("Error fetching remote schema: {}", err);
+                error!("Error fetching remote schema: {}", err);
+                METRICS.record_schema_refresh_failure();
                 continue;
             }
         };
@@ -208,8 +232,9 @@ pub async fn monitor_schema_updates(ch_client: Client) -> Result<(), String> {
                 .write()
                 .await;
             *schema_guard = remote_schema.clone();
+            METRICS.set_schema_version(remote_schema.get_version());
 
-            println!(
+            info!(
                 "Global schema updated from version {} to {}",
                 mem_version,
                 remote_schema.get_version()
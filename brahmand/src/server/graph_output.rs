@@ -0,0 +1,243 @@
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use serde_json::{Map, Value, json};
+
+use crate::graph_catalog::graph_schema::GraphSchema;
+
+// Collects every node and relationship entity found in the result rows into
+// {"nodes": [...], "edges": [...]}. Entities are recognised by the shape produced in
+// projection tagging: nodes have `id`, `labels` and `properties`; relationships have
+// `type`, `start_id`, `end_id` and `properties`. Scalars and other values are skipped.
+pub fn build_graph_response(rows: &[Value], graph_schema: &GraphSchema) -> Value {
+    let mut graph = GraphCollector::default();
+    for row in rows {
+        graph.collect(row, graph_schema);
+    }
+    json!({
+        "nodes": graph.nodes,
+        "edges": graph.edges,
+    })
+}
+
+#[derive(Default)]
+struct GraphCollector {
+    nodes: Vec<Value>,
+    edges: Vec<Value>,
+    seen_nodes: HashSet<String>,
+    seen_edges: HashSet<String>,
+}
+
+impl GraphCollector {
+    fn collect(&mut self, value: &Value, graph_schema: &GraphSchema) {
+        match value {
+            Value::Object(object) => {
+                if is_node(object) {
+                    self.add_node(object);
+                } else if is_edge(object) {
+                    self.add_edge(object, graph_schema);
+                } else {
+                    for inner_value in object.values() {
+                        self.collect(inner_value, graph_schema);
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for inner_value in values {
+                    self.collect(inner_value, graph_schema);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_node(&mut self, object: &Map<String, Value>) {
+        let label = object
+            .get("labels")
+            .and_then(|labels| labels.get(0))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let node_key = node_key(label, &object["id"]);
+        if !self.seen_nodes.insert(node_key.clone()) {
+            return;
+        }
+        self.nodes.push(json!({
+            "id": node_key,
+            "label": label,
+            "node_id": object["id"],
+            "properties": object["properties"],
+        }));
+    }
+
+    fn add_edge(&mut self, object: &Map<String, Value>, graph_schema: &GraphSchema) {
+        let rel_type = object["type"].as_str().unwrap_or_default();
+        let (edge_key, source, target) = edge_keys(object, graph_schema);
+        if !self.seen_edges.insert(edge_key.clone()) {
+            return;
+        }
+        self.edges.push(json!({
+            "id": edge_key,
+            "type": rel_type,
+            "source": source,
+            "target": target,
+            "properties": object["properties"],
+        }));
+    }
+}
+
+pub fn is_node(object: &Map<String, Value>) -> bool {
+    object.len() == 3
+        && object.contains_key("id")
+        && object.get("labels").is_some_and(Value::is_array)
+        && object.contains_key("properties")
+}
+
+pub fn is_edge(object: &Map<String, Value>) -> bool {
+    object.len() == 4
+        && object.get("type").is_some_and(Value::is_string)
+        && object.contains_key("start_id")
+        && object.contains_key("end_id")
+        && object.contains_key("properties")
+}
+
+// Edges only carry node ids, the labels of both ends come from the schema. Parallel edges
+// between the same nodes are told apart by their properties.
+// Returns the keys of the edge, its start node and its end node.
+pub fn edge_keys(
+    object: &Map<String, Value>,
+    graph_schema: &GraphSchema,
+) -> (String, String, String) {
+    let rel_type = object["type"].as_str().unwrap_or_default();
+    let (from_label, to_label) = graph_schema
+        .get_relationships_schema_opt(rel_type)
+        .map(|rel_schema| (rel_schema.from_node.as_str(), rel_schema.to_node.as_str()))
+        .unwrap_or_default();
+    let source = node_key(from_label, &object["start_id"]);
+    let target = node_key(to_label, &object["end_id"]);
+    let mut hasher = DefaultHasher::new();
+    object["properties"].to_string().hash(&mut hasher);
+    let edge_key = format!("{}:{}:{}:{:x}", rel_type, source, target, hasher.finish());
+    (edge_key, source, target)
+}
+
+// Neo4j clients also expect integer ids. They are derived from the keys, so they are stable for
+// the lifetime of the server.
+pub fn legacy_id(key: &str) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() & i64::MAX as u64) as i64
+}
+
+// Node ids are only unique within a label, so the label is part of the key.
+pub fn node_key(label: &str, id: &Value) -> String {
+    // string ids are used without their json quotes.
+    match id.as_str() {
+        Some(id) => format!("{}:{}", label, id),
+        None => format!("{}:{}", label, id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph_catalog::graph_schema::{RelationshipSchema, TableEngineSchema};
+    use std::collections::HashMap;
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            "AUTHORED".to_string(),
+            RelationshipSchema {
+                table_name: "AUTHORED".to_string(),
+                column_names: vec![],
+                from_node: "User".to_string(),
+                to_node: "Post".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+        GraphSchema::build(1, HashMap::new(), relationships, HashMap::new())
+    }
+
+    #[test]
+    fn test_build_graph_response_deduplicates_entities() {
+        let user = json!({"id": 1, "labels": ["User"], "properties": {"name": "alice"}});
+        let rows = vec![
+            json!({
+                "u": user,
+                "r": {"type": "AUTHORED", "start_id": 1, "end_id": 10, "properties": {}},
+                "p": {"id": 10, "labels": ["Post"], "properties": {"title": "first"}},
+                "cnt": 2,
+            }),
+            json!({
+                "u": user,
+                "r": {"type": "AUTHORED", "start_id": 1, "end_id": 11, "properties": {}},
+                "p": {"id": 11, "labels": ["Post"], "properties": {"title": "second"}},
+                "cnt": 2,
+            }),
+        ];
+
+        let graph = build_graph_response(&rows, &create_test_graph_schema());
+
+        let mut node_ids: Vec<&str> = graph["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["id"].as_str().unwrap())
+            .collect();
+        node_ids.sort();
+        assert_eq!(node_ids, vec!["Post:10", "Post:11", "User:1"]);
+
+        let edges = graph["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), 2);
+        assert!(
+            edges[0]["id"]
+                .as_str()
+                .unwrap()
+                .starts_with("AUTHORED:User:1:Post:10:")
+        );
+        assert_eq!(edges[0]["source"], "User:1");
+        assert_eq!(edges[1]["target"], "Post:11");
+    }
+
+    #[test]
+    fn test_build_graph_response_keeps_parallel_edges() {
+        let edge = |since: u64| json!({"type": "AUTHORED", "start_id": 1, "end_id": 10, "properties": {"since": since}});
+        let rows = vec![
+            json!({"r": edge(2020)}),
+            json!({"r": edge(2021)}),
+            json!({"r": edge(2020)}),
+        ];
+
+        let graph = build_graph_response(&rows, &create_test_graph_schema());
+
+        let edges = graph["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), 2);
+        assert_ne!(edges[0]["id"], edges[1]["id"]);
+        assert_eq!(edges[0]["properties"]["since"], 2020);
+        assert_eq!(edges[1]["properties"]["since"], 2021);
+    }
+
+    #[test]
+    fn test_build_graph_response_with_paths() {
+        // a path is returned as a tuple of nodes and relationships.
+        let rows = vec![json!({
+            "p": [
+                [
+                    {"id": "a", "labels": ["User"], "properties": {}},
+                    {"id": "b", "labels": ["Post"], "properties": {}}
+                ],
+                [{"type": "AUTHORED", "start_id": "a", "end_id": "b", "properties": {}}]
+            ]
+        })];
+
+        let graph = build_graph_response(&rows, &create_test_graph_schema());
+
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(graph["edges"][0]["source"], "User:a");
+        assert_eq!(graph["edges"][0]["target"], "Post:b");
+    }
+}
@@ -1,93 +1,238 @@
-use std::{sync::Arc, time::Instant};
+use std::{collections::HashMap, future::Future, sync::Arc, time::Instant};
 
 use axum::{
-    Json,
-    extract::State,
-    http::{HeaderValue, StatusCode, header},
+    Extension, Json,
+    body::{Body, Bytes},
+    extract::{Path, State},
+    http::{HeaderName, HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
-use clickhouse::Client;
-use serde_json::Value;
-use tokio::io::AsyncBufReadExt;
+use clickhouse::{Client, query::BytesCursor};
+use futures_util::{StreamExt, stream};
+use serde_json::{Map, Value};
+use tokio::io::{AsyncBufReadExt, Lines};
+use tracing::{Instrument, debug, debug_span};
+use uuid::Uuid;
 
 use crate::{
     clickhouse_query_generator,
-    graph_catalog::graph_schema::GraphSchemaElement,
-    open_cypher_parser::{self},
+    graph_catalog::{
+        graph_permissions::GraphPermissions,
+        graph_schema::{GraphSchema, GraphSchemaElement},
+    },
+    open_cypher_parser::{
+        self,
+        ast::{OpenCypherQueryAst, QueryPrefix},
+    },
     query_planner::{self, types::QueryType},
-    render_plan::plan_builder::RenderPlanBuilder,
+    render_plan::{
+        plan_builder::RenderPlanBuilder,
+        render_expr::{Literal, RenderExpr},
+    },
 };
 
 use super::{
-    AppState, graph_catalog,
-    models::{OutputFormat, QueryRequest},
+    AppState,
+    auth::Identity,
+    clickhouse_client,
+    clickhouse_pool::ClickHousePool,
+    errors::{BrahmandError, ErrorStage},
+    explain, graph_catalog, graph_output, logging,
+    metrics::METRICS,
+    models::{CancelQueryResponse, OutputFormat, QueryRequest},
+    query_limits::QueryLimits,
+    query_log::QueryLogRecord,
+    query_registry::{RunningQuery, RunningQueryGuard},
 };
 
+const QUERY_ID_HEADER: HeaderName = HeaderName::from_static("x-query-id");
+
 pub async fn query_handler(
     State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
     Json(payload): Json<QueryRequest>,
-) -> Result<impl IntoResponse, impl IntoResponse> {
+) -> Result<Response, BrahmandError> {
+    let query_log = app_state.start_query_log(&identity, &payload.query);
+    let result = run_query(&app_state, &identity, payload, &query_log).await;
+    if let Err(e) = &result {
+        query_log.set_error(e);
+    }
+    result
+}
+
+async fn run_query(
+    app_state: &AppState,
+    identity: &Identity,
+    payload: QueryRequest,
+    query_log: &QueryLogRecord,
+) -> Result<Response, BrahmandError> {
     let instant = Instant::now();
-    let output_format = payload.format.unwrap_or(OutputFormat::JSONEachRow);
+    let output_format = payload
+        .format
+        .unwrap_or_else(|| app_state.default_format.clone());
+    let clickhouse_client = clickhouse_client::with_request_settings(
+        app_state
+            .authenticator
+            .clickhouse_client(&app_state.clickhouse_client, identity)?,
+        &payload.settings,
+        &app_state.clickhouse_settings,
+    )?;
+    let permissions = app_state.graph_permissions(identity);
 
     let (ch_sql_queries, maybe_schema_elem, is_read) = {
         let graph_schema = graph_catalog::get_graph_schema().await;
 
-        let cypher_ast = open_cypher_parser::parse_query(&payload.query).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Brahmand Error: {}", e),
-            )
-        })?;
+        let cypher_ast = parse_query(&payload.query)?;
+
+        if let Some(query_prefix) = cypher_ast.query_prefix {
+            let explained_query = explain::plan_query(
+                cypher_ast,
+                &graph_schema,
+                &app_state.query_limits,
+                &payload.query,
+                permissions.as_ref(),
+            )?;
+            drop(graph_schema);
+            return match query_prefix {
+                QueryPrefix::Explain => {
+                    explain::explain_handler(clickhouse_client, explained_query).await
+                }
+                QueryPrefix::Profile => {
+                    query_log.set_sql(explained_query.sql());
+                    run_read_query(
+                        app_state,
+                        identity,
+                        payload.query_id,
+                        payload.query,
+                        query_log,
+                        clickhouse_client,
+                        |clickhouse_client, running_query| {
+                            explain::profile_handler(
+                                &app_state.clickhouse_pool,
+                                clickhouse_client,
+                                running_query,
+                                explained_query,
+                            )
+                        },
+                    )
+                    .await
+                }
+            };
+        }
 
         let query_type = query_planner::get_query_type(&cypher_ast);
+        METRICS.record_query(&query_type);
 
         let is_read = query_type == QueryType::Read;
 
         if is_read {
-            let logical_plan = query_planner::evaluate_read_query(cypher_ast, &graph_schema)
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Brahmand Error: {}", e),
-                    )
-                })?;
-
-            let render_plan = logical_plan.to_render_plan().map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Brahmand Error: {}", e),
-                )
-            })?;
-            let ch_query = clickhouse_query_generator::generate_sql(render_plan);
-            println!("\n ch_query \n {} \n", ch_query);
+            let ch_query = generate_read_sql(
+                cypher_ast,
+                &graph_schema,
+                &app_state.query_limits,
+                &payload.query,
+                &HashMap::new(),
+                permissions.as_ref(),
+            )?;
+            query_log.set_sql(&ch_query);
             (vec![ch_query], None, true)
         } else {
+            if !app_state.can_run_ddl(identity) {
+                return Err(BrahmandError::Forbidden(
+                    "Only the admin role and roles with the ddl permission can change the graph schema."
+                        .to_string(),
+                ));
+            }
             let (queries, schema_elem) =
-                clickhouse_query_generator::generate_ddl_query(cypher_ast, &graph_schema).map_err(
-                    |e| {
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            format!("Brahmand Error: {}", e),
-                        )
-                    },
-                )?;
+                clickhouse_query_generator::generate_ddl_query(cypher_ast, &graph_schema)?;
+            query_log.set_sql(&queries.join(";\n"));
             (queries, Some(schema_elem), false)
         }
     };
 
     if is_read {
-        execute_cte_queries(app_state, ch_sql_queries, output_format, instant).await
-    } else {
-        ddl_handler(
-            app_state.clickhouse_client.clone(),
-            ch_sql_queries,
-            maybe_schema_elem,
+        run_read_query(
+            app_state,
+            identity,
+            payload.query_id,
+            payload.query,
+            query_log,
+            clickhouse_client,
+            |clickhouse_client, running_query| {
+                execute_cte_queries(
+                    &app_state.clickhouse_pool,
+                    clickhouse_client,
+                    running_query,
+                    ch_sql_queries,
+                    output_format,
+                    instant,
+                )
+            },
         )
         .await
+    } else {
+        ddl_handler(clickhouse_client, ch_sql_queries, maybe_schema_elem).await
     }
 }
 
+// Registers the read so that it can be listed and cancelled and runs `execute` until it
+// finishes or times out.
+async fn run_read_query<F, Fut>(
+    app_state: &AppState,
+    identity: &Identity,
+    tag: Option<String>,
+    query: String,
+    query_log: &QueryLogRecord,
+    clickhouse_client: Client,
+    execute: F,
+) -> Result<Response, BrahmandError>
+where
+    F: FnOnce(Client, RunningQueryGuard) -> Fut,
+    Fut: Future<Output = Result<Response, BrahmandError>>,
+{
+    let running_query = register_read(
+        app_state,
+        identity,
+        tag,
+        query,
+        query_log,
+        &clickhouse_client,
+    )?;
+    let query_id = running_query.query_id().to_string();
+
+    // Dropping the running query before it finishes, e.g. on client disconnect or timeout,
+    // kills it on ClickHouse. The timeout of the registry also covers the streamed body.
+    let timed_out = running_query.timed_out();
+    let execution = execute(clickhouse_client, running_query)
+        .instrument(debug_span!("execute", query_id = %query_id));
+    tokio::select! {
+        response = execution => response,
+        _ = timed_out => Err(BrahmandError::Clickhouse(clickhouse::error::Error::TimedOut)),
+    }
+}
+
+// Every read gets a query_id of its own on ClickHouse, the one chosen by the client is kept as a
+// tag to find the query by.
+fn register_read(
+    app_state: &AppState,
+    identity: &Identity,
+    tag: Option<String>,
+    query: String,
+    query_log: &QueryLogRecord,
+    clickhouse_client: &Client,
+) -> Result<RunningQueryGuard, BrahmandError> {
+    let mut running_query = app_state.query_registry.register(
+        Uuid::new_v4().to_string(),
+        tag,
+        query,
+        identity.user.clone(),
+        clickhouse_client.clone(),
+        app_state.query_limits.query_timeout,
+    )?;
+    running_query.set_query_log(query_log.clone());
+    Ok(running_query)
+}
+
 // pub async fn query_handler_old(
 //     State(app_state): State<Arc<AppState>>,
 //     Json(payload): Json<QueryRequest>,
@@ -142,82 +287,369 @@ pub async fn query_handler(
 //     }
 // }
 
+pub fn generate_read_sql(
+    cypher_ast: OpenCypherQueryAst,
+    graph_schema: &GraphSchema,
+    query_limits: &QueryLimits,
+    query: &str,
+    parameters: &HashMap<String, RenderExpr>,
+    permissions: Option<&GraphPermissions>,
+) -> Result<String, BrahmandError> {
+    let plan_started_at = Instant::now();
+    let logical_plan = debug_span!("plan").in_scope(|| {
+        let logical_plan =
+            query_planner::evaluate_read_query(cypher_ast, graph_schema, permissions)
+                .map_err(|e| BrahmandError::from(e).locate(query))?;
+        query_limits.check_logical_plan(&logical_plan)?;
+        Ok::<_, BrahmandError>(logical_plan)
+    })?;
+    METRICS.observe_stage(ErrorStage::Plan, plan_started_at);
+
+    let render_started_at = Instant::now();
+    let ch_query = debug_span!("render").in_scope(|| {
+        let mut render_plan = logical_plan.to_render_plan()?;
+        query_limits.apply_to_render_plan(&mut render_plan)?;
+        render_plan.bind_parameters(parameters)?;
+        let ch_query = clickhouse_query_generator::generate_sql(render_plan);
+        debug!(sql = %logging::sql_for_log(&ch_query), "Generated SQL");
+        Ok::<_, BrahmandError>(ch_query)
+    })?;
+    METRICS.observe_stage(ErrorStage::Render, render_started_at);
+    Ok(ch_query)
+}
+
+fn parse_query(query: &str) -> Result<OpenCypherQueryAst<'_>, BrahmandError> {
+    let parse_started_at = Instant::now();
+    let cypher_ast = debug_span!("parse")
+        .in_scope(|| open_cypher_parser::parse_query(query))
+        .map_err(|e| BrahmandError::from_parsing_error(query, e))?;
+    METRICS.observe_stage(ErrorStage::Parse, parse_started_at);
+    Ok(cypher_ast)
+}
+
+// Used where only read queries can be run, e.g. jobs and Bolt.
+pub fn generate_read_only_sql(
+    query: &str,
+    graph_schema: &GraphSchema,
+    query_limits: &QueryLimits,
+    parameters: &HashMap<String, RenderExpr>,
+    permissions: Option<&GraphPermissions>,
+) -> Result<String, BrahmandError> {
+    let cypher_ast = parse_query(query)?;
+
+    let query_type = query_planner::get_query_type(&cypher_ast);
+    METRICS.record_query(&query_type);
+    if cypher_ast.query_prefix.is_some() || query_type != QueryType::Read {
+        return Err(BrahmandError::Unsupported(
+            "Only read queries are supported.".to_string(),
+        ));
+    }
+
+    generate_read_sql(
+        cypher_ast,
+        graph_schema,
+        query_limits,
+        query,
+        parameters,
+        permissions,
+    )
+}
+
+// Rows are JSON arrays in the order of the returned columns. Named tuples stay objects so node
+// and relationship entities can be recognised, and 64 bit integers are not quoted.
+pub async fn fetch_rows_with_columns(
+    clickhouse_client: Client,
+    ch_query: &str,
+) -> Result<(Vec<String>, Lines<BytesCursor>), BrahmandError> {
+    let mut lines = clickhouse_client
+        .with_option("output_format_json_quote_64bit_integers", "0")
+        .with_option("output_format_json_named_tuples_as_objects", "1")
+        .query(ch_query)
+        .fetch_bytes("JSONCompactEachRowWithNames")?
+        .lines();
+    // the first line has the column names
+    let columns: Vec<String> = match lines.next_line().await? {
+        Some(line) => serde_json::from_str(&line)?,
+        None => vec![],
+    };
+    Ok((columns, lines))
+}
+
+// Rows of a read started by `start_read`, JSON arrays in the order of `columns`.
+pub struct ReadResult {
+    pub columns: Vec<String>,
+    pub graph_schema: GraphSchema,
+    lines: Lines<BytesCursor>,
+    running_query: RunningQueryGuard,
+}
+
+impl ReadResult {
+    pub async fn next_row(&mut self) -> Result<Option<Vec<Value>>, BrahmandError> {
+        let timed_out = self.running_query.timed_out();
+        let line = tokio::select! {
+            line = self.lines.next_line() => line?,
+            _ = timed_out => {
+                return Err(BrahmandError::Clickhouse(clickhouse::error::Error::TimedOut));
+            }
+        };
+        let Some(line) = line else {
+            return Ok(None);
+        };
+        self.running_query.query_log().add_rows(1);
+        Ok(Some(serde_json::from_str(&line)?))
+    }
+
+    pub fn query_log(&self) -> &QueryLogRecord {
+        self.running_query.query_log()
+    }
+
+    // Resolves once the query ran out of time.
+    pub fn timed_out(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.running_query.timed_out()
+    }
+
+    pub fn finish(self) {
+        self.running_query.finish();
+    }
+}
+
+// Reads of the Bolt and Neo4j HTTP endpoints. Parameters are bound as literals and the read is
+// registered like the ones of /query, so it is logged, times out and can be cancelled the same way.
+pub async fn start_read(
+    app_state: &AppState,
+    identity: &Identity,
+    query: String,
+    parameters: &Map<String, Value>,
+    query_log: &QueryLogRecord,
+) -> Result<ReadResult, BrahmandError> {
+    let parameters = parameters
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), parameter_to_render_expr(name, value)?)))
+        .collect::<Result<HashMap<_, _>, BrahmandError>>()?;
+
+    let graph_schema = graph_catalog::get_graph_schema().await;
+    let ch_query = generate_read_only_sql(
+        &query,
+        &graph_schema,
+        &app_state.query_limits,
+        &parameters,
+        app_state.graph_permissions(identity).as_ref(),
+    )?;
+    query_log.set_sql(&ch_query);
+
+    let clickhouse_client = app_state
+        .authenticator
+        .clickhouse_client(&app_state.clickhouse_client, identity)?;
+    let mut running_query = register_read(
+        app_state,
+        identity,
+        None,
+        query,
+        query_log,
+        &clickhouse_client,
+    )?;
+    let query_id = running_query.query_id().to_string();
+    let timed_out = running_query.timed_out();
+    let read = app_state
+        .clickhouse_pool
+        .read(
+            &clickhouse_client,
+            &mut running_query,
+            |clickhouse_client| fetch_rows_with_columns(clickhouse_client, &ch_query),
+        )
+        .instrument(debug_span!("execute", query_id = %query_id));
+    let (columns, lines) = tokio::select! {
+        read = read => read?,
+        _ = timed_out => {
+            return Err(BrahmandError::Clickhouse(clickhouse::error::Error::TimedOut));
+        }
+    };
+
+    Ok(ReadResult {
+        columns,
+        graph_schema,
+        lines,
+        running_query,
+    })
+}
+
+fn parameter_to_render_expr(name: &str, value: &Value) -> Result<RenderExpr, BrahmandError> {
+    let expr = match value {
+        Value::Null => RenderExpr::Literal(Literal::Null),
+        Value::Bool(b) => RenderExpr::Literal(Literal::Boolean(*b)),
+        Value::Number(number) => match number.as_i64() {
+            Some(i) => RenderExpr::Literal(Literal::Integer(i)),
+            None => RenderExpr::Literal(Literal::Float(number.as_f64().unwrap_or_default())),
+        },
+        Value::String(s) => RenderExpr::Literal(Literal::String(s.clone())),
+        Value::Array(items) => RenderExpr::List(
+            items
+                .iter()
+                .map(|item| parameter_to_render_expr(name, item))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Value::Object(_) => return Err(unsupported_parameter(name)),
+    };
+    Ok(expr)
+}
+
+pub fn unsupported_parameter(name: &str) -> BrahmandError {
+    BrahmandError::InvalidRequest(format!(
+        "Unsupported value for parameter `${}`, only scalars and lists are supported.",
+        name
+    ))
+}
+
+pub async fn cancel_query_handler(
+    State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
+    Path(id): Path<String>,
+) -> Result<Json<CancelQueryResponse>, BrahmandError> {
+    // queries of other users are reported as not running
+    let Some(running_query) = app_state
+        .query_registry
+        .find(&id)
+        .filter(|running_query| app_state.can_access(&identity, &running_query.user))
+    else {
+        return Err(BrahmandError::QueryNotFound(id));
+    };
+    let query_id = running_query.query_id;
+    app_state
+        .query_registry
+        .kill(&app_state.clickhouse_client, &query_id)
+        .await?;
+    Ok(Json(CancelQueryResponse {
+        query_id,
+        cancelled: true,
+    }))
+}
+
+pub async fn running_queries_handler(
+    State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
+) -> Json<Vec<RunningQuery>> {
+    let running_queries = app_state
+        .query_registry
+        .get_running_queries()
+        .into_iter()
+        .filter(|running_query| app_state.can_access(&identity, &running_query.user))
+        .collect();
+    Json(running_queries)
+}
+
 async fn execute_cte_queries(
-    app_state: Arc<AppState>,
+    clickhouse_pool: &ClickHousePool,
+    clickhouse_client: Client,
+    mut running_query: RunningQueryGuard,
     ch_sql_queries: Vec<String>,
     output_format: OutputFormat,
     instant: Instant,
-) -> Result<Response, (StatusCode, String)> {
+) -> Result<Response, BrahmandError> {
     let ch_query_string = ch_sql_queries.join(" ");
-
-    if output_format == OutputFormat::Pretty
-        || output_format == OutputFormat::PrettyCompact
-        || output_format == OutputFormat::Csv
-        || output_format == OutputFormat::CSVWithNames
-    {
-        let mut lines = app_state
-            .clickhouse_client
-            .clone()
-            .query(&ch_query_string)
-            .fetch_bytes(output_format)
-            .map_err(|e| {
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Clickhouse Error: {}", e),
-                )
-            })?
-            .lines();
-
-        let mut rows: Vec<String> = vec![];
-        while let Some(line) = lines.next_line().await.map_err(|e| {
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Clickhouse Error: {}", e),
+    let query_id_header = HeaderValue::from_str(running_query.query_id())
+        .map_err(|_| BrahmandError::InvalidRequest("Invalid query_id.".to_string()))?;
+
+    if output_format == OutputFormat::Graph {
+        // nodes and edges are de-duplicated across rows so this format needs the whole result,
+        // which also lets a read be retried on another replica until its last row
+        let rows = clickhouse_pool
+            .read(
+                &clickhouse_client,
+                &mut running_query,
+                |clickhouse_client| {
+                    let query = clickhouse_client.query(&ch_query_string);
+                    async move {
+                        let mut lines = query.fetch_bytes(OutputFormat::Graph)?.lines();
+                        let mut rows: Vec<Value> = vec![];
+                        while let Some(line) = lines.next_line().await? {
+                            let value: Value = serde_json::from_str(&line)?;
+                            rows.push(value);
+                        }
+                        Ok(rows)
+                    }
+                },
             )
-        })? {
-            // let value: serde_json::Value = serde_json::de::from_str(&line).unwrap();
-            rows.push(line);
-        }
-
-        let now = Instant::now();
-        let elapsed = now.duration_since(instant).as_secs_f64();
-        let elapsed_rounded = (elapsed * 1000.0).round() / 1000.0;
-        rows.push(format!("\nElapsed: {} sec", elapsed_rounded));
+            .await?;
 
-        let text = rows.join("\n");
+        running_query.query_log().add_rows(rows.len() as u64);
+        running_query.finish();
 
-        let mut response = (StatusCode::OK, text).into_response();
+        let graph_schema = graph_catalog::get_graph_schema().await;
+        let mut response =
+            Json(graph_output::build_graph_response(&rows, &graph_schema)).into_response();
         response
             .headers_mut()
-            .insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
-        Ok(response)
-    } else {
-        let mut lines = app_state
-            .clickhouse_client
-            .clone()
-            .query(&ch_query_string)
-            .fetch_bytes("JSONEachRow")
-            .map_err(|e| {
-                (
-                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Clickhouse Error: {}", e),
-                )
-            })?
-            .lines();
-
-        let mut rows: Vec<Value> = vec![];
-        while let Some(line) = lines.next_line().await.map_err(|e| {
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Clickhouse Error: {}", e),
-            )
-        })? {
-            let value: serde_json::Value = serde_json::de::from_str(&line).unwrap();
-            rows.push(value);
+            .insert(QUERY_ID_HEADER, query_id_header);
+        return Ok(response);
+    }
+
+    // Wait for the first chunk so that query errors are still reported with an error status
+    // instead of a broken 200 response. Until then the read can move to another replica.
+    let (cursor, first_chunk) = clickhouse_pool
+        .read(
+            &clickhouse_client,
+            &mut running_query,
+            |clickhouse_client| {
+                let query = clickhouse_client.query(&ch_query_string);
+                let output_format = output_format.clone();
+                async move {
+                    let mut cursor = query.fetch_bytes(output_format)?;
+                    let first_chunk = cursor.next().await?;
+                    Ok((cursor, first_chunk))
+                }
+            },
+        )
+        .await?;
+
+    // Rows are counted from the line based formats, the Pretty ones are not counted.
+    let query_log = running_query.query_log().clone();
+    let mut header_lines = u64::from(output_format == OutputFormat::CSVWithNames);
+    let count_rows =
+        output_format != OutputFormat::Pretty && output_format != OutputFormat::PrettyCompact;
+    let count_lines = move |chunk: &Result<Bytes, clickhouse::error::Error>| {
+        if let (true, Ok(chunk)) = (count_rows, chunk) {
+            let lines = chunk.iter().filter(|byte| **byte == b'\n').count() as u64;
+            let header = header_lines.min(lines);
+            header_lines -= header;
+            query_log.add_rows(lines - header);
         }
+    };
 
-        Ok(Json(rows).into_response())
-    }
+    // Rest of the output is streamed as it arrives from Clickhouse. The body is only polled
+    // when the client is ready for more data, so rows are never buffered in memory.
+    // The query is only finished once the whole body is sent. If the client goes away earlier
+    // the body is dropped along with the running query, which kills it.
+    let chunks = running_query
+        .abort_on_timeout(stream::iter(first_chunk.map(Ok)).chain(cursor))
+        .inspect(count_lines);
+    let finished = stream::once(async move { running_query.finish() })
+        .filter_map(|_| async { None::<Result<Bytes, clickhouse::error::Error>> });
+
+    let (body, content_type) = if output_format == OutputFormat::JSONEachRow {
+        (
+            Body::from_stream(chunks.chain(finished)),
+            "application/x-ndjson",
+        )
+    } else {
+        let elapsed_footer = stream::once(async move {
+            let elapsed = Instant::now().duration_since(instant).as_secs_f64();
+            let elapsed_rounded = (elapsed * 1000.0).round() / 1000.0;
+            Ok(Bytes::from(format!("\nElapsed: {} sec", elapsed_rounded)))
+        });
+        (
+            Body::from_stream(chunks.chain(elapsed_footer).chain(finished)),
+            "text/plain",
+        )
+    };
+
+    let mut response = (StatusCode::OK, body).into_response();
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+        .headers_mut()
+        .insert(QUERY_ID_HEADER, query_id_header);
+    Ok(response)
 }
 
 // async fn execute_temp_table_queries(
@@ -311,7 +743,7 @@ pub async fn ddl_handler(
     clickhouse_client: Client,
     ch_sql_queries: Vec<String>,
     graph_schema_element_opt: Option<Vec<GraphSchemaElement>>,
-) -> Result<Response, (StatusCode, String)> {
+) -> Result<Response, BrahmandError> {
     // // parse cypher query
     // let cypher_ast = open_cypher_parser::parse_query(&payload.query).map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
@@ -319,48 +751,26 @@ pub async fn ddl_handler(
 
     let graph_schema_element: Vec<GraphSchemaElement> = graph_schema_element_opt.unwrap();
 
-    graph_catalog::validate_schema(&graph_schema_element)
-        .await
-        .map_err(|e| {
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Brahmand Error: {}", e),
-            )
-        })?;
+    graph_catalog::validate_schema(&graph_schema_element).await?;
 
     for ch_query in ch_sql_queries {
-        println!("\n ch_query -> {:?}", ch_query);
+        debug!(sql = %logging::sql_for_log(&ch_query), "Running DDL");
         let ch_client = clickhouse_client
             .clone()
             .with_option("wait_end_of_query", "1");
 
-        ch_client.query(&ch_query).execute().await.map_err(|e| {
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Clickhouse Error: {}", e),
-            )
-        })?;
+        ch_client.query(&ch_query).execute().await?;
     }
 
     // Now that DDL is applied successfully, add graph schema element into the schema and update the graph meta table here
 
     graph_catalog::add_to_schema(clickhouse_client.clone(), graph_schema_element)
         .await
-        .map_err(|e| {
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Clickhouse Error: {}", e),
-            )
-        })?;
+        .map_err(BrahmandError::Catalog)?;
 
     graph_catalog::refresh_global_schema(clickhouse_client)
         .await
-        .map_err(|e| {
-            (
-                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Clickhouse Error: {}", e),
-            )
-        })?;
+        .map_err(BrahmandError::Catalog)?;
 
     let mut response = (StatusCode::OK, "DDL applied successfully".to_string()).into_response();
     response
@@ -370,3 +780,121 @@ pub async fn ddl_handler(
     // println!("IN DDL HANDLER GLOBAL_GRAPH_SCHEMA {:?}",GLOBAL_GRAPH_SCHEMA.get());
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clickhouse::test::{Mock, handlers};
+    use serde_json::json;
+
+    use super::*;
+    use crate::server::{config::LoadBalancing, query_registry::QueryRegistry};
+
+    // Runs a read against the mock and returns the response with its body.
+    async fn execute(
+        mock: &Mock,
+        output_format: OutputFormat,
+    ) -> Result<(Response, String), BrahmandError> {
+        let pool = ClickHousePool::new(mock.url(), &[], LoadBalancing::RoundRobin);
+        let registry = Arc::new(QueryRegistry::default());
+        let running_query = registry
+            .register(
+                "q1".to_string(),
+                None,
+                "".to_string(),
+                "".to_string(),
+                Client::default(),
+                None,
+            )
+            .unwrap();
+        let response = execute_cte_queries(
+            &pool,
+            Client::default(),
+            running_query,
+            vec!["SELECT 1".to_string()],
+            output_format,
+            Instant::now(),
+        )
+        .await?;
+        let (parts, body) = response.into_parts();
+        let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+        assert!(registry.find("q1").is_none());
+        Ok((
+            Response::from_parts(parts, Body::empty()),
+            String::from_utf8(body.to_vec()).unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_streamed_response_format() {
+        let mock = Mock::new();
+        mock.add(handlers::provide(b"{\"a\":1}\n{\"a\":2}\n".to_vec()));
+        let (response, body) = execute(&mock, OutputFormat::JSONEachRow).await.unwrap();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        assert_eq!(response.headers()[QUERY_ID_HEADER], "q1");
+        // one JSON object per line and nothing after the last row
+        assert_eq!(body, "{\"a\":1}\n{\"a\":2}\n");
+
+        mock.add(handlers::provide(b"1\n".to_vec()));
+        let (response, body) = execute(&mock, OutputFormat::Pretty).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+        assert!(body.starts_with("1\n\nElapsed: "));
+        assert!(body.ends_with(" sec"));
+    }
+
+    #[tokio::test]
+    async fn test_error_before_first_chunk() {
+        let mock = Mock::new();
+        mock.add(handlers::failure(clickhouse::test::status::BAD_REQUEST));
+        assert!(matches!(
+            execute(&mock, OutputFormat::JSONEachRow).await,
+            Err(BrahmandError::Clickhouse(_))
+        ));
+        // ClickHouse did not run the query, a KILL would be an unexpected request to the mock
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn test_timeout_cuts_off_streamed_body() {
+        let registry = Arc::new(QueryRegistry::default());
+        let running_query = registry
+            .register(
+                "q1".to_string(),
+                None,
+                "".to_string(),
+                "".to_string(),
+                Client::default().with_url("http://127.0.0.1:1"),
+                Some(Duration::from_millis(20)),
+            )
+            .unwrap();
+        // the first chunk arrived but ClickHouse never sends the rest
+        let chunks = stream::iter([Ok(Bytes::from("{\"a\":1}\n"))]).chain(stream::pending());
+        let chunks: Vec<Result<Bytes, clickhouse::error::Error>> =
+            running_query.abort_on_timeout(chunks).collect().await;
+
+        assert_eq!(chunks.len(), 2);
+        assert!(matches!(chunks[1], Err(clickhouse::error::Error::TimedOut)));
+        assert!(registry.find("q1").is_some());
+        drop(running_query);
+        assert!(registry.find("q1").is_none());
+    }
+
+    #[test]
+    fn test_parameters() {
+        assert_eq!(
+            parameter_to_render_expr("ids", &json!([1, 2.5])).unwrap(),
+            RenderExpr::List(vec![
+                RenderExpr::Literal(Literal::Integer(1)),
+                RenderExpr::Literal(Literal::Float(2.5)),
+            ])
+        );
+        assert!(matches!(
+            parameter_to_render_expr("props", &json!({"a": 1})),
+            Err(BrahmandError::InvalidRequest(_))
+        ));
+    }
+}
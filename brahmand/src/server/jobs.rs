@@ -0,0 +1,482 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+};
+use clickhouse::{Client, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{io::AsyncBufReadExt, time::interval};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::{
+    AppState,
+    auth::Identity,
+    clickhouse_client,
+    errors::BrahmandError,
+    graph_catalog, handlers,
+    metrics::METRICS,
+    models::{
+        JobProgress, JobRequest, JobResultsPage, JobResultsResponse, JobStatus, JobStatusResponse,
+        JobSubmitResponse,
+    },
+    query_log::QueryLogRecord,
+    query_registry::RunningQueryGuard,
+};
+
+// Results of a job are written to their own ClickHouse table named after the job id. The job id
+// is also the query_id of the insert, so the job state can be read back from ClickHouse and
+// brahmand keeps nothing in memory. The user who submitted a job, its error or its completion
+// are kept in JOBS_TABLE.
+const RESULTS_TABLE_PREFIX: &str = "brahmand_job_";
+const JOBS_TABLE: &str = "brahmand_jobs";
+const DEFAULT_PAGE_SIZE: u64 = 1000;
+// rows of a page are collected before the response is sent
+const MAX_PAGE_SIZE: u64 = 10_000;
+// TTL only deletes the rows, the expired results tables are dropped on this interval
+const EXPIRED_RESULTS_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+// A job has one row once submitted and a second one when it failed or completed.
+#[derive(Debug, Clone, Default, PartialEq, Row, Serialize, Deserialize)]
+struct JobRecord {
+    job_id: String,
+    user: String,
+    // empty unless the job failed
+    error: String,
+    // set once the results table holds the whole result
+    completed: bool,
+    total_rows: u64,
+}
+
+pub async fn submit_job_handler(
+    State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
+    Json(payload): Json<JobRequest>,
+) -> Result<Json<JobSubmitResponse>, BrahmandError> {
+    let query_log = app_state.start_query_log(&identity, &payload.query);
+    let result = submit_job(&app_state, &identity, payload, query_log.clone()).await;
+    if let Err(e) = &result {
+        query_log.set_error(e);
+    }
+    result
+}
+
+async fn submit_job(
+    app_state: &AppState,
+    identity: &Identity,
+    payload: JobRequest,
+    query_log: QueryLogRecord,
+) -> Result<Json<JobSubmitResponse>, BrahmandError> {
+    // results are read back with the service user, only the job itself runs as the caller
+    let clickhouse_client = clickhouse_client::with_request_settings(
+        app_state
+            .authenticator
+            .clickhouse_client(&app_state.clickhouse_client, identity)?,
+        &payload.settings,
+        &app_state.clickhouse_settings,
+    )?;
+
+    let ch_query = {
+        let graph_schema = graph_catalog::get_graph_schema().await;
+        handlers::generate_read_only_sql(
+            &payload.query,
+            &graph_schema,
+            &app_state.query_limits,
+            &HashMap::new(),
+            app_state.graph_permissions(identity).as_ref(),
+        )?
+    };
+
+    let job_id = Uuid::new_v4();
+    query_log.set_sql(&ch_query);
+    query_log.set_query_id(&job_id.to_string());
+    let create_query = build_create_results_query(
+        &results_table_name(&job_id),
+        &ch_query,
+        app_state.job_results_ttl_hours,
+    );
+
+    let job_record = JobRecord {
+        job_id: job_id.to_string(),
+        user: identity.user.clone(),
+        ..Default::default()
+    };
+    insert_job_record(&app_state.clickhouse_client, &job_record).await?;
+    // Like a read, the job can be listed and cancelled, it times out and it is waited for on
+    // shutdown.
+    let mut running_query = app_state.query_registry.register(
+        job_id.to_string(),
+        None,
+        payload.query,
+        identity.user.clone(),
+        clickhouse_client.clone(),
+        app_state.query_limits.query_timeout,
+    )?;
+    running_query.set_query_log(query_log);
+    // Not tied to the request, the job keeps running after the response is sent.
+    tokio::spawn(run_job(
+        app_state.clickhouse_client.clone(),
+        clickhouse_client,
+        running_query,
+        job_id,
+        job_record,
+        create_query,
+    ));
+
+    Ok(Json(JobSubmitResponse {
+        job_id: job_id.to_string(),
+    }))
+}
+
+pub async fn job_status_handler(
+    State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobStatusResponse>, BrahmandError> {
+    let job_id = parse_job_id(&job_id)?;
+    let job_record = get_job_record(&app_state, &identity, &job_id).await?;
+    Ok(Json(
+        get_job_status(&app_state, &job_id, &job_record).await?,
+    ))
+}
+
+pub async fn job_results_handler(
+    State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
+    Path(job_id): Path<String>,
+    Query(page): Query<JobResultsPage>,
+) -> Result<Json<JobResultsResponse>, BrahmandError> {
+    let job_id = parse_job_id(&job_id)?;
+    let clickhouse_client = &app_state.clickhouse_client;
+
+    let job_record = get_job_record(&app_state, &identity, &job_id).await?;
+    if !job_record.completed {
+        return Err(BrahmandError::JobNotCompleted(job_id.to_string()));
+    }
+    let total_rows = job_record.total_rows;
+
+    let offset = page.offset.unwrap_or(0);
+    let limit = page_limit(&page)?;
+    let mut lines = clickhouse_client
+        .query(&format!(
+            "SELECT * EXCEPT (_row, _created_at) FROM {} WHERE _row >= ? ORDER BY _row LIMIT ?",
+            results_table_name(&job_id)
+        ))
+        .bind(offset)
+        .bind(limit)
+        .fetch_bytes("JSONEachRow")?
+        .lines();
+    let mut rows: Vec<Value> = vec![];
+    while let Some(line) = lines.next_line().await? {
+        rows.push(serde_json::from_str(&line)?);
+    }
+
+    Ok(Json(JobResultsResponse {
+        job_id: job_id.to_string(),
+        offset,
+        limit,
+        total_rows,
+        rows,
+    }))
+}
+
+// The job is logged once it finished, with the rows of its results table. `clickhouse_client`
+// runs the job as the caller, `service_client` records the job.
+async fn run_job(
+    service_client: Client,
+    clickhouse_client: Client,
+    running_query: RunningQueryGuard,
+    job_id: Uuid,
+    job_record: JobRecord,
+    create_query: String,
+) {
+    let timed_out = running_query.timed_out();
+    let create = clickhouse_client
+        .clone()
+        .with_option("query_id", job_id.to_string())
+        .with_option("wait_end_of_query", "1")
+        .query(&create_query)
+        .execute();
+    // on timeout the query is also killed by the registry
+    let result = tokio::select! {
+        result = create => result,
+        _ = timed_out => Err(clickhouse::error::Error::TimedOut),
+    };
+    let query_log = running_query.query_log().clone();
+
+    if let Err(e) = result {
+        error!("Job {} failed: {}", job_id, e);
+        let e = BrahmandError::from(e);
+        METRICS.record_error(&e);
+        query_log.set_error(&e);
+        let failed_record = JobRecord {
+            error: e.to_string(),
+            ..job_record.clone()
+        };
+        if let Err(e) = insert_job_record(&service_client, &failed_record).await {
+            error!("Unable to record the error of job {}: {}", job_id, e);
+        }
+        // A failed insert can leave a partially filled table behind which would look like a
+        // completed job.
+        let drop_query = format!("DROP TABLE IF EXISTS {}", results_table_name(&job_id));
+        if let Err(e) = clickhouse_client.query(&drop_query).execute().await {
+            error!("Unable to drop results of job {}: {}", job_id, e);
+        }
+        return;
+    }
+
+    // The job is only completed once it is recorded, a results table without the record was
+    // left behind by a brahmand which stopped during the insert. The job stays registered until
+    // then so that it is not reported as interrupted in between.
+    let count_query = format!("SELECT count() FROM {}", results_table_name(&job_id));
+    let completed = async {
+        let total_rows = service_client
+            .query(&count_query)
+            .fetch_one::<u64>()
+            .await?;
+        query_log.add_rows(total_rows);
+        let completed_record = JobRecord {
+            completed: true,
+            total_rows,
+            ..job_record
+        };
+        insert_job_record(&service_client, &completed_record).await
+    };
+    if let Err(e) = completed.await {
+        error!("Unable to record the completion of job {}: {}", job_id, e);
+    }
+    running_query.finish();
+}
+
+// Jobs of other users are reported as not found.
+async fn get_job_record(
+    app_state: &AppState,
+    identity: &Identity,
+    job_id: &Uuid,
+) -> Result<JobRecord, BrahmandError> {
+    let job_record = app_state
+        .clickhouse_client
+        .query(&format!(
+            "SELECT job_id, any(user) AS user, max(error) AS error, max(completed) AS completed, \
+             max(total_rows) AS total_rows FROM {JOBS_TABLE} WHERE job_id = ? GROUP BY job_id"
+        ))
+        .bind(job_id.to_string())
+        .fetch_optional::<JobRecord>()
+        .await?;
+    match job_record {
+        Some(job_record) if app_state.can_access(identity, &job_record.user) => Ok(job_record),
+        _ => Err(BrahmandError::JobNotFound(job_id.to_string())),
+    }
+}
+
+async fn get_job_status(
+    app_state: &AppState,
+    job_id: &Uuid,
+    job_record: &JobRecord,
+) -> Result<JobStatusResponse, BrahmandError> {
+    let clickhouse_client = &app_state.clickhouse_client;
+    let status = |status, progress, total_rows, error| JobStatusResponse {
+        job_id: job_id.to_string(),
+        status,
+        progress,
+        total_rows,
+        error,
+    };
+
+    if !job_record.error.is_empty() {
+        return Ok(status(
+            JobStatus::Failed,
+            None,
+            None,
+            Some(job_record.error.clone()),
+        ));
+    }
+
+    let progress = clickhouse_client
+        .query(
+            "SELECT read_rows, total_rows_approx, elapsed FROM system.processes \
+             WHERE query_id = ? LIMIT 1",
+        )
+        .bind(&job_record.job_id)
+        .fetch_optional::<JobProgress>()
+        .await?;
+    // the job is registered a moment before its query shows up in system.processes
+    if progress.is_some() || app_state.query_registry.is_running(&job_record.job_id) {
+        return Ok(status(JobStatus::Running, progress, None, None));
+    }
+
+    if job_record.completed {
+        return Ok(status(
+            JobStatus::Completed,
+            None,
+            Some(job_record.total_rows),
+            None,
+        ));
+    }
+
+    // brahmand stopped before the job finished
+    Ok(status(
+        JobStatus::Failed,
+        None,
+        None,
+        Some("Job was interrupted before it completed.".to_string()),
+    ))
+}
+
+pub async fn create_jobs_table(
+    clickhouse_client: &Client,
+    ttl_hours: u64,
+) -> Result<(), clickhouse::error::Error> {
+    let create_table_query = format!(
+        "CREATE TABLE IF NOT EXISTS {JOBS_TABLE} (
+            job_id String,
+            user String,
+            error String,
+            completed Bool,
+            total_rows UInt64,
+            created_at DateTime DEFAULT now()
+        ) ENGINE = MergeTree()
+        ORDER BY job_id
+        TTL created_at + INTERVAL {ttl_hours} HOUR"
+    );
+    clickhouse_client.query(&create_table_query).execute().await
+}
+
+async fn insert_job_record(
+    clickhouse_client: &Client,
+    job_record: &JobRecord,
+) -> Result<(), clickhouse::error::Error> {
+    let mut insert = clickhouse_client.insert(JOBS_TABLE)?;
+    insert.write(job_record).await?;
+    insert.end().await
+}
+
+// Drops the results tables older than the TTL on every tick.
+pub async fn drop_expired_results(clickhouse_client: Client, ttl_hours: u64) {
+    let mut ticker = interval(EXPIRED_RESULTS_CHECK_INTERVAL);
+    loop {
+        ticker.tick().await;
+        match drop_expired_results_tables(&clickhouse_client, ttl_hours).await {
+            Ok(0) => {}
+            Ok(dropped) => info!("Dropped the results of {} expired jobs", dropped),
+            Err(e) => warn!("Unable to drop the results of expired jobs: {}", e),
+        }
+    }
+}
+
+async fn drop_expired_results_tables(
+    clickhouse_client: &Client,
+    ttl_hours: u64,
+) -> Result<usize, clickhouse::error::Error> {
+    // the name pattern only matches results tables, whose names are safe to use as is
+    let tables = clickhouse_client
+        .query(
+            "SELECT name FROM system.tables WHERE database = currentDatabase() \
+             AND match(name, ?) AND metadata_modification_time < now() - INTERVAL ? HOUR",
+        )
+        .bind(format!("^{RESULTS_TABLE_PREFIX}[0-9a-f]{{32}}$"))
+        .bind(ttl_hours)
+        .fetch_all::<String>()
+        .await?;
+    for table in &tables {
+        clickhouse_client
+            .query(&format!("DROP TABLE IF EXISTS {}", table))
+            .execute()
+            .await?;
+    }
+    Ok(tables.len())
+}
+
+fn page_limit(page: &JobResultsPage) -> Result<u64, BrahmandError> {
+    match page.limit {
+        Some(0) => Err(BrahmandError::InvalidRequest(
+            "limit must be greater than 0.".to_string(),
+        )),
+        Some(limit) => Ok(limit.min(MAX_PAGE_SIZE)),
+        None => Ok(DEFAULT_PAGE_SIZE),
+    }
+}
+
+fn parse_job_id(job_id: &str) -> Result<Uuid, BrahmandError> {
+    Uuid::parse_str(job_id).map_err(|_| BrahmandError::JobNotFound(job_id.to_string()))
+}
+
+// Job ids are validated uuids, so the table name is safe to use in queries as is.
+fn results_table_name(job_id: &Uuid) -> String {
+    format!("{}{}", RESULTS_TABLE_PREFIX, job_id.simple())
+}
+
+fn build_create_results_query(results_table: &str, ch_query: &str, ttl_hours: u64) -> String {
+    // _row keeps the order of the result for paging
+    format!(
+        "CREATE TABLE {results_table} ENGINE = MergeTree ORDER BY _row \
+         TTL _created_at + INTERVAL {ttl_hours} HOUR \
+         AS SELECT rowNumberInAllBlocks() AS _row, now() AS _created_at, * FROM ({ch_query})"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clickhouse::test::{Mock, handlers};
+
+    use super::*;
+
+    #[test]
+    fn test_results_table_name() {
+        let job_id = parse_job_id("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+        assert_eq!(
+            results_table_name(&job_id),
+            "brahmand_job_67e5504410b1426f9247bb680e5fe0c8"
+        );
+        assert!(matches!(
+            parse_job_id("x; DROP TABLE users"),
+            Err(BrahmandError::JobNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_page_limit() {
+        let page = |limit| JobResultsPage {
+            offset: None,
+            limit,
+        };
+        assert_eq!(page_limit(&page(None)).unwrap(), DEFAULT_PAGE_SIZE);
+        assert_eq!(page_limit(&page(Some(10))).unwrap(), 10);
+        assert_eq!(page_limit(&page(Some(100_000_000))).unwrap(), MAX_PAGE_SIZE);
+        assert!(matches!(
+            page_limit(&page(Some(0))),
+            Err(BrahmandError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_results_query() {
+        let create_query = build_create_results_query("brahmand_job_1", "SELECT name FROM User", 6);
+        assert_eq!(
+            create_query,
+            "CREATE TABLE brahmand_job_1 ENGINE = MergeTree ORDER BY _row \
+             TTL _created_at + INTERVAL 6 HOUR \
+             AS SELECT rowNumberInAllBlocks() AS _row, now() AS _created_at, * FROM (SELECT name FROM User)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_drop_expired_results_tables() {
+        let mock = Mock::new();
+        let results_table = "brahmand_job_67e5504410b1426f9247bb680e5fe0c8";
+        mock.add(handlers::provide(vec![results_table.to_string()]));
+        let drop_query = mock.add(handlers::record_ddl());
+
+        let clickhouse_client = Client::default().with_url(mock.url());
+        let dropped = drop_expired_results_tables(&clickhouse_client, 24)
+            .await
+            .unwrap();
+        assert_eq!(dropped, 1);
+        assert_eq!(
+            drop_query.query().await,
+            format!("DROP TABLE IF EXISTS {}", results_table)
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use clickhouse::Client;
+use serde_json::{Value, json};
+use tokio::{signal, time::sleep};
+use tracing::{info, warn};
+
+use super::{
+    AppState, errors::BrahmandError, graph_catalog, models::ReadinessResponse,
+    query_registry::QueryRegistry,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const READINESS_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Set once SIGTERM or Ctrl+C is received, new queries are refused from then on.
+#[derive(Debug, Default)]
+pub struct Lifecycle {
+    shutting_down: AtomicBool,
+}
+
+impl Lifecycle {
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::Relaxed)
+    }
+
+    // Queries need the graph schema, which is loaded in the background at startup.
+    pub fn check_ready(&self) -> Result<(), BrahmandError> {
+        if self.is_shutting_down() {
+            return Err(BrahmandError::Unavailable(
+                "brahmand is shutting down.".to_string(),
+            ));
+        }
+        if !graph_catalog::is_schema_loaded() {
+            return Err(BrahmandError::Unavailable(
+                "The graph schema is not loaded yet.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// GET /health, the process is up. It does not depend on ClickHouse so that a ClickHouse outage
+// does not get brahmand restarted.
+pub async fn health_handler() -> Json<Value> {
+    Json(json!({"status": "ok"}))
+}
+
+// GET /ready, queries can be served: ClickHouse and at least one replica are reachable, the
+// schema is loaded and brahmand is not shutting down.
+pub async fn ready_handler(
+    State(app_state): State<Arc<AppState>>,
+) -> (StatusCode, Json<ReadinessResponse>) {
+    let clickhouse_error =
+        match tokio::time::timeout(READINESS_TIMEOUT, ping(&app_state.clickhouse_client)).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some("ClickHouse did not answer in time".to_string()),
+        };
+
+    let healthy_replicas = app_state.clickhouse_pool.healthy_replicas();
+    let readiness = ReadinessResponse {
+        ready: clickhouse_error.is_none()
+            && healthy_replicas > 0
+            && app_state.lifecycle.check_ready().is_ok(),
+        schema_loaded: graph_catalog::is_schema_loaded(),
+        clickhouse_reachable: clickhouse_error.is_none(),
+        healthy_replicas,
+        shutting_down: app_state.lifecycle.is_shutting_down(),
+        clickhouse_error,
+    };
+    let status_code = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status_code, Json(readiness))
+}
+
+async fn ping(clickhouse_client: &Client) -> Result<(), clickhouse::error::Error> {
+    clickhouse_client
+        .query("SELECT 1")
+        .fetch_one::<u8>()
+        .await
+        .map(|_| ())
+}
+
+// Middleware of the query routes.
+pub async fn require_ready(
+    State(app_state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, BrahmandError> {
+    app_state.lifecycle.check_ready()?;
+    Ok(next.run(request).await)
+}
+
+// Runs `operation` until it succeeds, waiting longer after each failure. Used at startup when
+// ClickHouse may not be up yet.
+pub async fn retry_with_backoff<T, E, F, Fut>(description: &str, mut operation: F) -> T
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        match operation().await {
+            Ok(value) => return value,
+            Err(e) => {
+                warn!(
+                    "Unable to {}, retrying in {:?}: {}",
+                    description, backoff, e
+                );
+                sleep(backoff).await;
+                backoff = next_backoff(backoff);
+            }
+        }
+    }
+}
+
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(MAX_BACKOFF)
+}
+
+// Resolves on SIGTERM or Ctrl+C.
+pub async fn shutdown_signal(lifecycle: Arc<Lifecycle>) {
+    let ctrl_c = async {
+        signal::ctrl_c().await.expect("Unable to listen for Ctrl+C");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Unable to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutting down, waiting for running queries to finish");
+    lifecycle.shutting_down.store(true, Ordering::Relaxed);
+}
+
+// Bolt connections are not tracked by the HTTP server, their queries are waited for here.
+pub async fn wait_for_running_queries(query_registry: &QueryRegistry) {
+    while !query_registry.is_empty() {
+        sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_readiness() {
+        assert_eq!(next_backoff(INITIAL_BACKOFF), Duration::from_secs(1));
+        assert_eq!(next_backoff(Duration::from_secs(20)), MAX_BACKOFF);
+
+        let lifecycle = Lifecycle::default();
+        lifecycle.shutting_down.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            lifecycle.check_ready(),
+            Err(BrahmandError::Unavailable(_))
+        ));
+    }
+}
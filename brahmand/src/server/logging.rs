@@ -0,0 +1,152 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{Instrument, field, info_span};
+use tracing_subscriber::{EnvFilter, fmt::format::FmtSpan};
+use uuid::Uuid;
+
+use super::config::{ConfigError, LogFormat, LoggingConfig};
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+static REDACT_SQL: AtomicBool = AtomicBool::new(false);
+
+// Spans are logged when they close, with their duration. Requests are logged at info, the query
+// stages and the analyzer and optimizer passes at debug.
+pub fn init(config: &LoggingConfig) -> Result<(), ConfigError> {
+    let filter = EnvFilter::try_new(&config.filter).map_err(|e| {
+        ConfigError::Invalid(vec![format!("logging.filter `{}`: {e}", config.filter)])
+    })?;
+    REDACT_SQL.store(config.redact_sql, Ordering::Relaxed);
+
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE);
+    match config.format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
+    Ok(())
+}
+
+// Generated SQL as it should be logged.
+pub fn sql_for_log(sql: &str) -> String {
+    if REDACT_SQL.load(Ordering::Relaxed) {
+        redact_literals(sql)
+    } else {
+        sql.to_string()
+    }
+}
+
+// Replaces string and number literals of SQL with `?`. Quoted identifiers are kept.
+pub fn redact_literals(sql: &str) -> String {
+    replace_literals(sql, &['\''])
+}
+
+// Strings are quoted with one of `string_quotes`, the other quotes of ` and " are identifiers.
+// Parameters e.g. `$1` are kept.
+pub fn replace_literals(text: &str, string_quotes: &[char]) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            quote if string_quotes.contains(&quote) => {
+                // doubled quotes and \ escape quotes
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        c if c == quote && chars.peek() == Some(&quote) => {
+                            chars.next();
+                        }
+                        c if c == quote => break,
+                        _ => {}
+                    }
+                }
+                redacted.push('?');
+            }
+            '`' | '"' => {
+                redacted.push(c);
+                for quoted in chars.by_ref() {
+                    redacted.push(quoted);
+                    if quoted == c {
+                        break;
+                    }
+                }
+            }
+            // digits of identifiers e.g. `u1.user_id` are not literals
+            '0'..='9'
+                if !redacted
+                    .chars()
+                    .next_back()
+                    .is_some_and(|prev| prev.is_alphanumeric() || prev == '_' || prev == '$') =>
+            {
+                while chars
+                    .peek()
+                    .is_some_and(|next| next.is_ascii_alphanumeric() || *next == '.')
+                {
+                    chars.next();
+                }
+                redacted.push('?');
+            }
+            _ => redacted.push(c),
+        }
+    }
+    redacted
+}
+
+// Middleware of every HTTP route. Logs of a request carry its id, which is taken from the
+// X-Request-Id header or generated, and sent back in the response.
+pub async fn trace_request(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+        status = field::Empty,
+    );
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_literals() {
+        assert_eq!(
+            redact_literals(
+                "SELECT u1.name FROM User AS u1 WHERE u1.name = 'O''Brien' AND u1.age > 30.5 LIMIT 10"
+            ),
+            "SELECT u1.name FROM User AS u1 WHERE u1.name = ? AND u1.age > ? LIMIT ?"
+        );
+        assert_eq!(
+            redact_literals(r#"SELECT `col 1`, "t2".x FROM t2 WHERE s IN ('a\'b', 'c')"#),
+            r#"SELECT `col 1`, "t2".x FROM t2 WHERE s IN (?, ?)"#
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{sync::LazyLock, time::Instant};
+
+use axum::{
+    http::{HeaderValue, header},
+    response::{IntoResponse, Response},
+};
+use prometheus_client::{
+    encoding::{EncodeLabelSet, text::encode},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        gauge::Gauge,
+        histogram::{Histogram, exponential_buckets},
+    },
+    registry::Registry,
+};
+
+use crate::query_planner::types::QueryType;
+
+use super::errors::{BrahmandError, ErrorStage};
+
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct QueryTypeLabels {
+    query_type: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct StageLabels {
+    stage: &'static str,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ReplicaLabels {
+    replica: String,
+}
+
+// Metrics of the query pipeline exposed on GET /metrics.
+pub struct Metrics {
+    registry: Registry,
+    queries: Family<QueryTypeLabels, Counter>,
+    stage_durations: Family<StageLabels, Histogram>,
+    errors: Family<StageLabels, Counter>,
+    schema_version: Gauge,
+    schema_refresh_failures: Counter,
+    in_flight_queries: Gauge,
+    replica_healthy: Family<ReplicaLabels, Gauge>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let mut registry = Registry::with_prefix("brahmand");
+
+        let queries = Family::<QueryTypeLabels, Counter>::default();
+        registry.register("queries", "Queries received by type", queries.clone());
+
+        // 1ms to ~65s
+        let stage_durations = Family::<StageLabels, Histogram>::new_with_constructor(|| {
+            Histogram::new(exponential_buckets(0.001, 2.0, 17))
+        });
+        registry.register(
+            "query_stage_duration_seconds",
+            "Time spent in each stage of a query: parse, plan, render and execute",
+            stage_durations.clone(),
+        );
+
+        let errors = Family::<StageLabels, Counter>::default();
+        registry.register("query_errors", "Failed queries by stage", errors.clone());
+
+        let schema_version = Gauge::default();
+        registry.register(
+            "schema_version",
+            "Version of the graph schema held in memory",
+            schema_version.clone(),
+        );
+
+        let schema_refresh_failures = Counter::default();
+        registry.register(
+            "schema_refresh_failures",
+            "Failed checks of the graph schema in ClickHouse",
+            schema_refresh_failures.clone(),
+        );
+
+        let in_flight_queries = Gauge::default();
+        registry.register(
+            "in_flight_queries",
+            "Read queries currently running on ClickHouse",
+            in_flight_queries.clone(),
+        );
+
+        let replica_healthy = Family::<ReplicaLabels, Gauge>::default();
+        registry.register(
+            "clickhouse_replica_healthy",
+            "1 when the ClickHouse replica receives reads, 0 when it is out of rotation",
+            replica_healthy.clone(),
+        );
+
+        Metrics {
+            registry,
+            queries,
+            stage_durations,
+            errors,
+            schema_version,
+            schema_refresh_failures,
+            in_flight_queries,
+            replica_healthy,
+        }
+    }
+
+    pub fn record_query(&self, query_type: &QueryType) {
+        let query_type = match query_type {
+            QueryType::Ddl => "ddl",
+            QueryType::Read => "read",
+            QueryType::Update => "update",
+            QueryType::Delete => "delete",
+        };
+        self.queries
+            .get_or_create(&QueryTypeLabels { query_type })
+            .inc();
+    }
+
+    pub fn observe_stage(&self, stage: ErrorStage, started_at: Instant) {
+        self.stage_durations
+            .get_or_create(&StageLabels {
+                stage: stage.as_str(),
+            })
+            .observe(started_at.elapsed().as_secs_f64());
+    }
+
+    pub fn record_error(&self, error: &BrahmandError) {
+        self.errors
+            .get_or_create(&StageLabels {
+                stage: error.stage().as_str(),
+            })
+            .inc();
+    }
+
+    pub fn set_schema_version(&self, version: u32) {
+        self.schema_version.set(version as i64);
+    }
+
+    pub fn record_schema_refresh_failure(&self) {
+        self.schema_refresh_failures.inc();
+    }
+
+    pub fn query_started(&self) {
+        self.in_flight_queries.inc();
+    }
+
+    pub fn query_finished(&self, started_at: Instant) {
+        self.in_flight_queries.dec();
+        self.observe_stage(ErrorStage::Execute, started_at);
+    }
+
+    pub fn set_replica_healthy(&self, replica: &str, is_healthy: bool) {
+        self.replica_healthy
+            .get_or_create(&ReplicaLabels {
+                replica: replica.to_string(),
+            })
+            .set(is_healthy as i64);
+    }
+
+    fn encode(&self) -> String {
+        let mut buffer = String::new();
+        // writing to a String can not fail
+        encode(&mut buffer, &self.registry).unwrap();
+        buffer
+    }
+}
+
+pub async fn metrics_handler() -> Response {
+    let mut response = METRICS.encode().into_response();
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(OPENMETRICS_CONTENT_TYPE),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let metrics = Metrics::new();
+        metrics.record_query(&QueryType::Read);
+        metrics.observe_stage(ErrorStage::Parse, Instant::now());
+        metrics.record_error(&BrahmandError::Unsupported("x".to_string()));
+        metrics.set_schema_version(3);
+        metrics.query_started();
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("brahmand_queries_total{query_type=\"read\"} 1"));
+        assert!(encoded.contains("brahmand_query_stage_duration_seconds_count{stage=\"parse\"} 1"));
+        assert!(encoded.contains("brahmand_query_errors_total{stage=\"plan\"} 1"));
+        assert!(encoded.contains("brahmand_schema_version 3"));
+        assert!(encoded.contains("brahmand_in_flight_queries 1"));
+        assert!(encoded.ends_with("# EOF\n"));
+    }
+}
@@ -1,61 +1,257 @@
-use std::{env, sync::Arc};
+use std::{sync::Arc, time::Duration};
 
-use axum::{Router, routing::post};
+use auth::{Authenticator, Identity, access_control::AccessControl};
+use axum::{
+    Router, middleware,
+    routing::{get, post},
+};
 use clickhouse::Client;
-use handlers::query_handler;
+use clickhouse_pool::ClickHousePool;
+use config::{Config, ConfigError};
+use futures_util::FutureExt;
+use handlers::{cancel_query_handler, query_handler, running_queries_handler};
+use jobs::{job_results_handler, job_status_handler, submit_job_handler};
+use lifecycle::{Lifecycle, health_handler, ready_handler};
+use metrics::metrics_handler;
+use models::OutputFormat;
+use neo4j_http::tx_commit_handler;
+use query_limits::QueryLimits;
+use query_log::{QueryLog, QueryLogRecord};
+use query_registry::QueryRegistry;
 
 use dotenv::dotenv;
 use tokio::sync::{OnceCell, RwLock};
+use tracing::{debug, error, info, warn};
 
-use crate::graph_catalog::graph_schema::GraphSchema;
+use crate::graph_catalog::{graph_permissions::GraphPermissions, graph_schema::GraphSchema};
 
+mod auth;
+mod bolt;
 mod clickhouse_client;
+mod clickhouse_pool;
+mod config;
+mod errors;
+mod explain;
 mod graph_catalog;
+mod graph_output;
 mod handlers;
+mod jobs;
+mod lifecycle;
+mod logging;
+mod metrics;
 mod models;
+mod neo4j_http;
+mod query_limits;
+mod query_log;
+mod query_registry;
 
 // #[derive(Clone)]
 struct AppState {
+    // DDL, writes, jobs and the graph catalog
     clickhouse_client: Client,
+    // replicas of the read queries
+    clickhouse_pool: Arc<ClickHousePool>,
+    // server wide ClickHouse settings, requests can only lower the limits among them
+    clickhouse_settings: Vec<(String, String)>,
+    query_limits: QueryLimits,
+    query_registry: Arc<QueryRegistry>,
+    job_results_ttl_hours: u64,
+    // of /query requests without a format
+    default_format: OutputFormat,
+    authenticator: Authenticator,
+    access_control: Option<AccessControl>,
+    // can see and cancel the queries and jobs of every user
+    admin_role: String,
+    query_log: Option<Arc<QueryLog>>,
+    lifecycle: Arc<Lifecycle>,
+}
+
+impl AppState {
+    // None when access control is not configured, every label can be read then.
+    fn graph_permissions(&self, identity: &Identity) -> Option<GraphPermissions> {
+        self.access_control
+            .as_ref()
+            .map(|access_control| access_control.permissions(identity))
+    }
+
+    fn is_admin(&self, identity: &Identity) -> bool {
+        match &self.access_control {
+            Some(access_control) => access_control
+                .roles(identity)
+                .any(|role| *role == self.admin_role),
+            None => identity.roles.contains(&self.admin_role),
+        }
+    }
+
+    // Without access control every caller can change the graph schema.
+    fn can_run_ddl(&self, identity: &Identity) -> bool {
+        self.access_control
+            .as_ref()
+            .is_none_or(|access_control| access_control.can_run_ddl(identity, &self.admin_role))
+    }
+
+    // Running queries and jobs can only be seen and cancelled by the user who started them and
+    // by admins.
+    fn can_access(&self, identity: &Identity, user: &str) -> bool {
+        identity.user == user || self.is_admin(identity)
+    }
+
+    fn start_query_log(&self, identity: &Identity, query: &str) -> QueryLogRecord {
+        self.query_log
+            .as_ref()
+            .map(|query_log| query_log.start(identity, query))
+            .unwrap_or_default()
+    }
 }
 
 pub static GLOBAL_GRAPH_SCHEMA: OnceCell<RwLock<GraphSchema>> = OnceCell::const_new();
 
 pub async fn run() {
     dotenv().ok();
+    let config = Config::load().unwrap_or_else(|e| exit_with_error(e));
+    logging::init(&config.logging).unwrap_or_else(|e| exit_with_error(e));
+    info!("brahmandDB v{}", env!("CARGO_PKG_VERSION"));
+
     // Create and configure the ClickHouse client.
-    let client = clickhouse_client::get_client();
+    let client =
+        clickhouse_client::get_client(&config.clickhouse).unwrap_or_else(|e| exit_with_error(e));
 
-    let app_state = AppState {
+    let app_state = Arc::new(AppState {
         clickhouse_client: client.clone(),
-    };
-
-    graph_catalog::initialize_global_schema(client.clone()).await;
-
-    println!("GLOBAL_GRAPH_SCHEMA {:?}", GLOBAL_GRAPH_SCHEMA.get());
+        clickhouse_pool: Arc::new(ClickHousePool::new(
+            &config.clickhouse.url,
+            &config.clickhouse.replicas,
+            config.clickhouse.load_balancing,
+        )),
+        clickhouse_settings: config.clickhouse.settings.clone(),
+        query_limits: config.limits.clone(),
+        query_registry: Arc::new(QueryRegistry::default()),
+        job_results_ttl_hours: config.server.job_results_ttl_hours,
+        default_format: config.server.default_format.clone(),
+        authenticator: Authenticator::from_config(&config.auth, &client)
+            .unwrap_or_else(|e| exit_with_error(e)),
+        access_control: config
+            .auth
+            .acl_file
+            .as_deref()
+            .map(AccessControl::from_file)
+            .transpose()
+            .unwrap_or_else(|e| exit_with_error(e)),
+        admin_role: config.auth.admin_role.clone(),
+        query_log: config
+            .logging
+            .query_log_table
+            .as_deref()
+            .map(|table| QueryLog::new(&client, table)),
+        lifecycle: Arc::new(Lifecycle::default()),
+    });
 
-    // Spawn the background task to monitor schema updates.
+    let clickhouse_pool = app_state.clickhouse_pool.clone();
+    tokio::spawn(jobs::drop_expired_results(
+        client.clone(),
+        app_state.job_results_ttl_hours,
+    ));
     tokio::spawn(async move {
-        if let Err(e) = graph_catalog::monitor_schema_updates(client).await {
-            eprintln!("Error in schema monitor: {}", e);
-        }
+        clickhouse_pool
+            .run_health_checks(client, config.clickhouse.health_check_interval)
+            .await
     });
 
+    // ClickHouse may not be up yet, the server starts right away and /ready reports when the
+    // schema is loaded.
+    tokio::spawn(load_schema(
+        app_state.clone(),
+        config.server.schema_refresh_interval,
+    ));
+
     // Build the Axum router, injecting the ClickHouse client as shared state.
     let app = Router::new()
         .route("/query", post(query_handler))
+        .route("/query/{id}/cancel", post(cancel_query_handler))
+        .route("/queries", get(running_queries_handler))
+        .route("/jobs", post(submit_job_handler))
+        .route("/jobs/{id}", get(job_status_handler))
+        .route("/jobs/{id}/results", get(job_results_handler))
+        .route("/db/{name}/tx/commit", post(tx_commit_handler))
         // .route("/ddl", post(ddl_handler))s
-        .with_state(Arc::new(app_state));
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            lifecycle::require_ready,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth::authenticate,
+        ))
+        // probes and scrapes without credentials
+        .route("/health", get(health_handler))
+        .route("/ready", get(ready_handler))
+        .route("/metrics", get(metrics_handler))
+        .layer(middleware::from_fn(logging::trace_request))
+        .with_state(app_state.clone());
+
+    let app_host = &config.server.host;
 
-    let app_host = env::var("BRAHMAND_HOST").unwrap_or("0.0.0.0".to_string());
-    let app_port = env::var("BRAHMAND_PORT").unwrap_or("8080".to_string());
+    // On SIGTERM the listeners stop accepting, new queries are refused and running ones are
+    // given the shutdown timeout to finish.
+    let shutdown = lifecycle::shutdown_signal(app_state.lifecycle.clone()).shared();
 
-    let bind_address = app_host + ":" + &app_port;
-    println!(" Server running on - {}", bind_address);
-    // run our app with hyper, listening globally on port 3000
+    // Bolt listener for Neo4j drivers, it shares the state with the HTTP server.
+    if let Some(bolt_port) = config.server.bolt_port {
+        tokio::spawn(bolt::run_listener(
+            app_state.clone(),
+            format!("{}:{}", app_host, bolt_port),
+            shutdown.clone(),
+        ));
+    }
+
+    let bind_address = format!("{}:{}", app_host, config.server.port);
+    info!("Server running on - {}", bind_address);
     let listener = tokio::net::TcpListener::bind(bind_address).await.unwrap();
-    axum::serve(listener, app)
-        // .with_graceful_shutdown(shutdown_signal())
-        .await
-        .unwrap();
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.clone());
+    let drained = async {
+        server.await.unwrap();
+        lifecycle::wait_for_running_queries(&app_state.query_registry).await;
+    };
+    let shutdown_timeout = async {
+        shutdown.await;
+        tokio::time::sleep(config.server.shutdown_timeout).await;
+    };
+    tokio::select! {
+        _ = drained => info!("Shutdown complete"),
+        _ = shutdown_timeout => warn!(
+            "Shutdown timeout reached with {} queries running",
+            app_state.query_registry.get_running_queries().len()
+        ),
+    }
+}
+
+async fn load_schema(app_state: Arc<AppState>, schema_refresh_interval: Duration) {
+    let client = app_state.clickhouse_client.clone();
+    graph_catalog::initialize_global_schema(client.clone()).await;
+    if let Some(query_log) = &app_state.query_log {
+        lifecycle::retry_with_backoff("create the query log table", || query_log.create_table())
+            .await;
+    }
+    lifecycle::retry_with_backoff("create the jobs table", || {
+        jobs::create_jobs_table(&client, app_state.job_results_ttl_hours)
+    })
+    .await;
+    let graph_schema = graph_catalog::get_graph_schema().await;
+    debug!(
+        version = graph_schema.get_version(),
+        node_labels = graph_schema.get_nodes_schemas().len(),
+        relationship_types = graph_schema.get_relationships_schemas().len(),
+        "Graph schema loaded"
+    );
+    info!("Ready to serve queries");
+
+    if let Err(e) = graph_catalog::monitor_schema_updates(client, schema_refresh_interval).await {
+        error!("Error in schema monitor: {}", e);
+    }
+}
+
+// Configuration problems are reported before logging is set up.
+fn exit_with_error(error: ConfigError) -> ! {
+    eprintln!("{error}");
+    std::process::exit(1);
 }
@@ -1,10 +1,21 @@
+use std::{collections::HashMap, str::FromStr};
+
 use clickhouse::Row;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::query_planner::plan_ctx::PlanSnapshot;
 
 #[derive(Debug, Deserialize)]
 pub struct QueryRequest {
     pub query: String,
     pub format: Option<OutputFormat>,
+    // ClickHouse settings for this query only, e.g. {"max_execution_time": 30}. Limits can only
+    // be lowered, see `clickhouse_client::with_request_settings`
+    pub settings: Option<HashMap<String, Value>>,
+    // id to cancel the query with. The ClickHouse query_id is always generated by brahmand and
+    // returned in the X-Query-Id header
+    pub query_id: Option<String>,
 }
 
 // #[derive(Debug, Serialize)]
@@ -24,6 +35,8 @@ pub enum OutputFormat {
     PrettyCompact,
     Csv,
     CSVWithNames,
+    // nodes and relationships from the result, de-duplicated into {"nodes": [...], "edges": [...]}
+    Graph,
 }
 
 impl From<OutputFormat> for String {
@@ -34,10 +47,62 @@ impl From<OutputFormat> for String {
             OutputFormat::PrettyCompact => "PrettyCompact".to_string(),
             OutputFormat::Csv => "CSV".to_string(),
             OutputFormat::CSVWithNames => "CSVWithNames".to_string(),
+            OutputFormat::Graph => "JSONEachRow".to_string(),
+        }
+    }
+}
+
+// Names as in the `format` of a request.
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "JSONEachRow" => Ok(OutputFormat::JSONEachRow),
+            "Pretty" => Ok(OutputFormat::Pretty),
+            "PrettyCompact" => Ok(OutputFormat::PrettyCompact),
+            "Csv" => Ok(OutputFormat::Csv),
+            "CSVWithNames" => Ok(OutputFormat::CSVWithNames),
+            "Graph" => Ok(OutputFormat::Graph),
+            _ => Err(format!(
+                "unknown format `{s}`, expected JSONEachRow, Pretty, PrettyCompact, Csv, CSVWithNames or Graph"
+            )),
         }
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct ExplainResponse {
+    pub passes: Vec<PlanSnapshot>,
+    pub render_plan: String,
+    pub sql: String,
+    pub clickhouse_plan: Vec<String>,
+    pub clickhouse_pipeline: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileResponse {
+    pub passes: Vec<PlanSnapshot>,
+    pub render_plan: String,
+    pub sql: String,
+    // rows are counted, not returned
+    pub result_rows: u64,
+    pub elapsed_ms: u64,
+    // None when ClickHouse reported no progress
+    pub query_stats: Option<QueryStats>,
+}
+
+// Progress of a finished query, as in the X-ClickHouse-Summary header.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct QueryStats {
+    pub read_rows: u64,
+    pub read_bytes: u64,
+    pub total_rows_to_read: u64,
+    pub elapsed_ns: u64,
+    // peak memory of the query in bytes, None when ClickHouse does not report it
+    pub peak_memory_usage: Option<u64>,
+}
+
 #[derive(Debug, Row, Serialize, Deserialize)]
 pub struct GraphCatalog {
     pub id: u64,
@@ -57,3 +122,119 @@ pub struct GraphCatalog {
 //     pub from_node: String,
 //     pub to_node: String
 // }
+
+#[derive(Debug, Serialize)]
+pub struct CancelQueryResponse {
+    pub query_id: String,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub schema_loaded: bool,
+    pub clickhouse_reachable: bool,
+    // replicas receiving reads
+    pub healthy_replicas: usize,
+    pub shutting_down: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clickhouse_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobRequest {
+    pub query: String,
+    pub settings: Option<HashMap<String, Value>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobSubmitResponse {
+    pub job_id: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Row, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub read_rows: u64,
+    pub total_rows_approx: u64,
+    pub elapsed: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobStatusResponse {
+    pub job_id: String,
+    pub status: JobStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<JobProgress>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_rows: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobResultsPage {
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JobResultsResponse {
+    pub job_id: String,
+    pub offset: u64,
+    pub limit: u64,
+    pub total_rows: u64,
+    pub rows: Vec<Value>,
+}
+
+// Request and response of the Neo4j HTTP transactional API
+#[derive(Debug, Deserialize)]
+pub struct Neo4jTxRequest {
+    #[serde(default)]
+    pub statements: Vec<Neo4jStatement>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Neo4jStatement {
+    pub statement: String,
+    #[serde(default)]
+    pub parameters: serde_json::Map<String, Value>,
+    // "row" and/or "graph", only "row" when not given
+    pub result_data_contents: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Neo4jTxResponse {
+    pub results: Vec<Neo4jResult>,
+    pub errors: Vec<Neo4jError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Neo4jResult {
+    pub columns: Vec<String>,
+    pub data: Vec<Neo4jData>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Neo4jData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Vec<Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Neo4jError {
+    pub code: String,
+    pub message: String,
+}
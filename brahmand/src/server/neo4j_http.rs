@@ -0,0 +1,273 @@
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+};
+use serde_json::{Map, Value, json};
+
+use crate::graph_catalog::graph_schema::GraphSchema;
+
+use super::{
+    AppState,
+    auth::Identity,
+    errors::BrahmandError,
+    graph_output::{edge_keys, is_edge, is_node, legacy_id, node_key},
+    handlers,
+    metrics::METRICS,
+    models::{Neo4jData, Neo4jError, Neo4jResult, Neo4jStatement, Neo4jTxRequest, Neo4jTxResponse},
+    query_log::QueryLogRecord,
+};
+
+// POST /db/{name}/tx/commit of the Neo4j HTTP API. Statements run one after the other and the
+// first failing one stops the rest. Like Neo4j, errors are reported in the body with a 200 status.
+// brahmand serves a single graph, so the database name is not used.
+pub async fn tx_commit_handler(
+    State(app_state): State<Arc<AppState>>,
+    Extension(identity): Extension<Identity>,
+    Path(_database): Path<String>,
+    Json(payload): Json<Neo4jTxRequest>,
+) -> Json<Neo4jTxResponse> {
+    let mut results = vec![];
+    let mut errors = vec![];
+
+    for statement in payload.statements {
+        let query_log = app_state.start_query_log(&identity, &statement.statement);
+        match run_statement(&app_state, &identity, statement, &query_log).await {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                METRICS.record_error(&e);
+                query_log.set_error(&e);
+                errors.push(Neo4jError {
+                    code: e.neo4j_code().to_string(),
+                    message: e.to_string(),
+                });
+                break;
+            }
+        }
+    }
+
+    Json(Neo4jTxResponse { results, errors })
+}
+
+async fn run_statement(
+    app_state: &AppState,
+    identity: &Identity,
+    statement: Neo4jStatement,
+    query_log: &QueryLogRecord,
+) -> Result<Neo4jResult, BrahmandError> {
+    let mut read = handlers::start_read(
+        app_state,
+        identity,
+        statement.statement,
+        &statement.parameters,
+        query_log,
+    )
+    .await?;
+
+    let result_data_contents = statement
+        .result_data_contents
+        .unwrap_or_else(|| vec!["row".to_string()]);
+    let include_row = result_data_contents.iter().any(|content| content == "row");
+    let include_graph = result_data_contents
+        .iter()
+        .any(|content| content == "graph");
+
+    let mut data = vec![];
+    while let Some(row) = read.next_row().await? {
+        data.push(Neo4jData {
+            row: include_row.then(|| row.iter().map(row_value).collect()),
+            meta: include_row.then(|| {
+                row.iter()
+                    .map(|value| meta_value(value, &read.graph_schema))
+                    .collect()
+            }),
+            graph: include_graph.then(|| graph_value(&row, &read.graph_schema)),
+        });
+    }
+    let columns = std::mem::take(&mut read.columns);
+    read.finish();
+
+    Ok(Neo4jResult { columns, data })
+}
+
+// Paths are projected as [[nodes...], [relationships...]], Neo4j lists them as
+// [node, relationship, node, ...].
+fn path_entities(value: &Value) -> Option<Vec<&Value>> {
+    let [Value::Array(nodes), Value::Array(rels)] = value.as_array()?.as_slice() else {
+        return None;
+    };
+    let is_path = !nodes.is_empty()
+        && nodes.len() == rels.len() + 1
+        && nodes
+            .iter()
+            .all(|node| node.as_object().is_some_and(is_node))
+        && rels.iter().all(|rel| rel.as_object().is_some_and(is_edge));
+    if !is_path {
+        return None;
+    }
+
+    let mut entities = vec![&nodes[0]];
+    for (rel, node) in rels.iter().zip(&nodes[1..]) {
+        entities.push(rel);
+        entities.push(node);
+    }
+    Some(entities)
+}
+
+// Entities are represented by their properties in `row`, their identity goes to `meta`.
+fn row_value(value: &Value) -> Value {
+    if let Some(entities) = path_entities(value) {
+        return Value::Array(entities.into_iter().map(row_value).collect());
+    }
+    match value {
+        Value::Object(object) if is_node(object) || is_edge(object) => object["properties"].clone(),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.clone(), row_value(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(row_value).collect()),
+        _ => value.clone(),
+    }
+}
+
+fn meta_value(value: &Value, graph_schema: &GraphSchema) -> Value {
+    if let Some(entities) = path_entities(value) {
+        return Value::Array(
+            entities
+                .into_iter()
+                .map(|entity| meta_value(entity, graph_schema))
+                .collect(),
+        );
+    }
+    match value {
+        Value::Object(object) if is_node(object) => {
+            let element_id = entity_node_key(object);
+            json!({"id": legacy_id(&element_id), "elementId": element_id, "type": "node", "deleted": false})
+        }
+        Value::Object(object) if is_edge(object) => {
+            let (element_id, _, _) = edge_keys(object, graph_schema);
+            json!({"id": legacy_id(&element_id), "elementId": element_id, "type": "relationship", "deleted": false})
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| meta_value(item, graph_schema))
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+// Every node and relationship of the row, nested ones included.
+fn graph_value(row: &[Value], graph_schema: &GraphSchema) -> Value {
+    let mut nodes: Vec<Value> = vec![];
+    let mut relationships: Vec<Value> = vec![];
+    let mut stack: Vec<&Value> = row.iter().collect();
+
+    while let Some(value) = stack.pop() {
+        match value {
+            Value::Object(object) if is_node(object) => {
+                let element_id = entity_node_key(object);
+                let node = json!({
+                    "id": legacy_id(&element_id).to_string(),
+                    "elementId": element_id,
+                    "labels": object["labels"],
+                    "properties": object["properties"],
+                });
+                if !nodes.contains(&node) {
+                    nodes.push(node);
+                }
+            }
+            Value::Object(object) if is_edge(object) => {
+                let (element_id, start_element_id, end_element_id) =
+                    edge_keys(object, graph_schema);
+                let relationship = json!({
+                    "id": legacy_id(&element_id).to_string(),
+                    "elementId": element_id,
+                    "type": object["type"],
+                    "startNode": legacy_id(&start_element_id).to_string(),
+                    "endNode": legacy_id(&end_element_id).to_string(),
+                    "startNodeElementId": start_element_id,
+                    "endNodeElementId": end_element_id,
+                    "properties": object["properties"],
+                });
+                if !relationships.contains(&relationship) {
+                    relationships.push(relationship);
+                }
+            }
+            Value::Object(object) => stack.extend(object.values()),
+            Value::Array(items) => stack.extend(items),
+            _ => {}
+        }
+    }
+
+    json!({"nodes": nodes, "relationships": relationships})
+}
+
+fn entity_node_key(object: &Map<String, Value>) -> String {
+    let label = object["labels"][0].as_str().unwrap_or_default();
+    node_key(label, &object["id"])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::graph_catalog::graph_schema::{RelationshipSchema, TableEngineSchema};
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            "FOLLOWS".to_string(),
+            RelationshipSchema {
+                table_name: "FOLLOWS".to_string(),
+                column_names: vec![],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+        GraphSchema::build(1, HashMap::new(), relationships, HashMap::new())
+    }
+
+    #[test]
+    fn test_row_and_meta() {
+        let graph_schema = create_test_graph_schema();
+        let alice = json!({"id": 1, "labels": ["User"], "properties": {"name": "alice"}});
+        let bob = json!({"id": 2, "labels": ["User"], "properties": {"name": "bob"}});
+        let follows = json!({"type": "FOLLOWS", "start_id": 1, "end_id": 2, "properties": {}});
+
+        assert_eq!(row_value(&alice), json!({"name": "alice"}));
+        assert_eq!(row_value(&json!(3)), json!(3));
+        assert_eq!(meta_value(&json!("alice"), &graph_schema), Value::Null);
+        assert_eq!(
+            meta_value(&alice, &graph_schema),
+            json!({"id": legacy_id("User:1"), "elementId": "User:1", "type": "node", "deleted": false})
+        );
+
+        let path = json!([[alice, bob], [follows]]);
+        assert_eq!(
+            row_value(&path),
+            json!([{"name": "alice"}, {}, {"name": "bob"}])
+        );
+        assert!(
+            meta_value(&path, &graph_schema)[1]["elementId"]
+                .as_str()
+                .unwrap()
+                .starts_with("FOLLOWS:User:1:User:2:")
+        );
+
+        let graph = graph_value(&[path, alice], &graph_schema);
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            graph["relationships"][0]["startNode"],
+            json!(legacy_id("User:1").to_string())
+        );
+    }
+}
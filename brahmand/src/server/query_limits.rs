@@ -0,0 +1,208 @@
+use std::time::Duration;
+
+use crate::{
+    query_planner::logical_plan::LogicalPlan,
+    render_plan::{RenderPlan, UnionItems},
+};
+
+use super::errors::BrahmandError;
+
+// Brahmand side guardrails for read queries. ClickHouse side limits like max_execution_time or
+// max_rows_to_read are passed as settings instead.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryLimits {
+    // relationships in the match pattern
+    pub max_hops: Option<usize>,
+    // selects combined with UNION across the whole query, e.g. from undirected relationships
+    pub max_unions: Option<usize>,
+    // LIMIT added to every query, an explicit LIMIT can only lower it
+    pub max_result_rows: Option<i64>,
+    // running queries are killed on ClickHouse once this is reached
+    pub query_timeout: Option<Duration>,
+}
+
+impl QueryLimits {
+    pub fn check_logical_plan(&self, logical_plan: &LogicalPlan) -> Result<(), BrahmandError> {
+        if let Some(max_hops) = self.max_hops {
+            let hops = count_hops(logical_plan);
+            if hops > max_hops {
+                return Err(BrahmandError::LimitExceeded(format!(
+                    "Query has {} hops, at most {} are allowed.",
+                    hops, max_hops
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply_to_render_plan(&self, render_plan: &mut RenderPlan) -> Result<(), BrahmandError> {
+        if let Some(max_unions) = self.max_unions {
+            let unions = count_unions(render_plan);
+            if unions > max_unions {
+                return Err(BrahmandError::LimitExceeded(format!(
+                    "Query expands to {} unions, at most {} are allowed.",
+                    unions, max_unions
+                )));
+            }
+        }
+
+        if let Some(max_result_rows) = self.max_result_rows {
+            let limit = render_plan
+                .limit
+                .0
+                .map_or(max_result_rows, |limit| limit.min(max_result_rows));
+            render_plan.limit.0 = Some(limit);
+        }
+        Ok(())
+    }
+}
+
+fn count_hops(logical_plan: &LogicalPlan) -> usize {
+    match logical_plan {
+        LogicalPlan::Empty | LogicalPlan::Scan(_) => 0,
+        LogicalPlan::GraphRel(graph_rel) => {
+            1 + count_hops(&graph_rel.left)
+                + count_hops(&graph_rel.center)
+                + count_hops(&graph_rel.right)
+        }
+        LogicalPlan::GraphNode(graph_node) => count_hops(&graph_node.input),
+        LogicalPlan::Filter(filter) => count_hops(&filter.input),
+        LogicalPlan::Projection(projection) => count_hops(&projection.input),
+        LogicalPlan::GroupBy(group_by) => count_hops(&group_by.input),
+        LogicalPlan::OrderBy(order_by) => count_hops(&order_by.input),
+        LogicalPlan::Skip(skip) => count_hops(&skip.input),
+        LogicalPlan::Limit(limit) => count_hops(&limit.input),
+        LogicalPlan::Cte(cte) => count_hops(&cte.input),
+        LogicalPlan::GraphJoins(graph_joins) => count_hops(&graph_joins.input),
+        LogicalPlan::Union(union) => union.inputs.iter().map(|input| count_hops(input)).sum(),
+    }
+}
+
+fn count_unions(render_plan: &RenderPlan) -> usize {
+    let cte_unions: usize = render_plan
+        .ctes
+        .0
+        .iter()
+        .map(|cte| count_unions(&cte.cte_plan))
+        .sum();
+    let unions = match &render_plan.union {
+        UnionItems(Some(union)) => {
+            union.input.len().saturating_sub(1)
+                + union.input.iter().map(count_unions).sum::<usize>()
+        }
+        UnionItems(None) => 0,
+    };
+    cte_unions + unions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        graph_catalog::graph_schema::{
+            GraphSchema, NodeIdSchema, NodeSchema, RelationshipSchema, TableEngineSchema,
+        },
+        open_cypher_parser, query_planner,
+        render_plan::plan_builder::RenderPlanBuilder,
+    };
+    use std::collections::HashMap;
+
+    fn create_test_graph_schema() -> GraphSchema {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            "User".to_string(),
+            NodeSchema {
+                table_name: "User".to_string(),
+                column_names: vec!["user_id".to_string(), "name".to_string()],
+                primary_keys: "user_id".to_string(),
+                node_id: NodeIdSchema {
+                    column: "user_id".to_string(),
+                    dtype: "UInt64".to_string(),
+                },
+                engine: TableEngineSchema::default(),
+            },
+        );
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            "FOLLOWS".to_string(),
+            RelationshipSchema {
+                table_name: "FOLLOWS".to_string(),
+                column_names: vec![],
+                from_node: "User".to_string(),
+                to_node: "User".to_string(),
+                from_node_id_dtype: "UInt64".to_string(),
+                to_node_id_dtype: "UInt64".to_string(),
+                engine: TableEngineSchema::default(),
+            },
+        );
+        GraphSchema::build(1, nodes, relationships, HashMap::new())
+    }
+
+    fn plan(query: &str) -> LogicalPlan {
+        let ast = open_cypher_parser::parse_query(query).unwrap();
+        query_planner::evaluate_read_query(ast, &create_test_graph_schema(), None).unwrap()
+    }
+
+    #[test]
+    fn test_max_hops() {
+        let logical_plan =
+            plan("MATCH (a:User)-[:FOLLOWS]->(b:User)-[:FOLLOWS]->(c:User) RETURN c.name;");
+        assert_eq!(count_hops(&logical_plan), 2);
+
+        let limits = QueryLimits {
+            max_hops: Some(1),
+            ..QueryLimits::default()
+        };
+        assert!(matches!(
+            limits.check_logical_plan(&logical_plan),
+            Err(BrahmandError::LimitExceeded(_))
+        ));
+        assert!(
+            QueryLimits::default()
+                .check_logical_plan(&logical_plan)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_max_unions() {
+        let logical_plan = plan("MATCH (a:User)-[:FOLLOWS]-(b:User) RETURN b.name;");
+        let mut render_plan = logical_plan.to_render_plan().unwrap();
+        assert_eq!(count_unions(&render_plan), 1);
+
+        let limits = QueryLimits {
+            max_unions: Some(0),
+            ..QueryLimits::default()
+        };
+        assert!(matches!(
+            limits.apply_to_render_plan(&mut render_plan),
+            Err(BrahmandError::LimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_max_result_rows() {
+        let limits = QueryLimits {
+            max_result_rows: Some(100),
+            ..QueryLimits::default()
+        };
+
+        let mut render_plan = plan("MATCH (a:User) RETURN a.name;")
+            .to_render_plan()
+            .unwrap();
+        limits.apply_to_render_plan(&mut render_plan).unwrap();
+        assert_eq!(render_plan.limit.0, Some(100));
+
+        let mut render_plan = plan("MATCH (a:User) RETURN a.name LIMIT 10;")
+            .to_render_plan()
+            .unwrap();
+        limits.apply_to_render_plan(&mut render_plan).unwrap();
+        assert_eq!(render_plan.limit.0, Some(10));
+
+        let mut render_plan = plan("MATCH (a:User) RETURN a.name LIMIT 1000;")
+            .to_render_plan()
+            .unwrap();
+        limits.apply_to_render_plan(&mut render_plan).unwrap();
+        assert_eq!(render_plan.limit.0, Some(100));
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clickhouse::{Client, Row};
+use serde::Serialize;
+use tracing::error;
+
+use super::{
+    auth::Identity,
+    errors::{BrahmandError, ErrorStage},
+    logging,
+};
+
+// Every Cypher query is recorded to the table of BRAHMAND_QUERY_LOG_TABLE when it is set, e.g.
+// BRAHMAND_QUERY_LOG_TABLE=brahmand_query_log. Entries are joined with system.query_log on
+// query_id for the ClickHouse side of a query.
+pub struct QueryLog {
+    clickhouse_client: Client,
+    table: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Row, Serialize)]
+pub struct QueryLogEntry {
+    // milliseconds since the epoch, stored as DateTime64(3)
+    pub event_time: i64,
+    pub query_id: String,
+    pub user: String,
+    // literals are replaced with `?`, so that queries only differing in values share a fingerprint
+    pub normalized_query: String,
+    pub fingerprint: String,
+    pub sql: String,
+    pub duration_ms: u64,
+    pub rows: u64,
+    // empty for successful queries
+    pub error_stage: String,
+    pub error: String,
+}
+
+impl QueryLog {
+    pub fn new(clickhouse_client: &Client, table: &str) -> Arc<Self> {
+        // entries are batched by ClickHouse instead of creating a part per query
+        let clickhouse_client = clickhouse_client
+            .clone()
+            .with_option("async_insert", "1")
+            .with_option("wait_for_async_insert", "0");
+        Arc::new(QueryLog {
+            clickhouse_client,
+            table: table.to_string(),
+        })
+    }
+
+    pub async fn create_table(&self) -> Result<(), clickhouse::error::Error> {
+        let create_table_query = format!(
+            "CREATE TABLE IF NOT EXISTS {} (
+                event_time DateTime64(3),
+                query_id String,
+                user String,
+                normalized_query String,
+                fingerprint String,
+                sql String,
+                duration_ms UInt64,
+                rows UInt64,
+                error_stage LowCardinality(String),
+                error String
+            ) ENGINE = MergeTree()
+            PARTITION BY toYYYYMM(event_time)
+            ORDER BY (event_time, fingerprint)",
+            self.table
+        );
+        self.clickhouse_client
+            .query(&create_table_query)
+            .execute()
+            .await
+    }
+
+    pub fn start(self: &Arc<Self>, identity: &Identity, query: &str) -> QueryLogRecord {
+        let normalized_query = normalize_query(query);
+        let event_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as i64)
+            .unwrap_or_default();
+        QueryLogRecord(Some(Arc::new(PendingEntry {
+            query_log: self.clone(),
+            started_at: Instant::now(),
+            cancelled: AtomicBool::new(false),
+            entry: Mutex::new(QueryLogEntry {
+                event_time,
+                user: identity.user.clone(),
+                fingerprint: fingerprint(&normalized_query),
+                normalized_query,
+                ..Default::default()
+            }),
+        })))
+    }
+
+    async fn insert(&self, entry: QueryLogEntry) -> Result<(), clickhouse::error::Error> {
+        let mut insert = self.clickhouse_client.insert(&self.table)?;
+        insert.write(&entry).await?;
+        insert.end().await
+    }
+}
+
+// Query being recorded. The entry is written once every clone is dropped, so a streamed result
+// is logged after the whole body is sent. Does nothing when the query log is not configured.
+#[derive(Clone, Default)]
+pub struct QueryLogRecord(Option<Arc<PendingEntry>>);
+
+struct PendingEntry {
+    query_log: Arc<QueryLog>,
+    started_at: Instant,
+    // the running query was killed before it finished
+    cancelled: AtomicBool,
+    entry: Mutex<QueryLogEntry>,
+}
+
+impl QueryLogRecord {
+    pub fn set_query_id(&self, query_id: &str) {
+        self.update(|entry| entry.query_id = query_id.to_string());
+    }
+
+    pub fn set_sql(&self, sql: &str) {
+        self.update(|entry| entry.sql = logging::sql_for_log(sql));
+    }
+
+    pub fn add_rows(&self, rows: u64) {
+        self.update(|entry| entry.rows += rows);
+    }
+
+    // Only the first error is kept, later ones are caused by it.
+    pub fn set_error(&self, error: &BrahmandError) {
+        self.update(|entry| {
+            if entry.error_stage.is_empty() {
+                entry.error_stage = error.stage().as_str().to_string();
+                entry.error = error.to_string();
+            }
+        });
+    }
+
+    pub fn set_cancelled(&self) {
+        if let Some(pending_entry) = &self.0 {
+            pending_entry.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut QueryLogEntry)) {
+        if let Some(pending_entry) = &self.0 {
+            f(&mut pending_entry.entry.lock().unwrap());
+        }
+    }
+}
+
+impl Drop for PendingEntry {
+    fn drop(&mut self) {
+        let mut entry = std::mem::take(self.entry.get_mut().unwrap());
+        entry.duration_ms = self.started_at.elapsed().as_millis() as u64;
+        // errors which caused the cancellation are recorded before the last clone is dropped
+        if self.cancelled.load(Ordering::Relaxed) && entry.error_stage.is_empty() {
+            entry.error_stage = ErrorStage::Execute.as_str().to_string();
+            entry.error = "Query was cancelled.".to_string();
+        }
+
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let query_log = self.query_log.clone();
+            runtime.spawn(async move {
+                if let Err(e) = query_log.insert(entry).await {
+                    error!("Unable to write to the query log: {}", e);
+                }
+            });
+        }
+    }
+}
+
+// Cypher strings can be single or double quoted.
+fn normalize_query(query: &str) -> String {
+    logging::replace_literals(query, &['\'', '"'])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn fingerprint(normalized_query: &str) -> String {
+    let digest = openssl::sha::sha256(normalized_query.as_bytes());
+    digest[..8]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_query() {
+        let query = "MATCH (u:User)\n  WHERE u.name = \"alice\" AND u.age > 30 AND u.id = $id1\nRETURN u.name LIMIT 5";
+        assert_eq!(
+            normalize_query(query),
+            "MATCH (u:User) WHERE u.name = ? AND u.age > ? AND u.id = $id1 RETURN u.name LIMIT ?"
+        );
+        assert_eq!(
+            fingerprint(&normalize_query(query)),
+            fingerprint(&normalize_query(
+                "MATCH (u:User) WHERE u.name = 'bob' AND u.age > 40 AND u.id = $id1 RETURN u.name LIMIT 10"
+            ))
+        );
+        assert_eq!(fingerprint("").len(), 16);
+    }
+
+    #[test]
+    fn test_record() {
+        let query_log = Arc::new(QueryLog {
+            clickhouse_client: Client::default(),
+            table: "brahmand_query_log".to_string(),
+        });
+        let record = query_log.start(&Identity::anonymous(), "MATCH (u:User) RETURN u");
+        record.add_rows(2);
+        record.add_rows(3);
+        record.set_error(&BrahmandError::Unsupported("first".to_string()));
+        record.set_error(&BrahmandError::QueryNotFound("second".to_string()));
+
+        let entry = record.0.as_ref().unwrap().entry.lock().unwrap().clone();
+        assert_eq!(entry.rows, 5);
+        assert_eq!(entry.error_stage, "plan");
+        assert_eq!(entry.error, "first");
+
+        assert!(QueryLogRecord::default().0.is_none());
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clickhouse::Client;
+use futures_util::{Stream, StreamExt, stream};
+use serde::Serialize;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::error;
+
+use super::{
+    clickhouse_pool::ReplicaLease, errors::BrahmandError, metrics::METRICS,
+    query_log::QueryLogRecord,
+};
+
+// Read queries which are currently running on ClickHouse, keyed by query_id. The query_id is
+// always generated by brahmand so that it cannot match a query started by anyone else.
+#[derive(Debug, Default)]
+pub struct QueryRegistry {
+    running_queries: Mutex<HashMap<String, RunningQuery>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningQuery {
+    pub query_id: String,
+    // query_id given by the client, the query can also be cancelled with it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    pub query: String,
+    // authenticated user who started the query, empty without authentication
+    pub user: String,
+    pub started_at_ms: u64,
+    // url of the ClickHouse replica the query runs on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replica: Option<String>,
+}
+
+impl QueryRegistry {
+    // `clickhouse_client` must not carry the query_id option, it is used to kill the query.
+    pub fn register(
+        self: &Arc<Self>,
+        query_id: String,
+        tag: Option<String>,
+        query: String,
+        user: String,
+        clickhouse_client: Client,
+        timeout: Option<Duration>,
+    ) -> Result<RunningQueryGuard, BrahmandError> {
+        let mut running_queries = self.running_queries.lock().unwrap();
+        let tag_is_running = |tag: &String| {
+            running_queries
+                .values()
+                .any(|running_query| running_query.tag.as_ref() == Some(tag))
+        };
+        if let Some(tag) = tag.as_ref().filter(|tag| tag_is_running(tag)) {
+            return Err(BrahmandError::InvalidRequest(format!(
+                "Query with id `{}` is already running.",
+                tag
+            )));
+        }
+
+        let started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default();
+        running_queries.insert(
+            query_id.clone(),
+            RunningQuery {
+                query_id: query_id.clone(),
+                tag,
+                query,
+                user,
+                started_at_ms,
+                replica: None,
+            },
+        );
+
+        // The response body can outlive the handler while it is streamed, so the timeout is
+        // enforced here rather than only around the handler. Whoever reads the result waits on
+        // `timed_out` so the request ends even when ClickHouse does not stop the query.
+        let (timed_out_sender, timed_out) = watch::channel(false);
+        let timeout_task = timeout.map(|timeout| {
+            let registry = self.clone();
+            let clickhouse_client = clickhouse_client.clone();
+            let query_id = query_id.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(timeout).await;
+                timed_out_sender.send_replace(true);
+                if let Err(e) = registry.kill(&clickhouse_client, &query_id).await {
+                    error!("Unable to kill timed out query {}: {}", query_id, e);
+                }
+            })
+        });
+
+        METRICS.query_started();
+        Ok(RunningQueryGuard {
+            registry: self.clone(),
+            query_id,
+            clickhouse_client,
+            timeout_task,
+            timed_out,
+            started_at: Instant::now(),
+            query_log: QueryLogRecord::default(),
+            replica: None,
+            finished: false,
+            failed: false,
+        })
+    }
+
+    pub fn is_running(&self, query_id: &str) -> bool {
+        self.running_queries.lock().unwrap().contains_key(query_id)
+    }
+
+    // Running query with `id` as its query_id or its tag.
+    pub fn find(&self, id: &str) -> Option<RunningQuery> {
+        let running_queries = self.running_queries.lock().unwrap();
+        running_queries
+            .get(id)
+            .or_else(|| {
+                running_queries
+                    .values()
+                    .find(|running_query| running_query.tag.as_deref() == Some(id))
+            })
+            .cloned()
+    }
+
+    // Kills the query on the replica it runs on.
+    pub async fn kill(
+        &self,
+        clickhouse_client: &Client,
+        query_id: &str,
+    ) -> Result<(), clickhouse::error::Error> {
+        kill_query(&self.replica_client(clickhouse_client, query_id), query_id).await
+    }
+
+    fn replica_client(&self, clickhouse_client: &Client, query_id: &str) -> Client {
+        let replica = self
+            .running_queries
+            .lock()
+            .unwrap()
+            .get(query_id)
+            .and_then(|running_query| running_query.replica.clone());
+        match replica {
+            Some(replica) => clickhouse_client.clone().with_url(replica),
+            None => clickhouse_client.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.running_queries.lock().unwrap().is_empty()
+    }
+
+    pub fn get_running_queries(&self) -> Vec<RunningQuery> {
+        let mut running_queries: Vec<RunningQuery> = self
+            .running_queries
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        running_queries.sort_by_key(|running_query| running_query.started_at_ms);
+        running_queries
+    }
+}
+
+// Keeps the query in the registry while the response is being produced. If it is dropped before
+// `finish` is called, i.e. the client disconnected or the request timed out, the query is
+// killed on ClickHouse unless ClickHouse already answered it with an error.
+pub struct RunningQueryGuard {
+    registry: Arc<QueryRegistry>,
+    query_id: String,
+    clickhouse_client: Client,
+    timeout_task: Option<JoinHandle<()>>,
+    timed_out: watch::Receiver<bool>,
+    started_at: Instant,
+    query_log: QueryLogRecord,
+    replica: Option<ReplicaLease>,
+    finished: bool,
+    // ClickHouse refused or failed the query, it is not running there anymore
+    failed: bool,
+}
+
+impl RunningQueryGuard {
+    pub fn query_id(&self) -> &str {
+        &self.query_id
+    }
+
+    // Rows are counted on the record while the result is read.
+    pub fn set_query_log(&mut self, query_log: QueryLogRecord) {
+        query_log.set_query_id(&self.query_id);
+        self.query_log = query_log;
+    }
+
+    // Set by each attempt of `ClickHousePool::read`.
+    pub fn set_replica(&mut self, replica: ReplicaLease) {
+        if let Some(running_query) = self
+            .registry
+            .running_queries
+            .lock()
+            .unwrap()
+            .get_mut(&self.query_id)
+        {
+            running_query.replica = Some(replica.url().to_string());
+        }
+        self.replica = Some(replica);
+    }
+
+    pub fn query_log(&self) -> &QueryLogRecord {
+        &self.query_log
+    }
+
+    // Resolves once the query ran out of time, never if it has no timeout.
+    pub fn timed_out(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut timed_out = self.timed_out.clone();
+        async move {
+            if timed_out.wait_for(|timed_out| *timed_out).await.is_err() {
+                std::future::pending::<()>().await;
+            }
+        }
+    }
+
+    // Ends `chunks` with a timeout error once the query ran out of time, so that a streamed
+    // response is cut off rather than read as a complete one by the client.
+    pub fn abort_on_timeout<S, T>(
+        &self,
+        chunks: S,
+    ) -> impl Stream<Item = Result<T, clickhouse::error::Error>> + Send + 'static
+    where
+        S: Stream<Item = Result<T, clickhouse::error::Error>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let state = Some((Box::pin(chunks), Box::pin(self.timed_out())));
+        stream::unfold(state, |state| async move {
+            let (mut chunks, mut timed_out) = state?;
+            tokio::select! {
+                chunk = chunks.next() => chunk.map(|chunk| (chunk, Some((chunks, timed_out)))),
+                _ = &mut timed_out => Some((Err(clickhouse::error::Error::TimedOut), None)),
+            }
+        })
+    }
+
+    pub fn set_failed(&mut self) {
+        self.failed = true;
+    }
+
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for RunningQueryGuard {
+    fn drop(&mut self) {
+        let clickhouse_client = self
+            .registry
+            .replica_client(&self.clickhouse_client, &self.query_id);
+        self.registry
+            .running_queries
+            .lock()
+            .unwrap()
+            .remove(&self.query_id);
+        if let Some(timeout_task) = &self.timeout_task {
+            timeout_task.abort();
+        }
+        METRICS.query_finished(self.started_at);
+
+        if self.finished || self.failed {
+            return;
+        }
+        self.query_log.set_cancelled();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let query_id = self.query_id.clone();
+            runtime.spawn(async move {
+                if let Err(e) = kill_query(&clickhouse_client, &query_id).await {
+                    error!("Unable to kill query {}: {}", query_id, e);
+                }
+            });
+        }
+    }
+}
+
+pub async fn kill_query(
+    clickhouse_client: &Client,
+    query_id: &str,
+) -> Result<(), clickhouse::error::Error> {
+    clickhouse_client
+        .query("KILL QUERY WHERE query_id = ? ASYNC")
+        .bind(query_id)
+        .execute()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_finish() {
+        let registry = Arc::new(QueryRegistry::default());
+        let guard = registry
+            .register(
+                "q1".to_string(),
+                Some("mine".to_string()),
+                "MATCH (a) RETURN a;".to_string(),
+                "alice".to_string(),
+                Client::default(),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(registry.find("q1").unwrap().user, "alice");
+        assert_eq!(registry.find("mine").unwrap().query_id, "q1");
+        assert_eq!(guard.query_id(), "q1");
+        assert!(
+            registry
+                .register(
+                    "q2".to_string(),
+                    Some("mine".to_string()),
+                    "".to_string(),
+                    "".to_string(),
+                    Client::default(),
+                    None,
+                )
+                .is_err()
+        );
+
+        guard.finish();
+        assert!(registry.find("q1").is_none());
+        assert!(registry.get_running_queries().is_empty());
+    }
+}